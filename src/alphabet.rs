@@ -0,0 +1,106 @@
+use std::fmt::Debug;
+
+const NOT_A_SYMBOL: u8 = u8::MAX;
+
+///
+/// The ordered symbols a machine's rotors are wired for.
+///
+/// Every core type is generic over the alphabet's size `N`, which defaults to the 26 letters of
+/// [`LATIN`]. The symbol table maps between symbols and their contact index in `0..N`. Symbols must
+/// be ASCII.
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Alphabet<const N: usize> {
+    symbols: [char; N],
+    indices: [u8; 128],
+}
+
+impl<const N: usize> Debug for Alphabet<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Alphabet").field(&self.symbols).finish()
+    }
+}
+
+impl<const N: usize> Alphabet<N> {
+    pub const fn new(symbols: [char; N]) -> Self {
+        if N == 0 || N >= NOT_A_SYMBOL as usize {
+            panic!("An alphabet must have between 1 and 254 symbols");
+        }
+
+        let mut indices = [NOT_A_SYMBOL; 128];
+        let mut i = 0;
+        while i < N {
+            let symbol = symbols[i];
+            if !symbol.is_ascii() {
+                panic!("Alphabet symbols must be ASCII");
+            }
+            if indices[symbol as usize] != NOT_A_SYMBOL {
+                panic!("Alphabet symbols must be unique");
+            }
+
+            indices[symbol as usize] = i as u8;
+            i += 1;
+        }
+
+        Self { symbols, indices }
+    }
+
+    pub const fn size(&self) -> usize {
+        N
+    }
+
+    /// Returns the contact index of `symbol`. Lookups are case-sensitive.
+    pub fn index_of(&self, symbol: char) -> Option<usize> {
+        match symbol.is_ascii() {
+            true => match self.indices[symbol as usize] {
+                NOT_A_SYMBOL => None,
+                index => Some(index as usize),
+            },
+            false => None,
+        }
+    }
+
+    /// Returns the symbol at contact `index`. Panics when the index is out of bounds.
+    pub fn symbol(&self, index: usize) -> char {
+        self.symbols[index]
+    }
+
+    pub fn contains(&self, symbol: char) -> bool {
+        self.index_of(symbol).is_some()
+    }
+
+    pub fn symbols(&self) -> &[char; N] {
+        &self.symbols
+    }
+}
+
+/// The uppercase letters A-Z used by every lettered machine.
+pub static LATIN: Alphabet<26> = Alphabet::new([
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+]);
+
+/// The digits of the numeric Enigma Z30, in the order of its keyboard and rotor contacts.
+pub static DIGITS: Alphabet<10> = Alphabet::new(['1', '2', '3', '4', '5', '6', '7', '8', '9', '0']);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alphabet_should_map_symbols_to_indices_and_back() {
+        assert_eq!(LATIN.index_of('A'), Some(0));
+        assert_eq!(LATIN.index_of('z'), None);
+        assert_eq!(DIGITS.index_of('0'), Some(9));
+        assert_eq!(DIGITS.index_of('A'), None);
+        assert!(
+            (0..DIGITS.size()).all(|index| DIGITS.index_of(DIGITS.symbol(index)) == Some(index))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn alphabet_should_panic_on_repeated_symbols() {
+        Alphabet::new(['1', '2', '1']);
+    }
+}
@@ -0,0 +1,40 @@
+pub mod banburismus;
+pub mod known_plaintext;
+pub mod period;
+pub mod rejewski;
+pub mod zygalski;
+
+use crate::consts::{ALPHABET_SIZE, FIRST_LETTER};
+use crate::error::Error;
+use crate::reflectors::Reflector;
+use crate::rotors_controller::RotorsController;
+
+pub use known_plaintext::known_plaintext;
+
+pub(crate) fn letter_index(letter: char) -> usize {
+    (letter as u8 - FIRST_LETTER as u8) as usize
+}
+
+pub(crate) fn index_letter(index: usize) -> char {
+    (index as u8 + FIRST_LETTER as u8) as char
+}
+
+/// Splits an index in `0..26^3` into left, middle and right rotor positions.
+pub(crate) fn position_from_index(index: usize) -> [usize; 3] {
+    [
+        index / (ALPHABET_SIZE * ALPHABET_SIZE),
+        (index / ALPHABET_SIZE) % ALPHABET_SIZE,
+        index % ALPHABET_SIZE,
+    ]
+}
+
+/// Maps a letter through the rotors, the reflector and back, without stepping and without a plugboard.
+pub(crate) fn scramble(
+    controller: &RotorsController,
+    reflector: &Reflector,
+    letter: char,
+) -> Result<char, Error> {
+    let mapped = controller.map_letter(letter)?;
+    let mapped = reflector.map_letter(mapped)?;
+    controller.inverse_map_letter(mapped)
+}
@@ -0,0 +1,429 @@
+use std::fmt::{self, Display};
+
+use crate::alphabet::LATIN;
+use crate::analysis::{index_letter, letter_index};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::Error;
+use crate::rotor::RotorType;
+use crate::rotors;
+
+#[derive(Debug, PartialEq)]
+pub enum BanburismusError {
+    NonAlphabetic,
+    InvalidIndicatorLength,
+}
+
+impl Display for BanburismusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BanburismusError::NonAlphabetic => write!(f, "messages may only hold letters"),
+            BanburismusError::InvalidIndicatorLength => {
+                write!(f, "an indicator must have 3 letters")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BanburismusError {}
+
+///
+/// An intercepted message. The indicator holds the message's start position, so messages sharing
+/// the first two indicator letters only differ in the starting position of the right-hand rotor.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub indicator: [char; 3],
+    pub ciphertext: Vec<char>,
+}
+
+impl Message {
+    pub fn new(indicator: &str, ciphertext: &str) -> Result<Self, BanburismusError> {
+        let indicator: Vec<char> = parse_letters(indicator)?;
+        let indicator = indicator
+            .try_into()
+            .map_err(|_| BanburismusError::InvalidIndicatorLength)?;
+
+        Ok(Self {
+            indicator,
+            ciphertext: parse_letters(ciphertext)?,
+        })
+    }
+}
+
+///
+/// Repeat rates used to weigh a comparison.
+///
+/// The defaults are the rate of repeats between two German naval messages in depth (about 1/17)
+/// and between two unrelated texts (1/26).
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoringModel {
+    pub plain_repeat_rate: f64,
+    pub random_repeat_rate: f64,
+}
+
+impl Default for ScoringModel {
+    fn default() -> Self {
+        Self {
+            plain_repeat_rate: 1.0 / 17.0,
+            random_repeat_rate: 1.0 / ALPHABET_SIZE as f64,
+        }
+    }
+}
+
+impl ScoringModel {
+    /// Decibans gained for every repeated letter.
+    pub fn repeat_score(&self) -> f64 {
+        10.0 * (self.plain_repeat_rate / self.random_repeat_rate).log10()
+    }
+
+    /// Decibans gained (a negative amount) for every letter that doesn't repeat.
+    pub fn non_repeat_score(&self) -> f64 {
+        10.0 * ((1.0 - self.plain_repeat_rate) / (1.0 - self.random_repeat_rate)).log10()
+    }
+
+    pub fn score(&self, repeats: usize, overlap: usize) -> f64 {
+        repeats as f64 * self.repeat_score() + (overlap - repeats) as f64 * self.non_repeat_score()
+    }
+}
+
+/// The result of setting the second message `offset` letters to the right of the first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alignment {
+    pub offset: usize,
+    pub overlap: usize,
+    pub repeats: usize,
+    pub score: f64,
+}
+
+pub fn compare(
+    first: &Message,
+    second: &Message,
+    offset: usize,
+    model: &ScoringModel,
+) -> Alignment {
+    let shifted = first.ciphertext.iter().skip(offset);
+    let (overlap, repeats) = shifted
+        .zip(second.ciphertext.iter())
+        .fold((0, 0), |(overlap, repeats), (a, b)| {
+            (overlap + 1, repeats + usize::from(a == b))
+        });
+
+    Alignment {
+        offset,
+        overlap,
+        repeats,
+        score: model.score(repeats, overlap),
+    }
+}
+
+/// Compares the two messages at every offset at which they still overlap.
+pub fn compare_all_offsets(
+    first: &Message,
+    second: &Message,
+    model: &ScoringModel,
+) -> Vec<Alignment> {
+    (0..first.ciphertext.len())
+        .map(|offset| compare(first, second, offset, model))
+        .collect()
+}
+
+///
+/// The evidence given by one pair of messages with equal left and middle start positions.
+///
+/// The messages are in depth at `alignment.offset` unless the middle rotor turned over while the
+/// right-hand rotor moved from the first message's start position to the second's.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairEvidence {
+    pub first: usize,
+    pub second: usize,
+    pub alignment: Alignment,
+    start: usize,
+}
+
+impl PairEvidence {
+    /// Whether a turnover onto `position` falls between the two start positions.
+    pub fn spans_turnover(&self, position: char) -> Result<bool, Error> {
+        LATIN
+            .index_of(position.to_ascii_uppercase())
+            .map(|position| self.spans_turnover_at(position))
+            .ok_or(Error::InvalidLetter(position))
+    }
+
+    fn spans_turnover_at(&self, position: usize) -> bool {
+        let distance_to_turnover = (position + ALPHABET_SIZE - self.start) % ALPHABET_SIZE;
+
+        (1..=self.alignment.offset).contains(&distance_to_turnover)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotorCandidate {
+    pub rotor: RotorType,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub pairs: Vec<PairEvidence>,
+    turnover_scores: [f64; ALPHABET_SIZE],
+}
+
+impl Analysis {
+    ///
+    /// The evidence in decibans for the middle rotor turning over as the right-hand rotor steps onto
+    /// each position. Only the differences between the scores are meaningful.
+    ///
+    pub fn turnover_scores(&self) -> &[f64; ALPHABET_SIZE] {
+        &self.turnover_scores
+    }
+
+    /// Returns every turnover position sharing the best score.
+    pub fn likely_turnovers(&self) -> Vec<char> {
+        let best = self.best_score();
+
+        (0..ALPHABET_SIZE)
+            .filter(|&position| self.turnover_scores[position] >= best)
+            .map(index_letter)
+            .collect()
+    }
+
+    ///
+    /// Returns the right-hand rotor candidates scoring within `threshold` decibans of the best
+    /// turnover position, best first.
+    ///
+    /// A rotor collects the score of every pair that none of its notches would have taken out of
+    /// depth, so rotors with several notches are judged on all of them. Fails for rotors that
+    /// [`rotors::create_rotor`] can't create.
+    ///
+    pub fn rotor_candidates(
+        &self,
+        rotor_types: &[RotorType],
+        threshold: f64,
+    ) -> Result<Vec<RotorCandidate>, Error> {
+        let best = self.best_score();
+
+        let mut candidates = Vec::with_capacity(rotor_types.len());
+        for &rotor in rotor_types {
+            let step_positions = rotors::create_rotor(rotor)?
+                .get_props()
+                .get_step_positions();
+            let candidate = RotorCandidate {
+                rotor,
+                score: self.score_in_depth(|pair| {
+                    step_positions
+                        .iter()
+                        .all(|&position| !pair.spans_turnover_at(letter_index(position)))
+                }),
+            };
+            if candidate.score >= best - threshold {
+                candidates.push(candidate);
+            }
+        }
+
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+
+    fn score_in_depth<F: Fn(&PairEvidence) -> bool>(&self, in_depth: F) -> f64 {
+        self.pairs
+            .iter()
+            .filter(|pair| in_depth(pair))
+            .map(|pair| pair.alignment.score)
+            .sum()
+    }
+
+    fn best_score(&self) -> f64 {
+        self.turnover_scores
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+///
+/// Compares every pair of messages whose indicators agree on the left and middle positions and
+/// scores each possible middle rotor turnover.
+///
+/// A turnover hypothesis collects the deciban score of every pair it predicts to be in depth.
+///
+pub fn analyse(messages: &[Message], model: &ScoringModel) -> Analysis {
+    let mut pairs = Vec::new();
+
+    for (first_index, first) in messages.iter().enumerate() {
+        for (second_index, second) in messages.iter().enumerate() {
+            if first.indicator[..2] != second.indicator[..2]
+                || first.indicator[2] == second.indicator[2]
+            {
+                continue;
+            }
+
+            let offset = (letter_index(second.indicator[2]) + ALPHABET_SIZE
+                - letter_index(first.indicator[2]))
+                % ALPHABET_SIZE;
+
+            // Each unordered pair is only compared once, with the later starting message shifted
+            // by the shorter distance.
+            if offset > ALPHABET_SIZE / 2
+                || (offset == ALPHABET_SIZE / 2 && first_index > second_index)
+            {
+                continue;
+            }
+
+            pairs.push(PairEvidence {
+                first: first_index,
+                second: second_index,
+                alignment: compare(first, second, offset, model),
+                start: letter_index(first.indicator[2]),
+            });
+        }
+    }
+
+    let mut analysis = Analysis {
+        pairs,
+        turnover_scores: [0.0; ALPHABET_SIZE],
+    };
+    for position in 0..ALPHABET_SIZE {
+        analysis.turnover_scores[position] =
+            analysis.score_in_depth(|pair| !pair.spans_turnover_at(position));
+    }
+
+    analysis
+}
+
+fn parse_letters(text: &str) -> Result<Vec<char>, BanburismusError> {
+    text.chars()
+        .map(|letter| match letter.is_ascii_alphabetic() {
+            true => Ok(letter.to_ascii_uppercase()),
+            false => Err(BanburismusError::NonAlphabetic),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Enigma, reflectors};
+
+    // German signals in the style of the naval traffic, so messages in depth repeat letters with
+    // German rather than English statistics.
+    const PLAINTEXT: &str = "\
+        AN BEFEHLSHABER DER UNTERSEEBOOTE WETTERBERICHT FUER DAS SEEGEBIET WESTLICH VON IRLAND \
+        WIND AUS SUEDWEST STAERKE SECHS SEEGANG FUENF SICHT GUT LUFTDRUCK FALLEND \
+        EIGENE POSITION QUADRAT DREI SIEBEN VIER EINS GELEITZUG IN SICHT KURS OST \
+        GESCHWINDIGKEIT NEUN SEEMEILEN ERBITTE WEITERE BEFEHLE \
+        VON BEFEHLSHABER DER UNTERSEEBOOTE AN ALLE BOOTE IM OPERATIONSGEBIET \
+        GELEITZUG ANGREIFEN SOBALD DIE DUNKELHEIT EINSETZT FUEHLUNG HALTEN UND MELDEN \
+        WETTER FUER DIE NACHT WIND AUS WEST STAERKE SIEBEN SEEGANG SECHS REGENSCHAUER \
+        EIGENE POSITION QUADRAT DREI SIEBEN VIER ZWEI FUEHLUNG AM GELEITZUG VERLOREN \
+        BRENNSTOFF NOCH ZWANZIG KUBIKMETER TORPEDOS NOCH SECHS AN BORD";
+
+    fn intercepted_messages() -> Vec<Message> {
+        let plaintext: String = PLAINTEXT
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .collect();
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_3(),
+            rotors::create_rotor_2(),
+            reflectors::create_reflector_b(),
+        );
+
+        ['A', 'C', 'E', 'H', 'K', 'N', 'Q', 'T', 'W', 'Y']
+            .iter()
+            .enumerate()
+            .map(|(index, &right_position)| {
+                enigma.set_left_rotor_position_from_char('M').unwrap();
+                enigma.set_middle_rotor_position_from_char('J').unwrap();
+                enigma
+                    .set_right_rotor_position_from_char(right_position)
+                    .unwrap();
+
+                let start = index * 60 % (plaintext.len() - 200);
+                let ciphertext = enigma.encrypt_str(&plaintext[start..start + 200]).unwrap();
+                Message::new(&format!("MJ{right_position}"), &ciphertext).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scores_should_reward_repeats() {
+        let model = ScoringModel::default();
+
+        assert!(model.repeat_score() > 0.0);
+        assert!(model.non_repeat_score() < 0.0);
+        assert_eq!(model.score(0, 0), 0.0);
+    }
+
+    #[test]
+    fn compare_should_count_repeats_at_offset() {
+        let first = Message::new("AAA", "XYZABC").unwrap();
+        let second = Message::new("AAB", "ZABQ").unwrap();
+
+        let alignment = compare(&first, &second, 2, &ScoringModel::default());
+
+        assert_eq!((alignment.overlap, alignment.repeats), (4, 3));
+        assert_eq!(
+            compare_all_offsets(&first, &second, &ScoringModel::default()).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn message_with_invalid_indicator_should_return_error() {
+        assert_eq!(
+            Message::new("AB", "XYZ"),
+            Err(BanburismusError::InvalidIndicatorLength)
+        );
+        assert_eq!(
+            Message::new("ABC", "X Z"),
+            Err(BanburismusError::NonAlphabetic)
+        );
+    }
+
+    #[test]
+    fn spans_turnover_should_check_positions_between_the_starts() {
+        let first = Message::new("AAC", "XYZABC").unwrap();
+        let second = Message::new("AAF", "ZABQ").unwrap();
+        let pair = PairEvidence {
+            first: 0,
+            second: 1,
+            alignment: compare(&first, &second, 3, &ScoringModel::default()),
+            start: letter_index('C'),
+        };
+
+        assert_eq!(pair.spans_turnover('D'), Ok(true));
+        assert_eq!(pair.spans_turnover('f'), Ok(true));
+        assert_eq!(pair.spans_turnover('G'), Ok(false));
+        assert_eq!(pair.spans_turnover('C'), Ok(false));
+        assert_eq!(pair.spans_turnover('1'), Err(Error::InvalidLetter('1')));
+    }
+
+    #[test]
+    fn analysis_should_identify_right_rotor_by_notch() {
+        let analysis = analyse(&intercepted_messages(), &ScoringModel::default());
+
+        assert!(analysis.likely_turnovers().contains(&'F'));
+
+        let candidates = analysis
+            .rotor_candidates(
+                &[
+                    RotorType::Rotor1,
+                    RotorType::Rotor2,
+                    RotorType::Rotor3,
+                    RotorType::Rotor4,
+                    RotorType::Rotor5,
+                ],
+                10.0,
+            )
+            .unwrap();
+        assert_eq!(candidates[0].rotor, RotorType::Rotor2);
+        assert!(
+            candidates
+                .iter()
+                .all(|candidate| candidate.rotor != RotorType::Rotor3)
+        );
+    }
+}
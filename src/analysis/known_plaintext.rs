@@ -0,0 +1,427 @@
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use crate::analysis::{index_letter, letter_index, scramble};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::Error;
+use crate::reflectors::Reflector;
+use crate::rotor::RotorType;
+use crate::rotors;
+use crate::rotors_controller::RotorsController;
+use crate::search::SearchSpace;
+use crate::stepping::{DoubleStep, SteppingStrategy};
+
+const UNPLUGGED: u8 = u8::MAX;
+
+#[derive(Debug, PartialEq)]
+pub enum KnownPlaintextError {
+    NonAlphabetic,
+    LengthMismatch,
+    /// The search space holds a setting the machine rejects, e.g. a ring setting outside the alphabet.
+    Machine(Error),
+}
+
+impl Display for KnownPlaintextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KnownPlaintextError::NonAlphabetic => write!(f, "the texts may only hold letters"),
+            KnownPlaintextError::LengthMismatch => {
+                write!(f, "the plaintext and ciphertext differ in length")
+            }
+            KnownPlaintextError::Machine(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for KnownPlaintextError {}
+
+impl From<Error> for KnownPlaintextError {
+    fn from(error: Error) -> Self {
+        KnownPlaintextError::Machine(error)
+    }
+}
+
+/// Limits the settings the attack considers.
+#[derive(Debug, Clone)]
+pub struct Constraints {
+    pub space: SearchSpace,
+    pub reflector: Reflector,
+    pub max_cables: Option<usize>,
+    /// How the rotors of the attacked machine step, [`DoubleStep`] unless set otherwise.
+    pub stepping: Arc<dyn SteppingStrategy>,
+}
+
+impl Constraints {
+    pub fn new(space: SearchSpace, reflector: Reflector) -> Self {
+        Self {
+            space,
+            reflector,
+            max_cables: None,
+            stepping: Arc::new(DoubleStep),
+        }
+    }
+
+    pub fn with_max_cables(mut self, max_cables: usize) -> Self {
+        self.max_cables = Some(max_cables);
+        self
+    }
+
+    pub fn with_stepping_strategy<S: SteppingStrategy + 'static>(mut self, stepping: S) -> Self {
+        self.stepping = Arc::new(stepping);
+        self
+    }
+}
+
+///
+/// A machine setting consistent with the plaintext/ciphertext pair.
+///
+/// `plugboard` holds the first consistent plugboard found for the letters of the pair. For every
+/// unconnected part of the menu, the partner of one letter is guessed in alphabet order and the
+/// cables that follow from the guess are added. So `plugboard` mixes guessed and forced cables,
+/// and other guesses may fit the pair just as well. Letters that appear in neither text may be
+/// plugged in any way that respects the cable limit.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub wheel_order: [RotorType; 3],
+    pub ring_settings: [char; 3],
+    pub position: [char; 3],
+    pub plugboard: Vec<(char, char)>,
+}
+
+///
+/// Returns every setting in the constraints' search space for which a plugboard exists that
+/// enciphers `plain` into `cipher`.
+///
+/// For each setting a plugboard partner is guessed for one letter of the menu and the consequences
+/// are propagated through every position the letter appears at - `S(c) = R(S(p))` for a scrambler
+/// `R` - until either every menu letter is plugged or two cables contradict each other.
+///
+pub fn known_plaintext(
+    plain: &str,
+    cipher: &str,
+    constraints: &Constraints,
+) -> Result<Vec<Solution>, KnownPlaintextError> {
+    let menu = Menu::new(plain, cipher)?;
+    let mut solutions = Vec::new();
+    let mut controller: Option<RotorsController> = None;
+    let mut current_wheel_order = None;
+
+    for index in 0..constraints.space.len() {
+        let (wheel_order, ring_settings, position) = constraints.space.setting(index);
+
+        let controller = match controller.as_mut() {
+            Some(controller) if current_wheel_order == Some(wheel_order) => controller,
+            _ => {
+                current_wheel_order = Some(wheel_order);
+                let mut created = RotorsController::new(
+                    rotors::create_rotor(wheel_order[0])?,
+                    rotors::create_rotor(wheel_order[1])?,
+                    rotors::create_rotor(wheel_order[2])?,
+                );
+                created.set_stepping_strategy(constraints.stepping.clone());
+                controller.insert(created)
+            }
+        };
+
+        controller.set_ring_settings(&ring_settings)?;
+        controller.set_left_rotor_position_from_int(position[0])?;
+        controller.set_middle_rotor_position_from_int(position[1])?;
+        controller.set_right_rotor_position_from_int(position[2])?;
+
+        let mut scrambler = Scrambler::new(controller, &constraints.reflector, menu.len());
+        let board = [UNPLUGGED; ALPHABET_SIZE];
+
+        if let Some(board) = menu.solve(board, &mut scrambler, constraints.max_cables) {
+            solutions.push(Solution {
+                wheel_order,
+                ring_settings,
+                position: position.map(index_letter),
+                plugboard: cables(&board),
+            });
+        }
+    }
+
+    Ok(solutions)
+}
+
+/// The letters of the pair and the positions connecting them.
+struct Menu {
+    edges: [Vec<(u8, usize)>; ALPHABET_SIZE],
+    letters: Vec<u8>,
+    length: usize,
+}
+
+impl Menu {
+    fn new(plain: &str, cipher: &str) -> Result<Self, KnownPlaintextError> {
+        let plain = parse_letters(plain)?;
+        let cipher = parse_letters(cipher)?;
+        if plain.len() != cipher.len() {
+            return Err(KnownPlaintextError::LengthMismatch);
+        }
+
+        let mut edges: [Vec<(u8, usize)>; ALPHABET_SIZE] = Default::default();
+        for (step, (&p, &c)) in plain.iter().zip(cipher.iter()).enumerate() {
+            edges[p as usize].push((c, step));
+            edges[c as usize].push((p, step));
+        }
+
+        // Guessing the best connected letters first lets a single guess plug most of the menu.
+        let mut letters: Vec<u8> = (0..ALPHABET_SIZE as u8)
+            .filter(|&letter| !edges[letter as usize].is_empty())
+            .collect();
+        letters.sort_by_key(|&letter| std::cmp::Reverse(edges[letter as usize].len()));
+
+        Ok(Self {
+            edges,
+            letters,
+            length: plain.len(),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn solve(
+        &self,
+        board: [u8; ALPHABET_SIZE],
+        scrambler: &mut Scrambler,
+        max_cables: Option<usize>,
+    ) -> Option<[u8; ALPHABET_SIZE]> {
+        let Some(&letter) = self
+            .letters
+            .iter()
+            .find(|&&letter| board[letter as usize] == UNPLUGGED)
+        else {
+            return Some(board);
+        };
+
+        (0..ALPHABET_SIZE as u8).find_map(|partner| {
+            let mut board = board;
+            let consistent = self.propagate(&mut board, letter, partner, scrambler)
+                && max_cables.is_none_or(|max_cables| cables_count(&board) <= max_cables);
+
+            match consistent {
+                true => self.solve(board, scrambler, max_cables),
+                false => None,
+            }
+        })
+    }
+
+    fn propagate(
+        &self,
+        board: &mut [u8; ALPHABET_SIZE],
+        letter: u8,
+        partner: u8,
+        scrambler: &mut Scrambler,
+    ) -> bool {
+        let mut pending = Vec::new();
+        if !plug(board, letter, partner, &mut pending) {
+            return false;
+        }
+
+        while let Some(letter) = pending.pop() {
+            let partner = board[letter as usize];
+            for &(connected, step) in &self.edges[letter as usize] {
+                let connected_partner = scrambler.map(step, partner);
+                if !plug(board, connected, connected_partner, &mut pending) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// The plugboard-less machine at every step of the pair, mapped lazily.
+struct Scrambler<'a> {
+    steps: Vec<RotorsController>,
+    reflector: &'a Reflector,
+    cache: Vec<[u8; ALPHABET_SIZE]>,
+}
+
+impl<'a> Scrambler<'a> {
+    fn new(start: &RotorsController, reflector: &'a Reflector, length: usize) -> Self {
+        let mut controller = start.clone();
+        let steps = (0..length)
+            .map(|_| {
+                controller.increment();
+                controller.clone()
+            })
+            .collect();
+
+        Self {
+            steps,
+            reflector,
+            cache: vec![[UNPLUGGED; ALPHABET_SIZE]; length],
+        }
+    }
+
+    fn map(&mut self, step: usize, letter: u8) -> u8 {
+        let cached = &mut self.cache[step][letter as usize];
+        if *cached == UNPLUGGED {
+            let mapped = scramble(
+                &self.steps[step],
+                self.reflector,
+                index_letter(letter as usize),
+            )
+            .expect("scrambler input is always an uppercase letter");
+            *cached = letter_index(mapped) as u8;
+        }
+
+        *cached
+    }
+}
+
+fn plug(board: &mut [u8; ALPHABET_SIZE], first: u8, second: u8, pending: &mut Vec<u8>) -> bool {
+    for (letter, partner) in [(first, second), (second, first)] {
+        match board[letter as usize] {
+            UNPLUGGED => {
+                board[letter as usize] = partner;
+                pending.push(letter);
+            }
+            existing if existing != partner => return false,
+            _ => {}
+        }
+    }
+
+    true
+}
+
+fn cables_count(board: &[u8; ALPHABET_SIZE]) -> usize {
+    board
+        .iter()
+        .enumerate()
+        .filter(|&(letter, &partner)| partner != UNPLUGGED && partner as usize > letter)
+        .count()
+}
+
+fn cables(board: &[u8; ALPHABET_SIZE]) -> Vec<(char, char)> {
+    board
+        .iter()
+        .enumerate()
+        .filter(|&(letter, &partner)| partner != UNPLUGGED && partner as usize > letter)
+        .map(|(letter, &partner)| (index_letter(letter), index_letter(partner as usize)))
+        .collect()
+}
+
+fn parse_letters(text: &str) -> Result<Vec<u8>, KnownPlaintextError> {
+    text.chars()
+        .map(|letter| match letter.is_ascii_alphabetic() {
+            true => Ok(letter_index(letter.to_ascii_uppercase()) as u8),
+            false => Err(KnownPlaintextError::NonAlphabetic),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MachineModel;
+    use crate::stepping::PawlAndNotch;
+    use crate::{Enigma, reflectors};
+
+    const PLAIN: &str = "KEINEBESONDERENEREIGNISSE";
+    const WHEEL_ORDER: [RotorType; 3] = [RotorType::Rotor2, RotorType::Rotor5, RotorType::Rotor3];
+
+    fn machine(position: [char; 3], plugboard: &[(char, char)]) -> Enigma {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor(WHEEL_ORDER[0]).unwrap(),
+            rotors::create_rotor(WHEEL_ORDER[1]).unwrap(),
+            rotors::create_rotor(WHEEL_ORDER[2]).unwrap(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_stepping_strategy(DoubleStep);
+        plugboard
+            .iter()
+            .for_each(|&(first, second)| enigma.set_transposition(first, second).unwrap());
+        enigma
+            .set_left_rotor_position_from_char(position[0])
+            .unwrap();
+        enigma
+            .set_middle_rotor_position_from_char(position[1])
+            .unwrap();
+        enigma
+            .set_right_rotor_position_from_char(position[2])
+            .unwrap();
+        enigma
+    }
+
+    fn constraints() -> Constraints {
+        Constraints::new(
+            SearchSpace {
+                wheel_orders: vec![WHEEL_ORDER],
+                ring_settings: vec![['A', 'A', 'A']],
+            },
+            reflectors::create_reflector_b(),
+        )
+    }
+
+    #[test]
+    fn attack_should_recover_setting_and_plugboard() {
+        let plugboard = [('E', 'Q'), ('N', 'T'), ('S', 'X'), ('B', 'L'), ('I', 'U')];
+        let cipher = machine(['P', 'D', 'H'], &plugboard)
+            .encrypt_str(PLAIN)
+            .unwrap();
+
+        let solutions =
+            known_plaintext(PLAIN, &cipher, &constraints().with_max_cables(10)).unwrap();
+
+        assert!(
+            solutions
+                .iter()
+                .any(|solution| solution.position == ['P', 'D', 'H'])
+        );
+        for solution in solutions {
+            let enciphered = machine(solution.position, &solution.plugboard)
+                .encrypt_str(PLAIN)
+                .unwrap();
+            assert_eq!(enciphered, cipher);
+        }
+    }
+
+    #[test]
+    fn attack_should_follow_the_double_step() {
+        let wheel_order = [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3];
+        let mut enigma = MachineModel::EnigmaI
+            .create(wheel_order, reflectors::ReflectorType::ReflectorB)
+            .unwrap();
+        enigma.set_rotor_positions(&['A', 'D', 'U']).unwrap();
+        // The middle rotor double steps on the third key press, from AEW to BFX.
+        let cipher = enigma.encrypt_str(PLAIN).unwrap();
+        let constraints = Constraints::new(
+            SearchSpace {
+                wheel_orders: vec![wheel_order],
+                ring_settings: vec![['A', 'A', 'A']],
+            },
+            reflectors::create_reflector_b(),
+        );
+
+        let found = |constraints: &Constraints| {
+            known_plaintext(PLAIN, &cipher, constraints)
+                .unwrap()
+                .iter()
+                .any(|solution| solution.position == ['A', 'D', 'U'])
+        };
+
+        assert!(found(&constraints));
+        assert!(!found(&constraints.with_stepping_strategy(PawlAndNotch)));
+    }
+
+    #[test]
+    fn letter_enciphered_to_itself_should_have_no_solution() {
+        let solutions = known_plaintext("ABC", "XBZ", &constraints()).unwrap();
+
+        assert!(solutions.is_empty());
+    }
+
+    #[test]
+    fn mismatched_lengths_should_return_error() {
+        assert_eq!(
+            known_plaintext("ABC", "AB", &constraints()),
+            Err(KnownPlaintextError::LengthMismatch)
+        );
+    }
+}
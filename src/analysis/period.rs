@@ -0,0 +1,307 @@
+use crate::Enigma;
+
+///
+/// Where the moving parts of a machine stand: the rotor positions, leftmost first, and the
+/// reflector position, which only changes on machines whose stepping drives the reflector.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct State {
+    pub positions: Vec<char>,
+    pub reflector_position: char,
+}
+
+impl State {
+    pub fn of<const N: usize>(enigma: &Enigma<N>) -> Self {
+        Self {
+            positions: enigma.get_rotor_positions(),
+            reflector_position: enigma.get_reflector_position(),
+        }
+    }
+}
+
+///
+/// The states a machine passes through as keys are pressed. Every machine eventually repeats
+/// itself, so the states form a tail that is never returned to, followed by a cycle that repeats
+/// forever.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orbit {
+    /// The states before the cycle, starting with the start state. Empty when the start state is
+    /// part of the cycle.
+    pub tail: Vec<State>,
+    pub cycle: Vec<State>,
+}
+
+impl Orbit {
+    pub fn period(&self) -> usize {
+        self.cycle.len()
+    }
+}
+
+/// Steps a copy of a machine from state to state, without telling the original's observer.
+struct Stepper<const N: usize> {
+    machine: Enigma<N>,
+}
+
+impl<const N: usize> Stepper<N> {
+    fn new(enigma: &Enigma<N>) -> Self {
+        let mut machine = enigma.clone();
+        machine.clear_observer();
+        Self { machine }
+    }
+
+    fn next(&mut self, state: &State) -> State {
+        self.machine
+            .set_rotor_positions(&state.positions)
+            .and_then(|()| {
+                self.machine
+                    .set_reflector_position(state.reflector_position)
+            })
+            .expect("states are read from the same machine");
+        self.machine.increment_by(1);
+        State::of(&self.machine)
+    }
+}
+
+///
+/// Returns how many key presses it takes the machine to return to a state, starting from its
+/// current one. This is how long the keystream runs before it repeats. It depends on the stepping
+/// and the rotors: [`PawlAndNotch`](crate::stepping::PawlAndNotch) stepping of three single-notch
+/// rotors visits all 26^3 positions, the [`DoubleStep`](crate::stepping::DoubleStep) of the
+/// Enigma I and M3 cuts this to 16,900, and multi-notch rotors shorten it further.
+///
+pub fn period<const N: usize>(enigma: &Enigma<N>) -> usize {
+    tail_and_period(enigma).1
+}
+
+///
+/// Lists the states the machine passes through from its current state until it repeats itself.
+/// The cycle can hold up to `N^(rotors + 1)` states, so this is meant for teaching and for bounding
+/// searches rather than for machines with many moving rotors.
+///
+pub fn orbit<const N: usize>(enigma: &Enigma<N>) -> Orbit {
+    let (tail_length, period) = tail_and_period(enigma);
+    let mut stepper = Stepper::new(enigma);
+
+    let mut state = State::of(enigma);
+    let mut tail = Vec::with_capacity(tail_length);
+    for _ in 0..tail_length {
+        let next = stepper.next(&state);
+        tail.push(std::mem::replace(&mut state, next));
+    }
+    let mut cycle = Vec::with_capacity(period);
+    for _ in 0..period {
+        let next = stepper.next(&state);
+        cycle.push(std::mem::replace(&mut state, next));
+    }
+
+    Orbit { tail, cycle }
+}
+
+///
+/// Returns the rotor positions, leftmost first, that the machine never comes back to. The operator
+/// can set them by hand, but once a key press steps the machine off them no number of key presses
+/// returns it there. For the [`DoubleStep`](crate::stepping::DoubleStep) of the Enigma I and M3
+/// these are the 676 positions that make up the difference between 26^3 and the period of 16,900.
+///
+/// Whether the machine comes back doesn't depend on the reflector, which is only ever driven by
+/// the rotors. Every combination of rotor positions is stepped once, which is `N^rotors` of them.
+///
+pub fn unreachable_positions<const N: usize>(enigma: &Enigma<N>) -> Vec<Vec<char>> {
+    let alphabet = enigma.entry_wheel.alphabet();
+    let rotors = enigma.rotor_count();
+    let mut stepper = Stepper::new(enigma);
+    let reflector_position = enigma.get_reflector_position();
+
+    let index_of = |positions: &[char]| {
+        positions.iter().fold(0, |index, &position| {
+            index * N + alphabet.index_of(position).unwrap_or_default()
+        })
+    };
+    let positions_of = |mut index: usize| {
+        let mut positions = vec![alphabet.symbol(0); rotors];
+        for position in positions.iter_mut().rev() {
+            *position = alphabet.symbol(index % N);
+            index /= N;
+        }
+        positions
+    };
+
+    let states = N.pow(rotors as u32);
+    let mut next = Vec::with_capacity(states);
+    let mut predecessors = vec![0; states];
+    for index in 0..states {
+        let state = stepper.next(&State {
+            positions: positions_of(index),
+            reflector_position,
+        });
+        let next_index = index_of(&state.positions);
+        next.push(next_index);
+        predecessors[next_index] += 1;
+    }
+
+    // Positions without a predecessor can't be on a cycle. Removing them may leave their successors
+    // without one too, until only the cycles are left.
+    let mut unreachable: Vec<usize> = (0..states)
+        .filter(|&index| predecessors[index] == 0)
+        .collect();
+    let mut removed = 0;
+    while let Some(&index) = unreachable.get(removed) {
+        removed += 1;
+        predecessors[next[index]] -= 1;
+        if predecessors[next[index]] == 0 {
+            unreachable.push(next[index]);
+        }
+    }
+
+    unreachable.sort_unstable();
+    unreachable.into_iter().map(positions_of).collect()
+}
+
+///
+/// Finds how many key presses pass before the machine enters its cycle and how long the cycle is,
+/// with Brent's algorithm, which only keeps two states at a time.
+///
+fn tail_and_period<const N: usize>(enigma: &Enigma<N>) -> (usize, usize) {
+    let mut stepper = Stepper::new(enigma);
+    let start = State::of(enigma);
+
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = stepper.next(&start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = stepper.next(&hare);
+        period += 1;
+    }
+
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..period {
+        hare = stepper.next(&hare);
+    }
+    let mut tail_length = 0;
+    while tortoise != hare {
+        tortoise = stepper.next(&tortoise);
+        hare = stepper.next(&hare);
+        tail_length += 1;
+    }
+
+    (tail_length, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MachineModel;
+    use crate::reflectors::ReflectorType;
+    use crate::rotor::RotorType;
+    use crate::rotors_controller::RotorRole;
+    use crate::stepping::{Cogwheel, DoubleStep};
+    use crate::{entry_wheels, reflectors, rotors};
+
+    fn enigma_i() -> Enigma {
+        Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        )
+    }
+
+    fn double_stepping_enigma_i() -> Enigma {
+        MachineModel::EnigmaI
+            .create(
+                [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3],
+                ReflectorType::ReflectorB,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn pawl_and_notch_should_visit_every_state() {
+        let enigma = enigma_i();
+
+        let orbit = orbit(&enigma);
+
+        assert_eq!(period(&enigma), 26 * 26 * 26);
+        assert!(orbit.tail.is_empty());
+        assert_eq!(orbit.period(), 26 * 26 * 26);
+        assert_eq!(orbit.cycle[0], State::of(&enigma));
+        assert_eq!(orbit.cycle[1].positions, vec!['A', 'A', 'B']);
+        assert!(unreachable_positions(&enigma).is_empty());
+    }
+
+    #[test]
+    fn double_stepping_should_shorten_the_period() {
+        let m3 = MachineModel::M3
+            .create(
+                [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3],
+                ReflectorType::ReflectorB,
+            )
+            .unwrap();
+        let mut enigma = enigma_i();
+        enigma.set_stepping_strategy(DoubleStep);
+
+        assert_eq!(period(&double_stepping_enigma_i()), 16_900);
+        assert_eq!(period(&m3), 16_900);
+        assert_eq!(period(&enigma), 16_900);
+    }
+
+    #[test]
+    fn unreachable_start_should_lead_into_the_cycle() {
+        let mut enigma = double_stepping_enigma_i();
+        // Rotor II carries on its step from E to F, so it never rests on E.
+        enigma.set_rotor_positions(&['A', 'E', 'A']).unwrap();
+
+        let orbit = orbit(&enigma);
+
+        assert_eq!(orbit.tail, vec![State::of(&enigma)]);
+        assert_eq!(orbit.period(), 16_900);
+        assert_eq!(orbit.cycle[0].positions, vec!['B', 'F', 'B']);
+    }
+
+    #[test]
+    fn unreachable_positions_should_be_skipped_by_the_double_step() {
+        let unreachable = unreachable_positions(&double_stepping_enigma_i());
+
+        assert_eq!(unreachable.len(), 26 * 26 * 26 - 16_900);
+        assert!(unreachable.contains(&vec!['A', 'E', 'A']));
+        assert!(!unreachable.contains(&vec!['A', 'E', 'W']));
+        // Positions only reached from skipped ones are never returned to either.
+        assert!(unreachable.contains(&vec!['B', 'F', 'W']));
+        assert!(!unreachable.contains(&vec!['B', 'F', 'X']));
+    }
+
+    #[test]
+    fn cogwheel_should_include_the_reflector_in_the_period() {
+        let mut enigma = enigma_i();
+        enigma.set_stepping_strategy(Cogwheel);
+
+        // The left rotor drives the reflector on by one for every turn of the rotors.
+        assert_eq!(period(&enigma), 26 * 26 * 26 * 26);
+    }
+
+    #[test]
+    fn multi_notch_rotors_should_shorten_the_period() {
+        let enigma = Enigma::with_rotor_stack(
+            vec![
+                (rotors::create_rotor_beta(), RotorRole::GreekWheel),
+                (rotors::create_rotor_6(), RotorRole::Stepping),
+                (rotors::create_rotor_7(), RotorRole::Stepping),
+                (rotors::create_rotor_8(), RotorRole::Stepping),
+            ],
+            reflectors::create_reflector_b_thin(),
+            entry_wheels::create_entry_wheel_identity(),
+        )
+        .unwrap();
+
+        // Two notches on the middle and right rotors carry the rotors left of them twice per turn.
+        assert_eq!(period(&enigma), 13 * 13 * 26);
+    }
+}
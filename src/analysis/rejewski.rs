@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::analysis::{index_letter, letter_index, position_from_index, scramble};
+use crate::consts::{ALPHABET_SIZE, FIRST_LETTER, LAST_LETTER};
+use crate::error::Error;
+use crate::permutation::Permutation;
+use crate::reflectors::Reflector;
+use crate::rotor::RotorType;
+use crate::rotors;
+use crate::rotors_controller::RotorsController;
+
+/// Number of letters in a doubled message key indicator.
+pub const INDICATOR_LENGTH: usize = 6;
+
+pub(crate) const POSITIONS_PER_WHEEL_ORDER: usize = ALPHABET_SIZE * ALPHABET_SIZE * ALPHABET_SIZE;
+
+/// A full substitution of the alphabet. Index 0 holds the image of 'A', index 25 the image of 'Z'.
+pub type Substitution = [char; ALPHABET_SIZE];
+
+#[derive(Debug, PartialEq)]
+pub enum RejewskiError {
+    NonAlphabetic,
+    InvalidIndicatorLength,
+    InconsistentIndicators,
+    IncompletePermutation,
+}
+
+impl Display for RejewskiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejewskiError::NonAlphabetic => write!(f, "indicators may only hold letters"),
+            RejewskiError::InvalidIndicatorLength => {
+                write!(f, "an indicator must have {INDICATOR_LENGTH} letters")
+            }
+            RejewskiError::InconsistentIndicators => {
+                write!(f, "the indicators contradict each other")
+            }
+            RejewskiError::IncompletePermutation => {
+                write!(
+                    f,
+                    "the indicators don't determine every letter of AD, BE and CF"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RejewskiError {}
+
+///
+/// The lengths of the cycles of a permutation, sorted from longest to shortest.
+///
+/// For the products AD, BE and CF every cycle length appears an even number of times, which is the
+/// property Rejewski's method is built on.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CycleStructure(Vec<usize>);
+
+impl CycleStructure {
+    pub fn of(substitution: &Substitution) -> Self {
+        let mut visited = [false; ALPHABET_SIZE];
+        let mut lengths = Vec::new();
+
+        for start in 0..ALPHABET_SIZE {
+            let mut length = 0;
+            let mut current = start;
+            while !visited[current] {
+                visited[current] = true;
+                current = letter_index(substitution[current]);
+                length += 1;
+            }
+
+            if length > 0 {
+                lengths.push(length);
+            }
+        }
+
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        Self(lengths)
+    }
+
+    pub fn lengths(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<&Permutation> for CycleStructure {
+    fn from(permutation: &Permutation) -> Self {
+        Self(permutation.cycle_type())
+    }
+}
+
+impl Display for CycleStructure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lengths: Vec<String> = self.0.iter().map(|length| length.to_string()).collect();
+        write!(f, "[{}]", lengths.join(", "))
+    }
+}
+
+/// The cycle structures of AD, BE and CF for a single ground setting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Characteristic {
+    pub ad: CycleStructure,
+    pub be: CycleStructure,
+    pub cf: CycleStructure,
+}
+
+impl Characteristic {
+    pub fn from_permutations(permutations: &[Substitution; INDICATOR_LENGTH]) -> Self {
+        Self {
+            ad: CycleStructure::of(&compose(&permutations[0], &permutations[3])),
+            be: CycleStructure::of(&compose(&permutations[1], &permutations[4])),
+            cf: CycleStructure::of(&compose(&permutations[2], &permutations[5])),
+        }
+    }
+}
+
+impl Display for Characteristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AD {} BE {} CF {}", self.ad, self.be, self.cf)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogueEntry {
+    pub wheel_order: [RotorType; 3],
+    pub position: [char; 3],
+}
+
+///
+/// Maps every characteristic to the wheel orders and ground settings that produce it.
+///
+/// A catalogue built from three rotors covers 6 wheel orders and 26^3 positions each, which gives
+/// the 105,456 entries of the historical card catalogue.
+///
+#[derive(Debug)]
+pub struct Catalogue {
+    entries: HashMap<Characteristic, Vec<CatalogueEntry>>,
+    len: usize,
+}
+
+impl Catalogue {
+    /// Builds the catalogue for every ordered choice of three distinct rotors out of `rotor_types`.
+    pub fn for_rotors(rotor_types: &[RotorType], reflector: Reflector) -> Result<Self, Error> {
+        let mut wheel_orders = Vec::new();
+        for &left in rotor_types {
+            for &middle in rotor_types.iter().filter(|&&middle| middle != left) {
+                for &right in rotor_types
+                    .iter()
+                    .filter(|&&right| right != left && right != middle)
+                {
+                    wheel_orders.push([left, middle, right]);
+                }
+            }
+        }
+
+        Self::for_wheel_orders(&wheel_orders, reflector)
+    }
+
+    pub fn for_wheel_orders(
+        wheel_orders: &[[RotorType; 3]],
+        reflector: Reflector,
+    ) -> Result<Self, Error> {
+        let mut entries: HashMap<Characteristic, Vec<CatalogueEntry>> = HashMap::new();
+
+        for &wheel_order in wheel_orders {
+            let mut controller = RotorsController::new(
+                rotors::create_rotor(wheel_order[0])?,
+                rotors::create_rotor(wheel_order[1])?,
+                rotors::create_rotor(wheel_order[2])?,
+            );
+
+            for index in 0..POSITIONS_PER_WHEEL_ORDER {
+                let position = position_from_index(index);
+                controller.set_left_rotor_position_from_int(position[0])?;
+                controller.set_middle_rotor_position_from_int(position[1])?;
+                controller.set_right_rotor_position_from_int(position[2])?;
+
+                entries
+                    .entry(characteristic(&controller, &reflector)?)
+                    .or_default()
+                    .push(CatalogueEntry {
+                        wheel_order,
+                        position: position.map(index_letter),
+                    });
+            }
+        }
+
+        Ok(Self {
+            entries,
+            len: wheel_orders.len() * POSITIONS_PER_WHEEL_ORDER,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of distinct characteristics in the catalogue.
+    pub fn characteristics_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn lookup(&self, characteristic: &Characteristic) -> &[CatalogueEntry] {
+        self.entries
+            .get(characteristic)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    ///
+    /// Returns every catalogued setting matching the characteristic of a day's doubled indicators.
+    ///
+    /// The indicators must cover enough message keys to fully determine AD, BE and CF.
+    ///
+    pub fn find_candidates<S: AsRef<str>>(
+        &self,
+        indicators: &[S],
+    ) -> Result<&[CatalogueEntry], RejewskiError> {
+        Ok(self.lookup(&characteristic_from_indicators(indicators)?))
+    }
+}
+
+///
+/// Returns the permutations the machine applies at each of the first six keypresses.
+///
+/// The controller is left untouched - the rotors are stepped on a copy, the same way they would be
+/// stepped while typing a doubled indicator from the given start position.
+///
+pub fn keypress_permutations(
+    controller: &RotorsController,
+    reflector: &Reflector,
+) -> Result<[Substitution; INDICATOR_LENGTH], Error> {
+    let mut controller = controller.clone();
+    let mut permutations = [[FIRST_LETTER; ALPHABET_SIZE]; INDICATOR_LENGTH];
+
+    for permutation in permutations.iter_mut() {
+        controller.increment();
+        *permutation = machine_permutation(&controller, reflector)?;
+    }
+
+    Ok(permutations)
+}
+
+pub fn characteristic(
+    controller: &RotorsController,
+    reflector: &Reflector,
+) -> Result<Characteristic, Error> {
+    Ok(Characteristic::from_permutations(&keypress_permutations(
+        controller, reflector,
+    )?))
+}
+
+///
+/// Reconstructs AD, BE and CF from doubled indicators and returns their cycle structures.
+///
+/// The first letter of an indicator is mapped by AD to its fourth letter, the second by BE to the
+/// fifth, and the third by CF to the sixth.
+///
+pub fn characteristic_from_indicators<S: AsRef<str>>(
+    indicators: &[S],
+) -> Result<Characteristic, RejewskiError> {
+    let mut products = [[None; ALPHABET_SIZE]; 3];
+
+    for indicator in indicators {
+        let letters: Vec<char> = indicator
+            .as_ref()
+            .chars()
+            .map(|letter| match letter.is_ascii_alphabetic() {
+                true => Ok(letter.to_ascii_uppercase()),
+                false => Err(RejewskiError::NonAlphabetic),
+            })
+            .collect::<Result<_, _>>()?;
+
+        if letters.len() != INDICATOR_LENGTH {
+            return Err(RejewskiError::InvalidIndicatorLength);
+        }
+
+        for (offset, product) in products.iter_mut().enumerate() {
+            let image = &mut product[letter_index(letters[offset])];
+            match image {
+                Some(existing) if *existing != letters[offset + 3] => {
+                    return Err(RejewskiError::InconsistentIndicators);
+                }
+                _ => *image = Some(letters[offset + 3]),
+            }
+        }
+    }
+
+    let mut structures = products.iter().map(|product| {
+        let wiring = product
+            .iter()
+            .map(|letter| letter.ok_or(RejewskiError::IncompletePermutation))
+            .collect::<Result<String, _>>()?;
+        let permutation =
+            Permutation::new(&wiring).map_err(|_| RejewskiError::InconsistentIndicators)?;
+
+        Ok(CycleStructure::from(&permutation))
+    });
+
+    Ok(Characteristic {
+        ad: structures.next().unwrap()?,
+        be: structures.next().unwrap()?,
+        cf: structures.next().unwrap()?,
+    })
+}
+
+/// Composes two substitutions, applying `first` and then `second`.
+pub fn compose(first: &Substitution, second: &Substitution) -> Substitution {
+    first.map(|letter| second[letter_index(letter)])
+}
+
+pub(crate) fn machine_permutation(
+    controller: &RotorsController,
+    reflector: &Reflector,
+) -> Result<Substitution, Error> {
+    let mut substitution = [FIRST_LETTER; ALPHABET_SIZE];
+
+    for (image, letter) in substitution.iter_mut().zip(FIRST_LETTER..=LAST_LETTER) {
+        *image = scramble(controller, reflector, letter)?;
+    }
+
+    Ok(substitution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotor::RotorType;
+    use crate::{Enigma, reflectors};
+
+    fn doubled_indicators(ground_setting: [char; 3]) -> Vec<String> {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            rotors::create_rotor_1(),
+            reflectors::create_reflector_b(),
+        );
+
+        (FIRST_LETTER..=LAST_LETTER)
+            .map(|letter| {
+                enigma
+                    .set_left_rotor_position_from_char(ground_setting[0])
+                    .unwrap();
+                enigma
+                    .set_middle_rotor_position_from_char(ground_setting[1])
+                    .unwrap();
+                enigma
+                    .set_right_rotor_position_from_char(ground_setting[2])
+                    .unwrap();
+                enigma.encrypt_str(&letter.to_string().repeat(6)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keypress_permutations_should_be_fixed_point_free_involutions() {
+        let controller = RotorsController::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+        );
+
+        let permutations =
+            keypress_permutations(&controller, &reflectors::create_reflector_b()).unwrap();
+
+        for permutation in permutations {
+            assert_eq!(
+                compose(&permutation, &permutation),
+                [
+                    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
+                    'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'
+                ]
+            );
+            assert!(
+                permutation
+                    .iter()
+                    .zip(FIRST_LETTER..=LAST_LETTER)
+                    .all(|(&image, letter)| image != letter)
+            );
+        }
+    }
+
+    #[test]
+    fn product_cycle_lengths_should_come_in_pairs() {
+        let controller = RotorsController::new(
+            rotors::create_rotor_3(),
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+        );
+
+        let characteristic =
+            characteristic(&controller, &reflectors::create_reflector_b()).unwrap();
+
+        for structure in [characteristic.ad, characteristic.be, characteristic.cf] {
+            assert_eq!(structure.lengths().iter().sum::<usize>(), ALPHABET_SIZE);
+            assert!(
+                structure
+                    .lengths()
+                    .chunks(2)
+                    .all(|pair| pair.len() == 2 && pair[0] == pair[1])
+            );
+        }
+    }
+
+    #[test]
+    fn characteristic_from_indicators_should_match_machine() {
+        let ground_setting = ['K', 'D', 'X'];
+        let mut controller = RotorsController::new(
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            rotors::create_rotor_1(),
+        );
+        controller
+            .set_left_rotor_position_from_char(ground_setting[0])
+            .unwrap();
+        controller
+            .set_middle_rotor_position_from_char(ground_setting[1])
+            .unwrap();
+        controller
+            .set_right_rotor_position_from_char(ground_setting[2])
+            .unwrap();
+
+        assert_eq!(
+            characteristic_from_indicators(&doubled_indicators(ground_setting)).unwrap(),
+            characteristic(&controller, &reflectors::create_reflector_b()).unwrap()
+        );
+    }
+
+    #[test]
+    fn too_few_indicators_should_return_error() {
+        let indicators = &doubled_indicators(['A', 'A', 'A'])[..10];
+
+        assert_eq!(
+            characteristic_from_indicators(indicators),
+            Err(RejewskiError::IncompletePermutation)
+        );
+    }
+
+    #[test]
+    fn inconsistent_indicators_should_return_error() {
+        assert_eq!(
+            characteristic_from_indicators(&["ABCDEF", "ABCXEF"]),
+            Err(RejewskiError::InconsistentIndicators)
+        );
+    }
+
+    #[test]
+    fn catalogue_lookup_should_find_ground_setting() {
+        let wheel_order = [RotorType::Rotor2, RotorType::Rotor3, RotorType::Rotor1];
+        let catalogue =
+            Catalogue::for_wheel_orders(&[wheel_order], reflectors::create_reflector_b()).unwrap();
+
+        let candidates = catalogue
+            .find_candidates(&doubled_indicators(['Q', 'E', 'B']))
+            .unwrap();
+
+        assert!(candidates.contains(&CatalogueEntry {
+            wheel_order,
+            position: ['Q', 'E', 'B'],
+        }));
+    }
+
+    #[test]
+    fn full_catalogue_should_have_historical_size() {
+        let catalogue = Catalogue::for_rotors(
+            &[RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3],
+            reflectors::create_reflector_b(),
+        )
+        .unwrap();
+        let thirteens = || CycleStructure(vec![13, 13]);
+        let largest_class = catalogue.entries.values().map(Vec::len).max();
+
+        assert_eq!(catalogue.len(), 105_456);
+        assert_eq!(catalogue.characteristics_count(), 21_121);
+        // As in the historical catalogue, the most common characteristic splits AD, BE and CF
+        // into two cycles of 13 letters each.
+        let characteristic = Characteristic {
+            ad: thirteens(),
+            be: thirteens(),
+            cf: thirteens(),
+        };
+        assert_eq!(largest_class, Some(1_708));
+        assert_eq!(catalogue.lookup(&characteristic).len(), 1_708);
+    }
+}
@@ -0,0 +1,506 @@
+use std::fmt::{self, Display, Write};
+
+use crate::alphabet::LATIN;
+use crate::analysis::rejewski::{
+    INDICATOR_LENGTH, POSITIONS_PER_WHEEL_ORDER, Substitution, compose, machine_permutation,
+};
+use crate::analysis::{index_letter, letter_index, position_from_index};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::Error;
+use crate::reflectors::Reflector;
+use crate::rotor::RotorType;
+use crate::rotors;
+use crate::rotors_controller::RotorsController;
+
+/// Number of letter pairs that can form a female in a doubled indicator (1-4, 2-5 and 3-6).
+pub const FEMALE_OFFSETS: usize = INDICATOR_LENGTH / 2;
+
+const SVG_CELL_SIZE: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub enum ZygalskiError {
+    NonAlphabetic,
+    InvalidIndicatorLength,
+    /// A female offset past the last of the [`FEMALE_OFFSETS`].
+    InvalidOffset(usize),
+    Machine(Error),
+}
+
+impl Display for ZygalskiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZygalskiError::NonAlphabetic => write!(f, "indicators may only hold letters"),
+            ZygalskiError::InvalidIndicatorLength => write!(
+                f,
+                "an indicator needs a ground setting of 3 letters and a key of {INDICATOR_LENGTH}"
+            ),
+            ZygalskiError::InvalidOffset(offset) => write!(
+                f,
+                "female offset {offset} is out of range, there are {FEMALE_OFFSETS}"
+            ),
+            ZygalskiError::Machine(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ZygalskiError {}
+
+impl From<Error> for ZygalskiError {
+    fn from(error: Error) -> Self {
+        ZygalskiError::Machine(error)
+    }
+}
+
+///
+/// A message indicator as it was sent - the ground setting in clear followed by the doubled message
+/// key enciphered at that ground setting.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indicator {
+    pub ground_setting: [char; 3],
+    pub enciphered_key: [char; INDICATOR_LENGTH],
+}
+
+impl Indicator {
+    pub fn new(ground_setting: &str, enciphered_key: &str) -> Result<Self, ZygalskiError> {
+        Ok(Self {
+            ground_setting: parse_letters(ground_setting)?,
+            enciphered_key: parse_letters(enciphered_key)?,
+        })
+    }
+
+    /// Returns the offsets (0 for 1-4, 1 for 2-5, 2 for 3-6) at which the indicator has a female.
+    pub fn female_offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..FEMALE_OFFSETS).filter(|&offset| {
+            self.enciphered_key[offset] == self.enciphered_key[offset + FEMALE_OFFSETS]
+        })
+    }
+}
+
+///
+/// Records, for every start position of one wheel order, at which offsets a female can occur.
+///
+/// A female at offset `i` is possible exactly when the product of the permutations applied at
+/// keypresses `i` and `i + 3` has a fixed point. The sheets are punched for ring setting 'A', where
+/// core positions and the positions shown in the window agree. The turnovers while typing the
+/// indicator follow the positions shown, so [`search`] models them for every ring setting it tries.
+///
+#[derive(Debug, Clone)]
+pub struct SheetSet {
+    wheel_order: [RotorType; 3],
+    controller: RotorsController,
+    /// The permutation the machine applies at every core position, without stepping.
+    permutations: Vec<Substitution>,
+    females: Vec<u8>,
+}
+
+impl SheetSet {
+    pub fn new(wheel_order: [RotorType; 3], reflector: Reflector) -> Result<Self, Error> {
+        let mut controller = RotorsController::new(
+            rotors::create_rotor(wheel_order[0])?,
+            rotors::create_rotor(wheel_order[1])?,
+            rotors::create_rotor(wheel_order[2])?,
+        );
+        let mut permutations = Vec::with_capacity(POSITIONS_PER_WHEEL_ORDER);
+        for index in 0..POSITIONS_PER_WHEEL_ORDER {
+            set_positions(&mut controller, position_from_index(index))?;
+            permutations.push(machine_permutation(&controller, &reflector)?);
+        }
+
+        let mut sheet_set = Self {
+            wheel_order,
+            controller,
+            permutations,
+            females: vec![0; POSITIONS_PER_WHEEL_ORDER],
+        };
+        for index in 0..POSITIONS_PER_WHEEL_ORDER {
+            let positions = sheet_set.keypress_positions(position_from_index(index))?;
+            for offset in 0..FEMALE_OFFSETS {
+                if sheet_set.female_possible(&positions, offset, [0; 3]) {
+                    sheet_set.females[index] |= 1 << offset;
+                }
+            }
+        }
+
+        Ok(sheet_set)
+    }
+
+    pub fn wheel_order(&self) -> [RotorType; 3] {
+        self.wheel_order
+    }
+
+    ///
+    /// Whether a female can occur at `offset` when the rotors start at `position`, given as core
+    /// positions from 0 to 25.
+    ///
+    pub fn has_female(&self, position: [usize; 3], offset: usize) -> Result<bool, ZygalskiError> {
+        if let Some(&position) = position.iter().find(|&&index| index >= ALPHABET_SIZE) {
+            return Err(ZygalskiError::Machine(Error::PositionOutOfRange {
+                position,
+                size: ALPHABET_SIZE,
+            }));
+        }
+        check_offset(offset)?;
+
+        Ok(self.female_at(position, offset))
+    }
+
+    /// Returns the sheet punched for the given left rotor position and female offset.
+    pub fn sheet(
+        &self,
+        left_position: char,
+        offset: usize,
+    ) -> Result<ZygalskiSheet, ZygalskiError> {
+        let left = LATIN
+            .index_of(left_position.to_ascii_uppercase())
+            .ok_or(Error::InvalidLetter(left_position))?;
+        check_offset(offset)?;
+        let mut holes = [[false; ALPHABET_SIZE]; ALPHABET_SIZE];
+
+        for (middle, row) in holes.iter_mut().enumerate() {
+            for (right, hole) in row.iter_mut().enumerate() {
+                *hole = self.female_at([left, middle, right], offset);
+            }
+        }
+
+        Ok(ZygalskiSheet {
+            wheel_order: self.wheel_order,
+            left_position: index_letter(left),
+            offset,
+            holes,
+        })
+    }
+
+    fn female_at(&self, position: [usize; 3], offset: usize) -> bool {
+        self.females[index_from_position(position)] & (1 << offset) != 0
+    }
+
+    ///
+    /// Returns the positions the rotors show at each keypress of an indicator typed from `start`.
+    /// The notches sit on the rings, so these don't depend on the ring settings.
+    ///
+    fn keypress_positions(
+        &self,
+        start: [usize; 3],
+    ) -> Result<[[usize; 3]; INDICATOR_LENGTH], Error> {
+        let mut controller = self.controller.clone();
+        set_positions(&mut controller, start)?;
+        let mut positions = [[0; 3]; INDICATOR_LENGTH];
+
+        for keypress in positions.iter_mut() {
+            controller.increment();
+            for (position, letter) in keypress.iter_mut().zip(controller.get_positions()) {
+                *position = letter_index(letter);
+            }
+        }
+
+        Ok(positions)
+    }
+
+    /// Whether the keypresses at `offset` and `offset + 3` can encipher a letter alike.
+    fn female_possible(
+        &self,
+        positions: &[[usize; 3]; INDICATOR_LENGTH],
+        offset: usize,
+        ring_settings: [usize; 3],
+    ) -> bool {
+        let core_permutation = |position: [usize; 3]| {
+            let core = [0, 1, 2].map(|rotor| {
+                (position[rotor] + ALPHABET_SIZE - ring_settings[rotor]) % ALPHABET_SIZE
+            });
+            &self.permutations[index_from_position(core)]
+        };
+        let product = compose(
+            core_permutation(positions[offset]),
+            core_permutation(positions[offset + FEMALE_OFFSETS]),
+        );
+
+        product
+            .iter()
+            .enumerate()
+            .any(|(letter, &image)| letter_index(image) == letter)
+    }
+}
+
+///
+/// A single perforated sheet. Rows are middle rotor positions and columns are right rotor positions;
+/// a hole marks a position at which a female can occur.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZygalskiSheet {
+    pub wheel_order: [RotorType; 3],
+    pub left_position: char,
+    pub offset: usize,
+    pub holes: [[bool; ALPHABET_SIZE]; ALPHABET_SIZE],
+}
+
+impl ZygalskiSheet {
+    pub fn holes_count(&self) -> usize {
+        self.holes.iter().flatten().filter(|&&hole| hole).count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("  ");
+        (0..ALPHABET_SIZE).for_each(|right| text.push(index_letter(right)));
+        text.push('\n');
+
+        for (middle, row) in self.holes.iter().enumerate() {
+            text.push(index_letter(middle));
+            text.push(' ');
+            row.iter()
+                .for_each(|&hole| text.push(if hole { '#' } else { '.' }));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Renders the sheet as a plain PBM image, with a black pixel for every hole.
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{ALPHABET_SIZE} {ALPHABET_SIZE}\n");
+
+        for row in self.holes.iter() {
+            let pixels: Vec<&str> = row
+                .iter()
+                .map(|&hole| if hole { "1" } else { "0" })
+                .collect();
+            pbm.push_str(&pixels.join(" "));
+            pbm.push('\n');
+        }
+
+        pbm
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = SVG_CELL_SIZE * (ALPHABET_SIZE + 1);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" font-size=\"{}\">\n",
+            SVG_CELL_SIZE - 2
+        );
+
+        for index in 0..ALPHABET_SIZE {
+            let offset = SVG_CELL_SIZE * (index + 1);
+            let _ = writeln!(
+                svg,
+                "<text x=\"{offset}\" y=\"{}\">{}</text>",
+                SVG_CELL_SIZE - 2,
+                index_letter(index)
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"0\" y=\"{}\">{}</text>",
+                offset + SVG_CELL_SIZE - 2,
+                index_letter(index)
+            );
+        }
+
+        for (middle, row) in self.holes.iter().enumerate() {
+            for (right, _) in row.iter().enumerate().filter(|&(_, &hole)| hole) {
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{SVG_CELL_SIZE}\" height=\"{SVG_CELL_SIZE}\"/>",
+                    SVG_CELL_SIZE * (right + 1),
+                    SVG_CELL_SIZE * (middle + 1)
+                );
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub wheel_order: [RotorType; 3],
+    pub ring_settings: [char; 3],
+}
+
+///
+/// Stacks the sheets for every female indicator and returns the wheel orders and ring settings for
+/// which all females line up over a hole.
+///
+/// The rotor core position at each keypress is the position shown in the window minus the ring
+/// setting, so each ring setting candidate shifts every sheet by the indicator's ground setting.
+/// The turnovers while typing an indicator follow the positions shown, whatever the ring setting.
+///
+pub fn search(sheet_sets: &[SheetSet], indicators: &[Indicator]) -> Result<Vec<Candidate>, Error> {
+    let mut candidates = Vec::new();
+
+    for sheet_set in sheet_sets {
+        let mut females = Vec::new();
+        for indicator in indicators {
+            let positions =
+                sheet_set.keypress_positions(indicator.ground_setting.map(letter_index))?;
+            females.extend(indicator.female_offsets().map(|offset| (positions, offset)));
+        }
+
+        for ring_index in 0..POSITIONS_PER_WHEEL_ORDER {
+            let ring_settings = position_from_index(ring_index);
+
+            let all_females_match = females.iter().all(|(positions, offset)| {
+                sheet_set.female_possible(positions, *offset, ring_settings)
+            });
+
+            if all_females_match {
+                candidates.push(Candidate {
+                    wheel_order: sheet_set.wheel_order,
+                    ring_settings: ring_settings.map(index_letter),
+                });
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn parse_letters<const N: usize>(text: &str) -> Result<[char; N], ZygalskiError> {
+    let mut letters = [' '; N];
+    let mut count = 0;
+
+    for letter in text.chars() {
+        if !letter.is_ascii_alphabetic() {
+            return Err(ZygalskiError::NonAlphabetic);
+        }
+        if count == N {
+            return Err(ZygalskiError::InvalidIndicatorLength);
+        }
+
+        letters[count] = letter.to_ascii_uppercase();
+        count += 1;
+    }
+
+    match count == N {
+        true => Ok(letters),
+        false => Err(ZygalskiError::InvalidIndicatorLength),
+    }
+}
+
+fn check_offset(offset: usize) -> Result<(), ZygalskiError> {
+    match offset < FEMALE_OFFSETS {
+        true => Ok(()),
+        false => Err(ZygalskiError::InvalidOffset(offset)),
+    }
+}
+
+fn set_positions(controller: &mut RotorsController, position: [usize; 3]) -> Result<(), Error> {
+    controller.set_left_rotor_position_from_int(position[0])?;
+    controller.set_middle_rotor_position_from_int(position[1])?;
+    controller.set_right_rotor_position_from_int(position[2])
+}
+
+fn index_from_position(position: [usize; 3]) -> usize {
+    (position[0] * ALPHABET_SIZE + position[1]) * ALPHABET_SIZE + position[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Enigma, reflectors};
+
+    const WHEEL_ORDER: [RotorType; 3] = [RotorType::Rotor2, RotorType::Rotor3, RotorType::Rotor1];
+
+    fn intercepted_indicators(ring_settings: [char; 3], count: usize) -> Vec<Indicator> {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor(WHEEL_ORDER[0]).unwrap(),
+            rotors::create_rotor(WHEEL_ORDER[1]).unwrap(),
+            rotors::create_rotor(WHEEL_ORDER[2]).unwrap(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_ring_settings(&ring_settings).unwrap();
+        let mut seed: usize = 17;
+        let mut next_letter = || {
+            seed = (seed * 1_103_515_245 + 12_345) % (1 << 31);
+            index_letter((seed >> 16) % ALPHABET_SIZE)
+        };
+
+        (0..count)
+            .map(|_| {
+                let ground_setting = [next_letter(), next_letter(), next_letter()];
+                let key: String = (0..3).map(|_| next_letter()).collect();
+
+                enigma.set_rotor_positions(&ground_setting).unwrap();
+                let enciphered = enigma.encrypt_str(&key.repeat(2)).unwrap();
+
+                let ground_setting: String = ground_setting.iter().collect();
+                Indicator::new(&ground_setting, &enciphered).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn indicator_should_report_female_offsets() {
+        let indicator = Indicator::new("abc", "QWEQXE").unwrap();
+
+        assert_eq!(indicator.female_offsets().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn indicator_with_wrong_length_should_return_error() {
+        assert_eq!(
+            Indicator::new("ABC", "QWEQX"),
+            Err(ZygalskiError::InvalidIndicatorLength)
+        );
+        assert_eq!(
+            Indicator::new("A1C", "QWEQXE"),
+            Err(ZygalskiError::NonAlphabetic)
+        );
+    }
+
+    #[test]
+    fn sheet_renderings_should_have_one_cell_per_position() {
+        let sheet_set = SheetSet::new(WHEEL_ORDER, reflectors::create_reflector_b()).unwrap();
+        let sheet = sheet_set.sheet('c', 0).unwrap();
+
+        let text = sheet.to_text();
+        assert_eq!(text.lines().count(), ALPHABET_SIZE + 1);
+        assert_eq!(text.matches('#').count(), sheet.holes_count());
+
+        let pbm = sheet.to_pbm();
+        assert!(pbm.starts_with("P1\n26 26\n"));
+        assert_eq!(pbm.matches('1').count() - 1, sheet.holes_count());
+
+        let svg = sheet.to_svg();
+        assert_eq!(svg.matches("<rect").count(), sheet.holes_count());
+    }
+
+    #[test]
+    fn sheet_lookups_should_reject_invalid_positions_and_offsets() {
+        let sheet_set = SheetSet::new(WHEEL_ORDER, reflectors::create_reflector_b()).unwrap();
+
+        assert!(sheet_set.has_female([0, 25, 3], 2).is_ok());
+        assert_eq!(
+            sheet_set.has_female([0, 26, 3], 0),
+            Err(ZygalskiError::Machine(Error::PositionOutOfRange {
+                position: 26,
+                size: ALPHABET_SIZE
+            }))
+        );
+        assert_eq!(
+            sheet_set.has_female([0, 0, 0], 3),
+            Err(ZygalskiError::InvalidOffset(3))
+        );
+        assert_eq!(
+            sheet_set.sheet('1', 0).unwrap_err(),
+            ZygalskiError::Machine(Error::InvalidLetter('1'))
+        );
+        assert_eq!(
+            sheet_set.sheet('A', 8).unwrap_err(),
+            ZygalskiError::InvalidOffset(8)
+        );
+    }
+
+    #[test]
+    fn stacking_should_find_ring_settings() {
+        let ring_settings = ['D', 'K', 'R'];
+        let sheet_set = SheetSet::new(WHEEL_ORDER, reflectors::create_reflector_b()).unwrap();
+
+        let candidates = search(&[sheet_set], &intercepted_indicators(ring_settings, 300)).unwrap();
+
+        assert!(candidates.len() < 10);
+        assert!(candidates.contains(&Candidate {
+            wheel_order: WHEEL_ORDER,
+            ring_settings,
+        }));
+    }
+}
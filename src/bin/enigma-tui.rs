@@ -0,0 +1,479 @@
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::{Command, ExitCode, Stdio};
+
+use enigma::Enigma;
+use enigma::keys::DailyKey;
+
+const DEFAULT_KEY: &str = "model = Enigma-I; reflector = B; rotors = I II III";
+const LAMPBOARD: [&str; 3] = ["QWERTZUIO", "ASDFGHJK", "PYXCVBNML"];
+const SHOWN_LETTERS: usize = 60;
+
+const USAGE: &str = "\
+Usage: enigma-tui [KEY]
+
+Opens a replica of the machine set up with KEY, written like \"model = M3; reflector = B;
+rotors = I II III; rings = 01 01 01; plugboard = AB CD; positions = AAA\". Without a key an
+Enigma I with reflector B and rotors I II III is used.";
+
+const HELP: &str = "letters: encipher   tab: select rotor   up/down: turn rotor   +/-: ring   \
+/: plug cable   backspace: undo   ctrl-c: quit";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Letter(char),
+    Up,
+    Down,
+    Tab,
+    RingUp,
+    RingDown,
+    Plug,
+    Backspace,
+    Quit,
+    Other,
+}
+
+impl Key {
+    ///
+    /// Reads a key press from a terminal in raw mode, where arrow keys arrive as `ESC [ A`. Reads
+    /// time out in raw mode, so a lone Esc, which no sequence follows, reads as [`Key::Other`]
+    /// instead of waiting for the next key. Returns `None` when no key was pressed in time.
+    ///
+    fn read(input: &mut impl Read) -> io::Result<Option<Key>> {
+        let Some(byte) = read_byte(input)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(match byte {
+            b'a'..=b'z' | b'A'..=b'Z' => Key::Letter(byte.to_ascii_uppercase() as char),
+            b'\t' => Key::Tab,
+            b'+' | b'=' => Key::RingUp,
+            b'-' => Key::RingDown,
+            b'/' => Key::Plug,
+            0x08 | 0x7f => Key::Backspace,
+            0x03 | 0x04 => Key::Quit,
+            0x1b => match read_byte(input)? {
+                Some(b'[') => match read_byte(input)? {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    _ => Key::Other,
+                },
+                _ => Key::Other,
+            },
+            _ => Key::Other,
+        }))
+    }
+}
+
+/// Reads one byte, or `None` when the read timed out or the input ended.
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// The positions of the rotors and reflector before a key press, to step back on backspace.
+#[derive(Debug, Clone)]
+struct Undo {
+    positions: Vec<char>,
+    reflector_position: char,
+}
+
+///
+/// The state of the replica: the machine, what was typed and lit, and the operator's selections.
+/// Key handling and rendering are kept free of terminal I/O.
+///
+#[derive(Debug)]
+struct Replica {
+    key: DailyKey,
+    enigma: Enigma,
+    selected_rotor: usize,
+    lit: Option<char>,
+    input: String,
+    output: String,
+    history: Vec<Undo>,
+    plugging: Option<Option<char>>,
+    status: String,
+}
+
+impl Replica {
+    fn new(key: DailyKey) -> Result<Self, String> {
+        let enigma = key.create_enigma().map_err(|error| error.to_string())?;
+
+        Ok(Self {
+            selected_rotor: enigma.rotor_count() - 1,
+            key,
+            enigma,
+            lit: None,
+            input: String::new(),
+            output: String::new(),
+            history: Vec::new(),
+            plugging: None,
+            status: String::new(),
+        })
+    }
+
+    /// Handles a key press and returns whether the replica keeps running.
+    fn handle(&mut self, key: Key) -> bool {
+        self.status.clear();
+        if !matches!(key, Key::Letter(_)) {
+            self.lit = None;
+        }
+
+        match (key, self.plugging) {
+            (Key::Letter(letter), Some(first)) => self.plug(first, letter),
+            (Key::Letter(letter), None) => self.press(letter),
+            (Key::Plug, Some(_)) => {
+                self.plugging = None;
+                self.status = "Plugging cancelled".to_string();
+            }
+            (Key::Plug, None) if self.key.model.has_plugboard() => {
+                self.plugging = Some(None);
+                self.status = "Type the two letters to connect".to_string();
+            }
+            (Key::Plug, None) => self.status = format!("The {} has no plugboard", self.key.model),
+            (Key::Tab, _) => self.selected_rotor = (self.selected_rotor + 1) % self.rotor_count(),
+            (Key::Up, _) => self.turn_selected_rotor(1),
+            (Key::Down, _) => self.turn_selected_rotor(25),
+            (Key::RingUp, _) => self.turn_selected_ring(1),
+            (Key::RingDown, _) => self.turn_selected_ring(25),
+            (Key::Backspace, _) => self.undo(),
+            (Key::Quit, _) => return false,
+            (Key::Other, _) => {}
+        }
+
+        true
+    }
+
+    fn rotor_count(&self) -> usize {
+        self.enigma.rotor_count()
+    }
+
+    fn press(&mut self, letter: char) {
+        self.history.push(Undo {
+            positions: self.enigma.get_rotor_positions(),
+            reflector_position: self.enigma.get_reflector_position(),
+        });
+        let lit = self
+            .enigma
+            .encrypt_char(letter)
+            .expect("letters are in the machine's alphabet");
+
+        self.input.push(letter);
+        self.output.push(lit);
+        self.lit = Some(lit);
+    }
+
+    /// Steps the machine back to where it was before the last key press.
+    fn undo(&mut self) {
+        let Some(undo) = self.history.pop() else {
+            self.status = "Nothing to undo".to_string();
+            return;
+        };
+
+        self.enigma
+            .set_rotor_positions(&undo.positions)
+            .and_then(|()| self.enigma.set_reflector_position(undo.reflector_position))
+            .expect("the saved positions came from this machine");
+        self.input.pop();
+        self.output.pop();
+    }
+
+    /// Plugs a cable once both letters are typed, or pulls the cable when a letter is typed twice.
+    fn plug(&mut self, first: Option<char>, letter: char) {
+        let Some(first) = first else {
+            self.plugging = Some(Some(letter));
+            return;
+        };
+
+        self.plugging = None;
+        if first == letter {
+            self.enigma.clear_transposition(letter);
+            self.status = format!("Unplugged {letter}");
+        } else {
+            self.status = match self.enigma.set_transposition(first, letter) {
+                Ok(()) => format!("Plugged {first}{letter}"),
+                Err(error) => format!("Can't plug {first}{letter}: {error}"),
+            };
+        }
+    }
+
+    /// Turning by hand starts a new message, so the key presses can no longer be undone.
+    fn turn_selected_rotor(&mut self, amount: u8) {
+        let mut positions = self.enigma.get_rotor_positions();
+        positions[self.selected_rotor] = rotate(positions[self.selected_rotor], amount);
+        self.enigma
+            .set_rotor_positions(&positions)
+            .expect("turned positions stay in the alphabet");
+        self.history.clear();
+    }
+
+    fn turn_selected_ring(&mut self, amount: u8) {
+        let mut rings = self.enigma.get_ring_settings();
+        rings[self.selected_rotor] = rotate(rings[self.selected_rotor], amount);
+        self.enigma
+            .set_ring_settings(&rings)
+            .expect("turned rings stay in the alphabet");
+        self.history.clear();
+    }
+
+    fn render(&self) -> String {
+        let mut screen = String::new();
+        let [left, middle, right] = self.key.wheel_order;
+        let _ = writeln!(
+            screen,
+            "  {}   {}   {left} {middle} {right}\n",
+            self.key.model, self.key.reflector
+        );
+
+        let windows: Vec<String> = self
+            .enigma
+            .get_rotor_positions()
+            .iter()
+            .map(|position| format!("[ {position} ]"))
+            .collect();
+        let rings: Vec<String> = self
+            .enigma
+            .get_ring_settings()
+            .iter()
+            .map(|&ring| format!("  {:02} ", ring as u8 - b'A' + 1))
+            .collect();
+        let selected: Vec<&str> = (0..self.rotor_count())
+            .map(|index| match index == self.selected_rotor {
+                true => "  ^  ",
+                false => "     ",
+            })
+            .collect();
+        let _ = writeln!(screen, "  Rotors     {}", windows.join(" "));
+        let _ = writeln!(screen, "  Rings      {}", rings.join(" "));
+        let _ = writeln!(screen, "             {}\n", selected.join(" "));
+
+        for (row, letters) in LAMPBOARD.iter().enumerate() {
+            let _ = write!(screen, "  {}", " ".repeat(row + 2));
+            for letter in letters.chars() {
+                let _ = match Some(letter) == self.lit {
+                    true => write!(screen, "\x1b[30;43m {letter} \x1b[0m "),
+                    false => write!(screen, "({letter}) "),
+                };
+            }
+            screen.push('\n');
+        }
+
+        let mut plugs: Vec<String> = self
+            .enigma
+            .get_transpositions()
+            .iter()
+            .filter(|(first, second)| first < second)
+            .map(|(first, second)| format!("{first}{second}"))
+            .collect();
+        plugs.sort();
+        let _ = writeln!(screen, "\n  Plugboard  {}", plugs.join(" "));
+        let _ = writeln!(screen, "  Input      {}", last_letters(&self.input));
+        let _ = writeln!(screen, "  Output     {}\n", last_letters(&self.output));
+        let _ = writeln!(screen, "  {}", self.status);
+        let _ = write!(screen, "  {HELP}");
+
+        screen
+    }
+}
+
+fn rotate(letter: char, amount: u8) -> char {
+    ((letter as u8 - b'A' + amount) % 26 + b'A') as char
+}
+
+fn last_letters(text: &str) -> &str {
+    &text[text.len().saturating_sub(SHOWN_LETTERS)..]
+}
+
+///
+/// Puts the terminal into raw mode with `stty` and restores its previous settings when dropped.
+/// Reads return after a tenth of a second without input, so escape sequences can be told apart
+/// from a lone Esc.
+///
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => Err(io::Error::other("stty failed, is stdin a terminal?")),
+    }
+}
+
+fn draw(out: &mut impl Write, replica: &Replica) -> io::Result<()> {
+    // Raw mode doesn't return the carriage on a line feed.
+    let screen = replica.render().replace('\n', "\r\n");
+    write!(out, "\x1b[2J\x1b[H{screen}")?;
+    out.flush()
+}
+
+fn run(mut replica: Replica) -> io::Result<()> {
+    let _raw_mode = RawMode::enable()?;
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "\x1b[?25l")?;
+
+    draw(&mut stdout, &replica)?;
+    loop {
+        let Some(key) = Key::read(&mut stdin)? else {
+            continue;
+        };
+        if !replica.handle(key) {
+            break;
+        }
+        draw(&mut stdout, &replica)?;
+    }
+
+    write!(stdout, "\x1b[?25h\x1b[2J\x1b[H")?;
+    stdout.flush()
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let key = match args.as_slice() {
+        [] => DEFAULT_KEY,
+        [flag] if flag == "--help" || flag == "-h" => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        [key] => key.as_str(),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let replica = key
+        .parse::<DailyKey>()
+        .map_err(|error| error.to_string())
+        .and_then(Replica::new);
+    let result = match replica {
+        Ok(replica) => run(replica).map_err(|error| error.to_string()),
+        Err(error) => Err(format!("invalid key: {error}")),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("enigma-tui: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replica() -> Replica {
+        Replica::new(DEFAULT_KEY.parse().unwrap()).unwrap()
+    }
+
+    fn type_keys(replica: &mut Replica, keys: &[Key]) {
+        for &key in keys {
+            assert!(replica.handle(key));
+        }
+    }
+
+    #[test]
+    fn keys_should_be_read_from_raw_bytes() {
+        let mut input: &[u8] = b"q\x1b[A\x1b[B\x7f\x03";
+
+        let keys: Vec<Option<Key>> = (0..6).map(|_| Key::read(&mut input).unwrap()).collect();
+
+        assert_eq!(
+            keys,
+            [
+                Some(Key::Letter('Q')),
+                Some(Key::Up),
+                Some(Key::Down),
+                Some(Key::Backspace),
+                Some(Key::Quit),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn lone_escape_should_not_wait_for_a_sequence() {
+        let mut input: &[u8] = b"\x1b";
+        assert_eq!(Key::read(&mut input).unwrap(), Some(Key::Other));
+
+        let mut input: &[u8] = b"\x1b[Ca";
+        assert_eq!(Key::read(&mut input).unwrap(), Some(Key::Other));
+        assert_eq!(Key::read(&mut input).unwrap(), Some(Key::Letter('A')));
+    }
+
+    #[test]
+    fn typing_should_light_lamps_and_backspace_should_step_back() {
+        let mut replica = replica();
+
+        type_keys(
+            &mut replica,
+            &"AAAAA".chars().map(Key::Letter).collect::<Vec<_>>(),
+        );
+        assert_eq!(replica.output, "BDZGO");
+        assert_eq!(replica.lit, Some('O'));
+        assert!(replica.render().contains("\x1b[30;43m O \x1b[0m"));
+
+        type_keys(&mut replica, &[Key::Backspace, Key::Backspace]);
+        assert_eq!(replica.output, "BDZ");
+        assert_eq!(replica.enigma.get_rotor_positions(), ['A', 'A', 'D']);
+
+        type_keys(&mut replica, &[Key::Letter('A')]);
+        assert_eq!(replica.output, "BDZG");
+    }
+
+    #[test]
+    fn rotors_rings_and_plugs_should_be_set_from_the_keyboard() {
+        let mut replica = replica();
+
+        type_keys(
+            &mut replica,
+            &[
+                Key::Tab,
+                Key::Up,
+                Key::Up,
+                Key::RingDown,
+                Key::Plug,
+                Key::Letter('A'),
+                Key::Letter('Q'),
+            ],
+        );
+
+        assert_eq!(replica.enigma.get_rotor_positions(), ['C', 'A', 'A']);
+        assert_eq!(replica.enigma.get_ring_settings(), ['Z', 'A', 'A']);
+        assert_eq!(replica.enigma.get_transpositions().get(&'A'), Some(&'Q'));
+        assert!(replica.render().contains("Plugboard  AQ"));
+        assert!(!replica.handle(Key::Quit));
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt::{self, Display};
+
+use crate::Enigma;
+use crate::catalogue::{self, CatalogueError};
+use crate::entry_wheels;
+use crate::error::Error;
+use crate::models::{MachineModel, ModelError};
+use crate::rotor::RotorType;
+use crate::rotors_controller::RotorRole;
+
+/// The number of rotors every [`MachineModel`] takes.
+const MODEL_ROTORS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuilderError {
+    MissingRotors,
+    /// Machines without a model need their reflector named.
+    MissingReflector,
+    WrongRotorCount {
+        expected: usize,
+        got: usize,
+    },
+    /// A ring given as a number that isn't between 1 and 26.
+    InvalidRing(String),
+    /// A plugboard cable that isn't written as two different letters.
+    InvalidPlug(String),
+    /// Plugs were given for a model without a plugboard.
+    NoPlugboard(MachineModel),
+    /// A reflector position was given for a model whose reflector can't be turned.
+    FixedReflector(MachineModel),
+    Catalogue(CatalogueError),
+    Model(ModelError),
+    Machine(Error),
+}
+
+impl Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::MissingRotors => write!(f, "no rotors were given"),
+            BuilderError::MissingReflector => {
+                write!(f, "a machine without a model needs a reflector")
+            }
+            BuilderError::WrongRotorCount { expected, got } => {
+                write!(f, "expected {expected} rotors but got {got}")
+            }
+            BuilderError::InvalidRing(ring) => write!(f, "invalid ring setting '{ring}'"),
+            BuilderError::InvalidPlug(pair) => write!(f, "invalid plugboard cable '{pair}'"),
+            BuilderError::NoPlugboard(model) => write!(f, "the {model} has no plugboard"),
+            BuilderError::FixedReflector(model) => write!(f, "the {model} has a fixed reflector"),
+            BuilderError::Catalogue(error) => error.fmt(f),
+            BuilderError::Model(error) => error.fmt(f),
+            BuilderError::Machine(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BuilderError {}
+
+impl From<CatalogueError> for BuilderError {
+    fn from(error: CatalogueError) -> Self {
+        BuilderError::Catalogue(error)
+    }
+}
+
+impl From<ModelError> for BuilderError {
+    fn from(error: ModelError) -> Self {
+        BuilderError::Model(error)
+    }
+}
+
+impl From<Error> for BuilderError {
+    fn from(error: Error) -> Self {
+        BuilderError::Machine(error)
+    }
+}
+
+///
+/// Assembles a machine from the names of its parts and its settings, e.g.
+/// `EnigmaBuilder::new().model(MachineModel::M3).rotors(["I", "II", "III"]).positions("AAA")
+/// .plugboard("AB CD").build()`.
+///
+/// Nothing is checked until [`build`](Self::build), which reports the first problem found. With a
+/// model the rotors and reflector must be ones it accepts, the reflector defaults to its first one
+/// and plugs and reflector positions are only allowed where the model has them. Without a model any
+/// Latin rotors can be stacked, with Beta and Gamma as greek wheels left of the stepping rotors.
+///
+#[derive(Debug, Clone, Default)]
+pub struct EnigmaBuilder {
+    model: Option<MachineModel>,
+    rotors: Vec<String>,
+    reflector: Option<String>,
+    reflector_position: Option<char>,
+    rings: Option<String>,
+    positions: Option<String>,
+    plugboard: Option<String>,
+}
+
+impl EnigmaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn model(mut self, model: MachineModel) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// The rotors by their historical names, leftmost first.
+    pub fn rotors<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.rotors = names
+            .into_iter()
+            .map(|name| name.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    pub fn reflector(mut self, name: &str) -> Self {
+        self.reflector = Some(name.to_string());
+        self
+    }
+
+    pub fn reflector_position(mut self, position: char) -> Self {
+        self.reflector_position = Some(position);
+        self
+    }
+
+    ///
+    /// The ring settings, leftmost first, as letters like `"BUL"` or as numbers from 01 to 26 the
+    /// way key sheets print them, like `"02 21 12"`. Rings default to `A`.
+    ///
+    pub fn rings(mut self, rings: &str) -> Self {
+        self.rings = Some(rings.to_string());
+        self
+    }
+
+    /// The rotor positions as letters, leftmost first. Positions default to `A`.
+    pub fn positions(mut self, positions: &str) -> Self {
+        self.positions = Some(positions.to_string());
+        self
+    }
+
+    /// The plugboard cables as pairs of letters separated by spaces, e.g. `"AB CD"`.
+    pub fn plugboard(mut self, cables: &str) -> Self {
+        self.plugboard = Some(cables.to_string());
+        self
+    }
+
+    pub fn build(&self) -> Result<Enigma, BuilderError> {
+        if self.rotors.is_empty() {
+            return Err(BuilderError::MissingRotors);
+        }
+        let wheel_order = self
+            .rotors
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<RotorType>, _>>()?;
+
+        let mut enigma = match self.model {
+            Some(model) => self.build_model(model, wheel_order)?,
+            None => self.build_stack(wheel_order)?,
+        };
+
+        if let Some(rings) = &self.rings {
+            let rings = crate::parse_rings(rings).map_err(|invalid| match invalid.error {
+                Some(error) => BuilderError::Machine(error),
+                None => BuilderError::InvalidRing(invalid.value.to_string()),
+            })?;
+            enigma.set_ring_settings(&rings)?;
+        }
+        if let Some(positions) = &self.positions {
+            let positions: Vec<char> = positions.chars().filter(|c| !c.is_whitespace()).collect();
+            enigma.set_rotor_positions(&positions)?;
+        }
+        if let Some(position) = self.reflector_position {
+            if let Some(model) = self.model.filter(|model| !model.has_settable_reflector()) {
+                return Err(BuilderError::FixedReflector(model));
+            }
+            enigma.set_reflector_position(position)?;
+        }
+        if let Some(cables) = &self.plugboard {
+            if let Some(model) = self.model.filter(|model| !model.has_plugboard())
+                && !cables.trim().is_empty()
+            {
+                return Err(BuilderError::NoPlugboard(model));
+            }
+            let cables = crate::parse_plugboard(cables).map_err(|invalid| match invalid.error {
+                Some(error) => BuilderError::Machine(error),
+                None => BuilderError::InvalidPlug(invalid.value.to_string()),
+            })?;
+            for (first, second) in cables {
+                enigma.set_transposition(first, second)?;
+            }
+        }
+
+        Ok(enigma)
+    }
+
+    fn build_model(
+        &self,
+        model: MachineModel,
+        wheel_order: Vec<RotorType>,
+    ) -> Result<Enigma, BuilderError> {
+        let reflector = match &self.reflector {
+            Some(name) => name.parse()?,
+            None => model.allowed_reflectors()[0],
+        };
+        let got = wheel_order.len();
+        let wheel_order = wheel_order
+            .try_into()
+            .map_err(|_| BuilderError::WrongRotorCount {
+                expected: MODEL_ROTORS,
+                got,
+            })?;
+
+        Ok(model.create(wheel_order, reflector)?)
+    }
+
+    fn build_stack(&self, wheel_order: Vec<RotorType>) -> Result<Enigma, BuilderError> {
+        let reflector = self
+            .reflector
+            .as_deref()
+            .ok_or(BuilderError::MissingReflector)?;
+        let reflector = catalogue::reflector(reflector)?;
+
+        let mut stack = Vec::with_capacity(wheel_order.len());
+        for (index, &typ) in wheel_order.iter().enumerate() {
+            if wheel_order[..index].contains(&typ) {
+                return Err(ModelError::RepeatedRotor(typ).into());
+            }
+            let role = match typ {
+                RotorType::Beta | RotorType::Gamma => RotorRole::GreekWheel,
+                _ => RotorRole::Stepping,
+            };
+            stack.push((catalogue::rotor(&self.rotors[index])?, role));
+        }
+
+        Ok(Enigma::with_rotor_stack(
+            stack,
+            reflector,
+            entry_wheels::create_entry_wheel_identity(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_should_match_the_historical_vector() {
+        let mut enigma = EnigmaBuilder::new()
+            .model(MachineModel::M3)
+            .rotors(["I", "II", "III"])
+            .positions("AAA")
+            .build()
+            .unwrap();
+
+        assert_eq!(enigma.encrypt_str("AAAAA").unwrap(), "BDZGO");
+    }
+
+    #[test]
+    fn builder_should_apply_every_setting() {
+        let enigma = EnigmaBuilder::new()
+            .model(MachineModel::EnigmaI)
+            .rotors(["II", "IV", "V"])
+            .reflector("UKW-C")
+            .rings("02 21 12")
+            .positions("blA")
+            .plugboard("AV BS cg")
+            .build()
+            .unwrap();
+
+        assert_eq!(enigma.get_ring_settings(), vec!['B', 'U', 'L']);
+        assert_eq!(enigma.get_rotor_positions(), vec!['B', 'L', 'A']);
+        assert_eq!(enigma.get_transpositions().len(), 6);
+        assert_eq!(enigma.get_transpositions()[&'G'], 'C');
+    }
+
+    #[test]
+    fn builder_without_model_should_stack_greek_wheels_left() {
+        let enigma = EnigmaBuilder::new()
+            .rotors(["Beta", "II", "IV", "I"])
+            .reflector("B-thin")
+            .positions("AVJN")
+            .build()
+            .unwrap();
+
+        assert_eq!(enigma.rotor_count(), 4);
+        assert_eq!(
+            EnigmaBuilder::new()
+                .rotors(["II", "Beta", "IV", "I"])
+                .reflector("B-thin")
+                .build()
+                .err(),
+            Some(BuilderError::Machine(Error::InvalidRotorStack))
+        );
+    }
+
+    #[test]
+    fn builder_should_reject_what_the_model_forbids() {
+        let m3 = EnigmaBuilder::new()
+            .model(MachineModel::M3)
+            .rotors(["I", "II", "III"]);
+
+        assert_eq!(
+            m3.clone().rotors(["I", "I", "III"]).build().err(),
+            Some(BuilderError::Model(ModelError::RepeatedRotor(
+                RotorType::Rotor1
+            )))
+        );
+        assert_eq!(
+            m3.clone()
+                .model(MachineModel::EnigmaI)
+                .rotors(["I", "VI", "III"])
+                .build()
+                .err(),
+            Some(BuilderError::Model(ModelError::UnsupportedRotor(
+                RotorType::Rotor6
+            )))
+        );
+        assert_eq!(
+            m3.clone().rotors(["I", "II"]).build().err(),
+            Some(BuilderError::WrongRotorCount {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(
+            m3.clone().rotors(["I", "IX", "III"]).build().err(),
+            Some(BuilderError::Catalogue(CatalogueError::UnknownRotor(
+                "IX".to_string()
+            )))
+        );
+        assert_eq!(
+            m3.clone().reflector_position('C').build().err(),
+            Some(BuilderError::FixedReflector(MachineModel::M3))
+        );
+        assert_eq!(
+            m3.clone()
+                .model(MachineModel::CommercialD)
+                .rotors(["D-I", "D-II", "D-III"])
+                .plugboard("AB")
+                .build()
+                .err(),
+            Some(BuilderError::NoPlugboard(MachineModel::CommercialD))
+        );
+        assert_eq!(
+            EnigmaBuilder::new().rotors(["I", "II", "I"]).build().err(),
+            Some(BuilderError::MissingReflector)
+        );
+    }
+
+    #[test]
+    fn builder_should_reject_invalid_settings() {
+        let m3 = EnigmaBuilder::new()
+            .model(MachineModel::M3)
+            .rotors(["I", "II", "III"]);
+
+        assert_eq!(
+            m3.clone().positions("AA").build().err(),
+            Some(BuilderError::Machine(Error::WrongSettingCount {
+                expected: 3,
+                got: 2
+            }))
+        );
+        assert_eq!(
+            m3.clone().positions("A4A").build().err(),
+            Some(BuilderError::Machine(Error::InvalidLetter('4')))
+        );
+        assert_eq!(
+            m3.clone().rings("01 27 01").build().err(),
+            Some(BuilderError::InvalidRing("27".to_string()))
+        );
+        assert_eq!(
+            m3.clone().plugboard("AB CA").build().err(),
+            Some(BuilderError::Machine(Error::DuplicatePlug('A')))
+        );
+        assert_eq!(
+            m3.clone().plugboard("AB CDE").build().err(),
+            Some(BuilderError::InvalidPlug("CDE".to_string()))
+        );
+        assert_eq!(
+            m3.plugboard("AB cc").build().err(),
+            Some(BuilderError::InvalidPlug("cc".to_string()))
+        );
+    }
+}
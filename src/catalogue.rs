@@ -0,0 +1,305 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::entry_wheels::{self, EntryWheel, EntryWheelType};
+use crate::models::MachineModel;
+use crate::reflectors::{self, Reflector, ReflectorType};
+use crate::rotor::{Rotor, RotorType};
+use crate::rotors;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogueError {
+    UnknownRotor(String),
+    UnknownReflector(String),
+    UnknownEntryWheel(String),
+    UnknownModel(String),
+    /// The named part is wired for the numeric alphabet and has to be created with its own factory.
+    NumericPart(String),
+}
+
+impl Display for CatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogueError::UnknownRotor(name) => write!(f, "unknown rotor '{name}'"),
+            CatalogueError::UnknownReflector(name) => write!(f, "unknown reflector '{name}'"),
+            CatalogueError::UnknownEntryWheel(name) => write!(f, "unknown entry wheel '{name}'"),
+            CatalogueError::UnknownModel(name) => write!(f, "unknown machine model '{name}'"),
+            CatalogueError::NumericPart(name) => {
+                write!(f, "'{name}' is wired for the numeric alphabet")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogueError {}
+
+///
+/// Every rotor with its historical name. The first name is the one it is displayed with, the rest
+/// are accepted when parsing. Names are matched case-insensitively.
+///
+const ROTORS: &[(RotorType, &[&str])] = &[
+    (RotorType::Rotor1, &["I"]),
+    (RotorType::Rotor2, &["II"]),
+    (RotorType::Rotor3, &["III"]),
+    (RotorType::Rotor4, &["IV"]),
+    (RotorType::Rotor5, &["V"]),
+    (RotorType::Rotor6, &["VI"]),
+    (RotorType::Rotor7, &["VII"]),
+    (RotorType::Rotor8, &["VIII"]),
+    (RotorType::Beta, &["Beta"]),
+    (RotorType::Gamma, &["Gamma"]),
+    (RotorType::CommercialD1, &["D-I"]),
+    (RotorType::CommercialD2, &["D-II"]),
+    (RotorType::CommercialD3, &["D-III"]),
+    (RotorType::SwissK1, &["SwissK-I"]),
+    (RotorType::SwissK2, &["SwissK-II"]),
+    (RotorType::SwissK3, &["SwissK-III"]),
+    (RotorType::AbwehrG1, &["G-I"]),
+    (RotorType::AbwehrG2, &["G-II"]),
+    (RotorType::AbwehrG3, &["G-III"]),
+    (RotorType::Railway1, &["Railway-I", "Rocket-I"]),
+    (RotorType::Railway2, &["Railway-II", "Rocket-II"]),
+    (RotorType::Railway3, &["Railway-III", "Rocket-III"]),
+    (RotorType::Tirpitz1, &["T-I"]),
+    (RotorType::Tirpitz2, &["T-II"]),
+    (RotorType::Tirpitz3, &["T-III"]),
+    (RotorType::Tirpitz4, &["T-IV"]),
+    (RotorType::Tirpitz5, &["T-V"]),
+    (RotorType::Tirpitz6, &["T-VI"]),
+    (RotorType::Tirpitz7, &["T-VII"]),
+    (RotorType::Tirpitz8, &["T-VIII"]),
+    (RotorType::TypexExample1, &["Typex-Example-1"]),
+    (RotorType::TypexExample2, &["Typex-Example-2"]),
+    (RotorType::TypexExample3, &["Typex-Example-3"]),
+    (RotorType::TypexExample4, &["Typex-Example-4"]),
+    (RotorType::TypexExample5, &["Typex-Example-5"]),
+    (RotorType::TypexExample6, &["Typex-Example-6"]),
+    (RotorType::TypexExample7, &["Typex-Example-7"]),
+    (RotorType::Z30Rotor1, &["Z30-I"]),
+    (RotorType::Z30Rotor2, &["Z30-II"]),
+    (RotorType::Z30Rotor3, &["Z30-III"]),
+];
+
+const REFLECTORS: &[(ReflectorType, &[&str])] = &[
+    (ReflectorType::ReflectorA, &["UKW-A", "A"]),
+    (ReflectorType::ReflectorB, &["UKW-B", "B"]),
+    (ReflectorType::ReflectorC, &["UKW-C", "C"]),
+    (ReflectorType::ReflectorBThin, &["B-thin", "UKW-B-thin"]),
+    (ReflectorType::ReflectorCThin, &["C-thin", "UKW-C-thin"]),
+    (
+        ReflectorType::ReflectorCommercial,
+        &["UKW-D/K", "UKW-Commercial"],
+    ),
+    (ReflectorType::ReflectorAbwehrG, &["UKW-G"]),
+    (
+        ReflectorType::ReflectorRailway,
+        &["UKW-Railway", "UKW-Rocket"],
+    ),
+    (ReflectorType::ReflectorSwissK, &["UKW-SwissK"]),
+    (ReflectorType::ReflectorTirpitz, &["UKW-T"]),
+    (ReflectorType::ReflectorTypexExample, &["UKW-Typex-Example"]),
+    (ReflectorType::ReflectorZ30, &["UKW-Z30"]),
+];
+
+const ENTRY_WHEELS: &[(EntryWheelType, &[&str])] = &[
+    (EntryWheelType::Identity, &["ETW-ABC", "ETW"]),
+    (EntryWheelType::Qwertz, &["ETW-QWERTZ"]),
+    (EntryWheelType::Tirpitz, &["ETW-T"]),
+];
+
+const MODELS: &[(MachineModel, &[&str])] = &[
+    (MachineModel::EnigmaI, &["Enigma-I", "I"]),
+    (MachineModel::M3, &["M3"]),
+    (MachineModel::CommercialD, &["Enigma-D", "D"]),
+    (MachineModel::CommercialK, &["Enigma-K", "K"]),
+    (MachineModel::AbwehrG, &["Enigma-G", "G", "Abwehr"]),
+    (MachineModel::Railway, &["Railway", "Rocket"]),
+    (MachineModel::SwissK, &["Swiss-K"]),
+    (MachineModel::Tirpitz, &["Enigma-T", "T", "Tirpitz"]),
+    (MachineModel::Z30, &["Enigma-Z30", "Z30"]),
+];
+
+fn name_of<T: PartialEq>(table: &[(T, &[&'static str])], value: &T) -> &'static str {
+    table
+        .iter()
+        .find(|(entry, _)| entry == value)
+        .map(|(_, names)| names[0])
+        .expect("every variant is named in the catalogue")
+}
+
+fn parse<T: Copy>(table: &[(T, &[&str])], name: &str) -> Option<T> {
+    let name = name.trim();
+    table
+        .iter()
+        .find(|(_, names)| names.iter().any(|known| known.eq_ignore_ascii_case(name)))
+        .map(|&(value, _)| value)
+}
+
+/// The name custom parts are displayed with. It isn't parsed, as there is no part to look up.
+pub(crate) const CUSTOM: &str = "Custom";
+
+impl Display for RotorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotorType::Custom => f.write_str(CUSTOM),
+            typ => f.write_str(name_of(ROTORS, typ)),
+        }
+    }
+}
+
+impl FromStr for RotorType {
+    type Err = CatalogueError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        parse(ROTORS, name).ok_or_else(|| CatalogueError::UnknownRotor(name.to_string()))
+    }
+}
+
+impl Display for ReflectorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectorType::Custom => f.write_str(CUSTOM),
+            typ => f.write_str(name_of(REFLECTORS, typ)),
+        }
+    }
+}
+
+impl FromStr for ReflectorType {
+    type Err = CatalogueError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        parse(REFLECTORS, name).ok_or_else(|| CatalogueError::UnknownReflector(name.to_string()))
+    }
+}
+
+impl Display for EntryWheelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(name_of(ENTRY_WHEELS, self))
+    }
+}
+
+impl FromStr for EntryWheelType {
+    type Err = CatalogueError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        parse(ENTRY_WHEELS, name).ok_or_else(|| CatalogueError::UnknownEntryWheel(name.to_string()))
+    }
+}
+
+impl Display for MachineModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(name_of(MODELS, self))
+    }
+}
+
+impl FromStr for MachineModel {
+    type Err = CatalogueError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        parse(MODELS, name).ok_or_else(|| CatalogueError::UnknownModel(name.to_string()))
+    }
+}
+
+pub fn rotor_types() -> impl Iterator<Item = RotorType> {
+    ROTORS.iter().map(|&(typ, _)| typ)
+}
+
+pub fn reflector_types() -> impl Iterator<Item = ReflectorType> {
+    REFLECTORS.iter().map(|&(typ, _)| typ)
+}
+
+pub fn entry_wheel_types() -> impl Iterator<Item = EntryWheelType> {
+    ENTRY_WHEELS.iter().map(|&(typ, _)| typ)
+}
+
+pub fn models() -> impl Iterator<Item = MachineModel> {
+    MODELS.iter().map(|&(model, _)| model)
+}
+
+/// Creates a rotor of the Latin alphabet by its historical name, e.g. "I", "VI" or "Beta".
+pub fn rotor(name: &str) -> Result<Rotor, CatalogueError> {
+    rotors::create_rotor(name.parse()?).map_err(|_| CatalogueError::NumericPart(name.to_string()))
+}
+
+/// Creates a reflector of the Latin alphabet by its name, e.g. "UKW-B" or "B-thin".
+pub fn reflector(name: &str) -> Result<Reflector, CatalogueError> {
+    reflectors::create_reflector(name.parse()?)
+        .map_err(|_| CatalogueError::NumericPart(name.to_string()))
+}
+
+/// Creates an entry wheel by its name, e.g. "ETW-QWERTZ".
+pub fn entry_wheel(name: &str) -> Result<EntryWheel, CatalogueError> {
+    Ok(entry_wheels::create_entry_wheel(name.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_name_should_round_trip() {
+        for typ in rotor_types() {
+            assert_eq!(typ.to_string().parse(), Ok(typ));
+        }
+        for typ in reflector_types() {
+            assert_eq!(typ.to_string().parse(), Ok(typ));
+        }
+        for typ in entry_wheel_types() {
+            assert_eq!(typ.to_string().parse(), Ok(typ));
+        }
+        for model in models() {
+            assert_eq!(model.to_string().parse(), Ok(model));
+        }
+    }
+
+    #[test]
+    fn historical_names_should_create_parts() {
+        assert_eq!(
+            rotor("vi").unwrap().get_props().get_type(),
+            RotorType::Rotor6
+        );
+        assert_eq!(
+            rotor("Beta").unwrap().get_props().get_type(),
+            RotorType::Beta
+        );
+        assert_eq!(reflector("UKW-B").unwrap().typ, ReflectorType::ReflectorB);
+        assert_eq!(
+            reflector("B-thin").unwrap().typ,
+            ReflectorType::ReflectorBThin
+        );
+        assert_eq!(
+            entry_wheel("ETW-QWERTZ").unwrap().typ,
+            EntryWheelType::Qwertz
+        );
+    }
+
+    #[test]
+    fn unknown_and_numeric_names_should_be_rejected() {
+        assert_eq!(
+            "IX".parse::<RotorType>(),
+            Err(CatalogueError::UnknownRotor("IX".to_string()))
+        );
+        assert_eq!(
+            rotor("Z30-I").err(),
+            Some(CatalogueError::NumericPart("Z30-I".to_string()))
+        );
+    }
+
+    #[test]
+    fn every_model_should_only_allow_catalogued_parts() {
+        for model in models() {
+            assert!(
+                model
+                    .allowed_rotors()
+                    .iter()
+                    .all(|typ| rotor_types().any(|known| known == *typ))
+            );
+            assert!(
+                model
+                    .allowed_reflectors()
+                    .iter()
+                    .all(|typ| reflector_types().any(|known| known == *typ))
+            );
+        }
+    }
+}
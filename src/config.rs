@@ -0,0 +1,759 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Write as _};
+use std::sync::Arc;
+
+use crate::Enigma;
+use crate::alphabet::LATIN;
+use crate::catalogue::CatalogueError;
+use crate::entry_wheels::{self, EntryWheelType};
+use crate::error::{Error, WiringError};
+use crate::models::{MachineModel, ModelError};
+use crate::reflectors::{self, ReflectorType};
+use crate::rotor::{Orientation, Rotor, RotorType};
+use crate::rotors;
+use crate::rotors_controller::{RotorRole, RotorsController};
+use crate::stepping::{self, PawlAndNotch, SteppingStrategy};
+
+/// The version written by [`save`]. [`load`] rejects configurations of any other version.
+pub const VERSION: u32 = 1;
+
+const ROTOR_SECTION: &str = "rotor";
+const MIN_ROTORS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The text doesn't describe a valid machine. Lines and columns count from 1.
+    Parse {
+        line: usize,
+        column: usize,
+        kind: ParseErrorKind,
+    },
+    /// The machine steps with a strategy that has no
+    /// [`config_name`](stepping::SteppingStrategy::config_name).
+    CustomStepping,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// A line that is neither a `[section]` header nor written as `key = value`.
+    ExpectedKeyValue,
+    UnknownSection(String),
+    UnknownKey(String),
+    RepeatedKey(String),
+    MissingKey(&'static str),
+    /// Both keys describe the same part, so only one may be given.
+    ConflictingKeys(&'static str, &'static str),
+    UnsupportedVersion(String),
+    InvalidValue(String),
+    /// The rotors can't be stacked, e.g. there are too few or a stepping rotor sits left of a
+    /// rotor that doesn't step.
+    InvalidRotorStack,
+    Catalogue(CatalogueError),
+    Wiring(WiringError),
+    Model(ModelError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Parse { line, column, kind } => {
+                write!(f, "line {line}, column {column}: {kind}")
+            }
+            ConfigError::CustomStepping => {
+                write!(f, "the machine's stepping strategy can't be saved")
+            }
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::ExpectedKeyValue => write!(f, "expected 'key = value' or '[section]'"),
+            ParseErrorKind::UnknownSection(name) => write!(f, "unknown section '{name}'"),
+            ParseErrorKind::UnknownKey(key) => write!(f, "unknown key '{key}'"),
+            ParseErrorKind::RepeatedKey(key) => write!(f, "key '{key}' is given twice"),
+            ParseErrorKind::MissingKey(key) => write!(f, "missing key '{key}'"),
+            ParseErrorKind::ConflictingKeys(first, second) => {
+                write!(f, "only one of '{first}' and '{second}' may be given")
+            }
+            ParseErrorKind::UnsupportedVersion(version) => {
+                write!(f, "unsupported version '{version}', expected {VERSION}")
+            }
+            ParseErrorKind::InvalidValue(value) => write!(f, "invalid value '{value}'"),
+            ParseErrorKind::InvalidRotorStack => write!(
+                f,
+                "a machine needs at least {MIN_ROTORS} rotors, with the stepping rotors rightmost"
+            ),
+            ParseErrorKind::Catalogue(error) => error.fmt(f),
+            ParseErrorKind::Wiring(error) => error.fmt(f),
+            ParseErrorKind::Model(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+///
+/// Writes the complete state of a machine in the configuration format, so it can be restored with
+/// [`load`], e.g. as a checkpoint in the middle of a message.
+///
+/// The format is line based. Top-level `key = value` lines describe the machine, followed by one
+/// `[rotor]` section per rotor, leftmost first. `#` starts a comment. Historical parts are named as
+/// in the [`catalogue`](crate::catalogue), custom ones are given by their wiring.
+///
+/// Fails only for machines with a custom stepping strategy, which can't be written down.
+///
+pub fn save(enigma: &Enigma) -> Result<String, ConfigError> {
+    let stepping = enigma
+        .get_stepping_strategy()
+        .config_name()
+        .ok_or(ConfigError::CustomStepping)?;
+
+    let mut text = String::new();
+    let _ = writeln!(text, "version = {VERSION}");
+    if let Some(model) = enigma.get_model() {
+        let _ = writeln!(text, "model = {model}");
+    }
+    let _ = writeln!(text, "entry-wheel = {}", enigma.entry_wheel.typ);
+    let _ = writeln!(text, "stepping = {stepping}");
+    match enigma.reflector.typ {
+        ReflectorType::Custom => {
+            let _ = writeln!(text, "reflector-wiring = {}", enigma.reflector.get_wiring());
+        }
+        typ => {
+            let _ = writeln!(text, "reflector = {typ}");
+        }
+    }
+    let _ = writeln!(
+        text,
+        "reflector-position = {}",
+        enigma.get_reflector_position()
+    );
+    let _ = writeln!(
+        text,
+        "plugboard = {}",
+        plugboard_text(&enigma.transpositions)
+    );
+
+    let controller = &enigma.rotor_controller;
+    for index in 0..controller.len() {
+        let rotor = controller.get_rotor(index);
+        let orientation = rotor.get_orientation();
+        let _ = writeln!(text, "\n[{ROTOR_SECTION}]");
+        match rotor.get_props().get_type() {
+            RotorType::Custom => {
+                let _ = writeln!(text, "wiring = {}", rotor.get_props().get_wiring());
+                let _ = writeln!(text, "step-positions = {}", forward_step_positions(rotor));
+            }
+            typ => {
+                let _ = writeln!(text, "type = {typ}");
+            }
+        }
+        let _ = writeln!(text, "role = {}", role_name(controller.get_role(index)));
+        let _ = writeln!(text, "ring = {}", rotor.get_ring_setting());
+        let _ = writeln!(text, "position = {}", rotor.get_position());
+        let _ = writeln!(text, "orientation = {}", orientation_name(orientation));
+        let _ = writeln!(text, "core = {}", orientation_name(core_orientation(rotor)));
+    }
+
+    Ok(text)
+}
+
+///
+/// Restores a machine written by [`save`] or by hand.
+///
+/// Only `version`, a reflector and three `[rotor]` sections with a `type` or `wiring` are required.
+/// A `model` checks the parts against it and supplies its entry wheel and stepping; rings and
+/// positions default to `A`.
+///
+pub fn load(text: &str) -> Result<Enigma, ConfigError> {
+    let (mut machine, sections) = parse_sections(text)?;
+
+    let version = machine.require("version")?;
+    if version.value != VERSION.to_string() {
+        return Err(version.error(ParseErrorKind::UnsupportedVersion(
+            version.value.to_string(),
+        )));
+    }
+    let model = machine
+        .take("model")
+        .map(|entry| Ok((entry.parse::<MachineModel>()?, entry)))
+        .transpose()?;
+    if let Some((_, entry)) = model
+        .as_ref()
+        .filter(|(model, _)| model.alphabet_size() != LATIN.size())
+    {
+        return Err(entry.error(ParseErrorKind::Model(ModelError::WrongAlphabet)));
+    }
+
+    let entry_wheel = match machine.take("entry-wheel") {
+        Some(entry) => entry.parse::<EntryWheelType>()?,
+        None => model
+            .as_ref()
+            .map_or(EntryWheelType::Identity, |(model, _)| model.entry_wheel()),
+    };
+    let stepping = match machine.take("stepping") {
+        Some(entry) => {
+            stepping::from_config_name(entry.value).ok_or_else(|| entry.invalid_value())?
+        }
+        None => default_stepping(model.as_ref().map(|(model, _)| *model)),
+    };
+    let mut reflector = match (machine.take("reflector"), machine.take("reflector-wiring")) {
+        (Some(entry), None) => reflector_by_name(&entry)?,
+        (None, Some(entry)) => {
+            reflectors::create_custom_reflector(entry.value).map_err(|error| entry.wiring(error))?
+        }
+        (Some(entry), Some(_)) => {
+            return Err(entry.error(ParseErrorKind::ConflictingKeys(
+                "reflector",
+                "reflector-wiring",
+            )));
+        }
+        (None, None) => {
+            return Err(machine.missing("reflector"));
+        }
+    };
+    if let Some(entry) = machine.take("reflector-position") {
+        entry.set_letter(|letter| reflector.set_position(letter))?;
+    }
+    let plugboard = machine
+        .take("plugboard")
+        .map(|entry| parse_plugboard(entry.value).map_err(|kind| entry.error(kind)))
+        .transpose()?
+        .unwrap_or_default();
+    machine.reject_unknown()?;
+
+    let section_lines: Vec<usize> = sections.iter().map(|section| section.line).collect();
+    let mut rotors = Vec::with_capacity(sections.len());
+    for mut section in sections {
+        rotors.push(parse_rotor(&mut section)?);
+        section.reject_unknown()?;
+    }
+    validate_stack(&rotors, &section_lines, text)?;
+
+    if let Some((model, entry)) = &model {
+        let types: Vec<RotorType> = rotors
+            .iter()
+            .map(|(rotor, _)| rotor.get_props().get_type())
+            .collect();
+        model
+            .validate(&types, reflector.typ)
+            .map_err(|error| entry.error(ParseErrorKind::Model(error)))?;
+    }
+
+    let mut enigma = Enigma::with_controller(
+        RotorsController::from_stack(rotors),
+        reflector,
+        entry_wheels::create_entry_wheel(entry_wheel),
+    );
+    enigma.rotor_controller.set_stepping_strategy(stepping);
+    enigma.model = model.map(|(model, _)| model);
+    enigma.transpositions = plugboard;
+
+    Ok(enigma)
+}
+
+fn parse_rotor(section: &mut Section) -> Result<(Rotor, RotorRole), ConfigError> {
+    let orientation = match section.take("orientation") {
+        Some(entry) => parse_orientation(&entry)?,
+        None => Orientation::Forward,
+    };
+    let mut rotor = match (section.take("type"), section.take("wiring")) {
+        (Some(entry), None) => rotor_by_name(&entry)?,
+        (None, Some(entry)) => {
+            let step_positions = match section.take("step-positions") {
+                Some(step_positions) => parse_step_positions(&step_positions)?,
+                None => String::new(),
+            };
+
+            rotors::create_custom_rotor(entry.value, &step_positions)
+                .map_err(|error| entry.wiring(error))?
+        }
+        (Some(entry), Some(_)) => {
+            return Err(entry.error(ParseErrorKind::ConflictingKeys("type", "wiring")));
+        }
+        (None, None) => return Err(section.missing("type")),
+    };
+
+    if orientation == Orientation::Reversed {
+        rotor = rotor.reversed();
+    }
+    let core = match section.take("core") {
+        Some(entry) => parse_orientation(&entry)?,
+        None => Orientation::Forward,
+    };
+    if core == Orientation::Reversed {
+        rotor.set_core_reversed(!rotor.is_core_reversed());
+    }
+    let role = match section.take("role") {
+        Some(entry) => parse_role(&entry)?,
+        None => RotorRole::Stepping,
+    };
+    if let Some(entry) = section.take("ring") {
+        entry.set_letter(|letter| rotor.set_ring_setting(letter))?;
+    }
+    if let Some(entry) = section.take("position") {
+        entry.set_letter(|letter| rotor.set_position(letter))?;
+    }
+
+    Ok((rotor, role))
+}
+
+/// The stepping of the model, or the usual pawls and notches when there is no model.
+pub(crate) fn default_stepping(model: Option<MachineModel>) -> Arc<dyn SteppingStrategy> {
+    match model {
+        Some(model) => model.stepping(),
+        None => Arc::new(PawlAndNotch),
+    }
+}
+
+/// Points at the first rotor that sits out of order, or at the end when rotors are missing.
+fn validate_stack(
+    rotors: &[(Rotor, RotorRole)],
+    section_lines: &[usize],
+    text: &str,
+) -> Result<(), ConfigError> {
+    let first_stepping = rotors
+        .iter()
+        .position(|&(_, role)| role == RotorRole::Stepping)
+        .unwrap_or(rotors.len());
+    let misplaced = rotors[first_stepping..]
+        .iter()
+        .position(|&(_, role)| role != RotorRole::Stepping)
+        .map(|offset| section_lines[first_stepping + offset]);
+
+    let line = match (misplaced, rotors.len() >= MIN_ROTORS) {
+        (Some(line), _) => line,
+        (None, false) => text.lines().count().max(1),
+        (None, true) => return Ok(()),
+    };
+    Err(ConfigError::Parse {
+        line,
+        column: 1,
+        kind: ParseErrorKind::InvalidRotorStack,
+    })
+}
+
+/// Reads a custom rotor's step positions, which are written as they are on the forward rotor.
+fn parse_step_positions(entry: &Entry) -> Result<String, ConfigError> {
+    entry
+        .value
+        .chars()
+        .map(
+            |position| match LATIN.contains(position.to_ascii_uppercase()) {
+                true => Ok(position.to_ascii_uppercase()),
+                false => Err(entry.invalid_value()),
+            },
+        )
+        .collect()
+}
+
+fn rotor_by_name(entry: &Entry) -> Result<Rotor, ConfigError> {
+    rotors::create_rotor(entry.parse()?).map_err(|_| {
+        entry.error(ParseErrorKind::Catalogue(CatalogueError::NumericPart(
+            entry.value.to_string(),
+        )))
+    })
+}
+
+fn reflector_by_name(entry: &Entry) -> Result<reflectors::Reflector, ConfigError> {
+    reflectors::create_reflector(entry.parse()?).map_err(|_| {
+        entry.error(ParseErrorKind::Catalogue(CatalogueError::NumericPart(
+            entry.value.to_string(),
+        )))
+    })
+}
+
+/// Writes the plugboard as its cables, e.g. "AD CN ET".
+pub(crate) fn plugboard_text(transpositions: &HashMap<char, char>) -> String {
+    let mut plugs: Vec<String> = transpositions
+        .iter()
+        .filter(|(first, second)| first < second)
+        .map(|(first, second)| format!("{first}{second}"))
+        .collect();
+    plugs.sort();
+    plugs.join(" ")
+}
+
+/// Reads the cables written by [`plugboard_text`], rejecting letters that are plugged twice.
+pub(crate) fn parse_plugboard(value: &str) -> Result<HashMap<char, char>, ParseErrorKind> {
+    let cables = crate::parse_plugboard(value)
+        .map_err(|invalid| ParseErrorKind::InvalidValue(invalid.value.to_string()))?;
+
+    Ok(cables
+        .into_iter()
+        .flat_map(|(first, second)| [(first, second), (second, first)])
+        .collect())
+}
+
+fn parse_orientation(entry: &Entry) -> Result<Orientation, ConfigError> {
+    match entry.value {
+        "forward" => Ok(Orientation::Forward),
+        "reversed" => Ok(Orientation::Reversed),
+        _ => Err(entry.invalid_value()),
+    }
+}
+
+fn orientation_name(orientation: Orientation) -> &'static str {
+    match orientation {
+        Orientation::Forward => "forward",
+        Orientation::Reversed => "reversed",
+    }
+}
+
+fn parse_role(entry: &Entry) -> Result<RotorRole, ConfigError> {
+    match entry.value {
+        "stepping" => Ok(RotorRole::Stepping),
+        "greek-wheel" => Ok(RotorRole::GreekWheel),
+        "stator" => Ok(RotorRole::Stator),
+        _ => Err(entry.invalid_value()),
+    }
+}
+
+fn role_name(role: RotorRole) -> &'static str {
+    match role {
+        RotorRole::Stepping => "stepping",
+        RotorRole::GreekWheel => "greek-wheel",
+        RotorRole::Stator => "stator",
+    }
+}
+
+/// A custom rotor's step positions as they are on the forward rotor, however it is inserted.
+pub(crate) fn forward_step_positions(rotor: &Rotor) -> String {
+    rotor
+        .get_props()
+        .get_step_positions()
+        .into_iter()
+        .map(|position| match rotor.get_orientation() {
+            Orientation::Forward => position,
+            Orientation::Reversed => mirror_step_position(position),
+        })
+        .collect()
+}
+
+/// Which way round the core sits inside the rotor, relative to the rotor's orientation.
+pub(crate) fn core_orientation(rotor: &Rotor) -> Orientation {
+    match rotor.is_core_reversed() != (rotor.get_orientation() == Orientation::Reversed) {
+        true => Orientation::Reversed,
+        false => Orientation::Forward,
+    }
+}
+
+/// Mirrors a step position the way [`Rotor::reversed`] does, which undoes itself.
+fn mirror_step_position(position: char) -> char {
+    match LATIN.index_of(position) {
+        Some(index) => LATIN.symbol((LATIN.size() + 2 - index) % LATIN.size()),
+        None => position,
+    }
+}
+
+/// A `key = value` line, with the columns of its key and value for error reporting.
+#[derive(Debug)]
+struct Entry<'a> {
+    key: &'a str,
+    value: &'a str,
+    line: usize,
+    key_column: usize,
+    value_column: usize,
+}
+
+impl Entry<'_> {
+    fn error(&self, kind: ParseErrorKind) -> ConfigError {
+        ConfigError::Parse {
+            line: self.line,
+            column: self.value_column,
+            kind,
+        }
+    }
+
+    fn invalid_value(&self) -> ConfigError {
+        self.error(ParseErrorKind::InvalidValue(self.value.to_string()))
+    }
+
+    fn wiring(&self, error: WiringError) -> ConfigError {
+        self.error(ParseErrorKind::Wiring(error))
+    }
+
+    fn parse<T: std::str::FromStr<Err = CatalogueError>>(&self) -> Result<T, ConfigError> {
+        self.value
+            .parse()
+            .map_err(|error| self.error(ParseErrorKind::Catalogue(error)))
+    }
+
+    /// Applies a setting given as a single letter.
+    fn set_letter(&self, set: impl FnOnce(char) -> Result<(), Error>) -> Result<(), ConfigError> {
+        let mut chars = self.value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) => set(letter).map_err(|_| self.invalid_value()),
+            _ => Err(self.invalid_value()),
+        }
+    }
+}
+
+/// The top-level entries or those of one `[section]`, starting on `line`.
+#[derive(Debug)]
+struct Section<'a> {
+    line: usize,
+    entries: Vec<Entry<'a>>,
+}
+
+impl<'a> Section<'a> {
+    fn take(&mut self, key: &str) -> Option<Entry<'a>> {
+        let index = self.entries.iter().position(|entry| entry.key == key)?;
+        Some(self.entries.remove(index))
+    }
+
+    fn require(&mut self, key: &'static str) -> Result<Entry<'a>, ConfigError> {
+        self.take(key).ok_or_else(|| self.missing(key))
+    }
+
+    fn missing(&self, key: &'static str) -> ConfigError {
+        ConfigError::Parse {
+            line: self.line,
+            column: 1,
+            kind: ParseErrorKind::MissingKey(key),
+        }
+    }
+
+    fn reject_unknown(&self) -> Result<(), ConfigError> {
+        match self.entries.first() {
+            Some(entry) => Err(ConfigError::Parse {
+                line: entry.line,
+                column: entry.key_column,
+                kind: ParseErrorKind::UnknownKey(entry.key.to_string()),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Splits the text into its top-level entries and `[rotor]` sections.
+fn parse_sections(text: &str) -> Result<(Section<'_>, Vec<Section<'_>>), ConfigError> {
+    let mut machine = Section {
+        line: 1,
+        entries: Vec::new(),
+    };
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw.split('#').next().unwrap_or_default();
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let column_of = |part: &str| {
+            raw[..part.as_ptr() as usize - raw.as_ptr() as usize]
+                .chars()
+                .count()
+                + 1
+        };
+        let error = |column, kind| ConfigError::Parse { line, column, kind };
+
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .ok_or_else(|| error(column_of(trimmed), ParseErrorKind::ExpectedKeyValue))?
+                .trim();
+            if name != ROTOR_SECTION {
+                return Err(error(
+                    column_of(trimmed),
+                    ParseErrorKind::UnknownSection(name.to_string()),
+                ));
+            }
+            sections.push(Section {
+                line,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| error(column_of(trimmed), ParseErrorKind::ExpectedKeyValue))?;
+        let (key, value) = (key.trim(), value.trim());
+        let section = sections.last_mut().unwrap_or(&mut machine);
+        if key.is_empty() {
+            return Err(error(column_of(trimmed), ParseErrorKind::ExpectedKeyValue));
+        }
+        if section.entries.iter().any(|entry| entry.key == key) {
+            return Err(error(
+                column_of(key),
+                ParseErrorKind::RepeatedKey(key.to_string()),
+            ));
+        }
+
+        section.entries.push(Entry {
+            key,
+            value,
+            line,
+            key_column: column_of(key),
+            value_column: match value.is_empty() {
+                true => content.trim_end().chars().count() + 1,
+                false => column_of(value),
+            },
+        });
+    }
+
+    Ok((machine, sections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typex::insert_reversed;
+    use crate::{reflectors, rotors};
+
+    const M3: &str = "\
+# Daily key for the 14th
+version = 1
+model = M3
+reflector = B
+plugboard = AQ ep
+
+[rotor]
+type = I
+[rotor]
+type = II
+ring = B
+[rotor]
+type = III
+position = V
+";
+
+    fn parse_error(text: &str) -> (usize, usize, ParseErrorKind) {
+        match load(text) {
+            Err(ConfigError::Parse { line, column, kind }) => (line, column, kind),
+            other => panic!("expected a parse error but got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn configuration_should_set_up_the_machine() {
+        let mut enigma = load(M3).unwrap();
+
+        let mut expected = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        expected.set_stepping_strategy(stepping::DoubleStep);
+        expected.set_ring_settings(&['A', 'B', 'A']).unwrap();
+        expected.set_rotor_positions(&['A', 'A', 'V']).unwrap();
+        expected.set_transposition('A', 'Q').unwrap();
+        expected.set_transposition('E', 'P').unwrap();
+
+        assert_eq!(enigma.get_model(), Some(MachineModel::M3));
+        assert_eq!(
+            enigma.encrypt_str(&"ATTACKATDAWN".repeat(30)),
+            expected.encrypt_str(&"ATTACKATDAWN".repeat(30))
+        );
+    }
+
+    #[test]
+    fn checkpoint_should_restore_a_machine_mid_message() {
+        let mut enigma = Enigma::with_rotor_stack(
+            vec![
+                (
+                    insert_reversed(rotors::create_rotor_beta()),
+                    RotorRole::GreekWheel,
+                ),
+                (rotors::create_rotor_6().reversed(), RotorRole::Stepping),
+                (
+                    rotors::create_custom_rotor("QWERTZUIOASDFGHJKPYXCVBNML", "CM")
+                        .unwrap()
+                        .reversed(),
+                    RotorRole::Stepping,
+                ),
+                (rotors::create_rotor_8(), RotorRole::Stepping),
+            ],
+            reflectors::create_custom_reflector("BADCFEHGJILKNMPORQTSVUXWZY").unwrap(),
+            entry_wheels::create_entry_wheel_qwertz(),
+        )
+        .unwrap();
+        enigma.set_stepping_strategy(stepping::Cogwheel);
+        enigma.set_ring_settings(&['C', 'O', 'D', 'E']).unwrap();
+        enigma.set_transposition('K', 'Z').unwrap();
+        enigma.encrypt_str(&"CHECKPOINT".repeat(70)).unwrap();
+
+        let saved = save(&enigma).unwrap();
+        let mut restored = load(&saved).unwrap();
+
+        assert_eq!(save(&restored), Ok(saved));
+        assert_eq!(
+            restored.encrypt_str(&"ATTACKATDAWN".repeat(50)),
+            enigma.encrypt_str(&"ATTACKATDAWN".repeat(50))
+        );
+    }
+
+    #[test]
+    fn errors_should_point_at_line_and_column() {
+        assert_eq!(
+            parse_error("version = 1\nreflector =  UKW-X\n"),
+            (
+                2,
+                14,
+                ParseErrorKind::Catalogue(CatalogueError::UnknownReflector("UKW-X".to_string()))
+            )
+        );
+        assert_eq!(
+            parse_error(&M3.replace("ring = B", "ring = 7")),
+            (11, 8, ParseErrorKind::InvalidValue("7".to_string()))
+        );
+        assert_eq!(
+            parse_error(&M3.replace("ring = B", "  colour = red")),
+            (11, 3, ParseErrorKind::UnknownKey("colour".to_string()))
+        );
+        assert_eq!(
+            parse_error(&M3.replace("AQ ep", "AQ ea")).2,
+            ParseErrorKind::InvalidValue("ea".to_string())
+        );
+        assert_eq!(
+            parse_error(&M3.replace("type = III", "type = Beta")),
+            (
+                3,
+                9,
+                ParseErrorKind::Model(ModelError::UnsupportedRotor(RotorType::Beta))
+            )
+        );
+        assert_eq!(
+            parse_error(&M3.replace("type = II\n", "type = II\nrole = stator\n")),
+            (9, 1, ParseErrorKind::InvalidRotorStack)
+        );
+        assert_eq!(
+            parse_error("version = 2"),
+            (1, 11, ParseErrorKind::UnsupportedVersion("2".to_string()))
+        );
+        assert_eq!(
+            parse_error("version = 1\nreflector = B\n[wheel]"),
+            (3, 1, ParseErrorKind::UnknownSection("wheel".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_custom_wirings_should_be_rejected() {
+        let text = "version = 1\nreflector-wiring = ABCDEFGHIJKLMNOPQRSTUVWXYZ\n";
+
+        assert_eq!(
+            parse_error(text),
+            (
+                2,
+                20,
+                ParseErrorKind::Wiring(WiringError::SelfConnected('A'))
+            )
+        );
+        assert_eq!(
+            parse_error(&M3.replace("type = II", "wiring = AAB")),
+            (
+                10,
+                10,
+                ParseErrorKind::Wiring(WiringError::WrongLength {
+                    expected: 26,
+                    got: 3
+                })
+            )
+        );
+    }
+}
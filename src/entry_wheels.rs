@@ -0,0 +1,232 @@
+use crate::alphabet::{Alphabet, DIGITS};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::Error;
+use crate::letter_permutation::{LetterPermutation, bidir_map};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryWheelType {
+    Identity,
+    Qwertz,
+    Tirpitz,
+}
+
+///
+/// The fixed wheel (Eintrittswalze) between the plugboard and the right-hand rotor.
+///
+/// Military machines wire it as the identity, so a key enters the rotors at its own contact.
+/// Commercial machines wire it in keyboard order - Q enters at contact A, W at B and so on.
+/// The Enigma T has its own scrambled wiring.
+///
+#[derive(Debug, Clone, Copy)]
+pub struct EntryWheel<const N: usize = ALPHABET_SIZE> {
+    map: LetterPermutation<N>,
+    inverse: LetterPermutation<N>,
+    pub typ: EntryWheelType,
+}
+
+impl<const N: usize> EntryWheel<N> {
+    pub(crate) fn alphabet(&self) -> &'static Alphabet<N> {
+        self.map.alphabet()
+    }
+
+    /// Maps a key to the contact it enters the rotors at.
+    pub fn map_letter(&self, letter: char) -> Result<char, Error> {
+        self.map
+            .get(letter.to_ascii_uppercase())
+            .map_err(|_| Error::InvalidLetter(letter))
+    }
+
+    /// Maps a contact leaving the rotors back to its lamp.
+    pub fn inverse_map_letter(&self, letter: char) -> Result<char, Error> {
+        self.inverse
+            .get(letter.to_ascii_uppercase())
+            .map_err(|_| Error::InvalidLetter(letter))
+    }
+}
+
+bidir_map!(IDENTITY_PERMUTATION, IDENTITY_INVERSE,
+{
+    'A' => 'A',
+    'B' => 'B',
+    'C' => 'C',
+    'D' => 'D',
+    'E' => 'E',
+    'F' => 'F',
+    'G' => 'G',
+    'H' => 'H',
+    'I' => 'I',
+    'J' => 'J',
+    'K' => 'K',
+    'L' => 'L',
+    'M' => 'M',
+    'N' => 'N',
+    'O' => 'O',
+    'P' => 'P',
+    'Q' => 'Q',
+    'R' => 'R',
+    'S' => 'S',
+    'T' => 'T',
+    'U' => 'U',
+    'V' => 'V',
+    'W' => 'W',
+    'X' => 'X',
+    'Y' => 'Y',
+    'Z' => 'Z',
+});
+
+bidir_map!(QWERTZ_PERMUTATION, QWERTZ_INVERSE,
+{
+    'A' => 'J',
+    'B' => 'W',
+    'C' => 'U',
+    'D' => 'L',
+    'E' => 'C',
+    'F' => 'M',
+    'G' => 'N',
+    'H' => 'O',
+    'I' => 'H',
+    'J' => 'P',
+    'K' => 'Q',
+    'L' => 'Z',
+    'M' => 'Y',
+    'N' => 'X',
+    'O' => 'I',
+    'P' => 'R',
+    'Q' => 'A',
+    'R' => 'D',
+    'S' => 'K',
+    'T' => 'E',
+    'U' => 'G',
+    'V' => 'V',
+    'W' => 'B',
+    'X' => 'T',
+    'Y' => 'S',
+    'Z' => 'F',
+});
+
+bidir_map!(TIRPITZ_PERMUTATION, TIRPITZ_INVERSE,
+{
+    'A' => 'I',
+    'B' => 'L',
+    'C' => 'X',
+    'D' => 'R',
+    'E' => 'Z',
+    'F' => 'T',
+    'G' => 'K',
+    'H' => 'G',
+    'I' => 'J',
+    'J' => 'Y',
+    'K' => 'A',
+    'L' => 'M',
+    'M' => 'W',
+    'N' => 'V',
+    'O' => 'D',
+    'P' => 'U',
+    'Q' => 'F',
+    'R' => 'C',
+    'S' => 'P',
+    'T' => 'Q',
+    'U' => 'E',
+    'V' => 'O',
+    'W' => 'N',
+    'X' => 'S',
+    'Y' => 'H',
+    'Z' => 'B',
+});
+
+bidir_map!(DIGITS_PERMUTATION, DIGITS_INVERSE, 10,
+{
+    '1' => '1',
+    '2' => '2',
+    '3' => '3',
+    '4' => '4',
+    '5' => '5',
+    '6' => '6',
+    '7' => '7',
+    '8' => '8',
+    '9' => '9',
+    '0' => '0',
+});
+
+pub fn create_entry_wheel_identity() -> EntryWheel {
+    EntryWheel {
+        map: LetterPermutation::from_table(&IDENTITY_PERMUTATION),
+        inverse: LetterPermutation::from_table(&IDENTITY_INVERSE),
+        typ: EntryWheelType::Identity,
+    }
+}
+
+/// The keyboard-ordered entry wheel of the commercial Enigma D and K.
+pub fn create_entry_wheel_qwertz() -> EntryWheel {
+    EntryWheel {
+        map: LetterPermutation::from_table(&QWERTZ_PERMUTATION),
+        inverse: LetterPermutation::from_table(&QWERTZ_INVERSE),
+        typ: EntryWheelType::Qwertz,
+    }
+}
+
+/// The entry wheel of the Enigma T, wired neither as the identity nor in keyboard order.
+pub fn create_entry_wheel_tirpitz() -> EntryWheel {
+    EntryWheel {
+        map: LetterPermutation::from_table(&TIRPITZ_PERMUTATION),
+        inverse: LetterPermutation::from_table(&TIRPITZ_INVERSE),
+        typ: EntryWheelType::Tirpitz,
+    }
+}
+
+/// The identity entry wheel of the numeric Enigma Z30.
+pub fn create_entry_wheel_numeric() -> EntryWheel<10> {
+    EntryWheel {
+        map: LetterPermutation::from_table_with_alphabet(&DIGITS_PERMUTATION, &DIGITS),
+        inverse: LetterPermutation::from_table_with_alphabet(&DIGITS_INVERSE, &DIGITS),
+        typ: EntryWheelType::Identity,
+    }
+}
+
+pub fn create_entry_wheel(typ: EntryWheelType) -> EntryWheel {
+    match typ {
+        EntryWheelType::Identity => create_entry_wheel_identity(),
+        EntryWheelType::Qwertz => create_entry_wheel_qwertz(),
+        EntryWheelType::Tirpitz => create_entry_wheel_tirpitz(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qwertz_entry_wheel_should_follow_keyboard_order() {
+        let entry_wheel = create_entry_wheel_qwertz();
+
+        assert_eq!(entry_wheel.map_letter('Q'), Ok('A'));
+        assert_eq!(entry_wheel.map_letter('w'), Ok('B'));
+        assert_eq!(entry_wheel.map_letter('L'), Ok('Z'));
+        assert_eq!(entry_wheel.inverse_map_letter('A'), Ok('Q'));
+    }
+
+    #[test]
+    fn tirpitz_entry_wheel_should_invert_its_own_mapping() {
+        let entry_wheel = create_entry_wheel_tirpitz();
+
+        assert_eq!(entry_wheel.map_letter('K'), Ok('A'));
+        assert!(('A'..='Z').all(|letter| {
+            entry_wheel.inverse_map_letter(entry_wheel.map_letter(letter).unwrap()) == Ok(letter)
+        }));
+    }
+
+    #[test]
+    fn identity_entry_wheel_should_not_change_letters() {
+        let entry_wheel = create_entry_wheel_identity();
+
+        assert!(('A'..='Z').all(|letter| entry_wheel.map_letter(letter) == Ok(letter)));
+    }
+
+    #[test]
+    fn non_alphabetic_letter_should_return_error() {
+        assert_eq!(
+            create_entry_wheel_qwertz().map_letter('1'),
+            Err(Error::InvalidLetter('1'))
+        );
+    }
+}
@@ -1,4 +1,125 @@
-#[derive(Debug, PartialEq)]
+use std::fmt::{self, Display};
+
+use crate::models::ModelError;
+
+/// Why an operation on a machine or one of its parts failed.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Error {
-    NonAlphabetic,
+    /// The character isn't part of the alphabet of the machine or part.
+    InvalidLetter(char),
+    /// A position given as a number is past the last symbol of the alphabet.
+    PositionOutOfRange {
+        position: usize,
+        size: usize,
+    },
+    InvalidWiring(WiringError),
+    /// The letter already has a plugboard cable to another letter.
+    DuplicatePlug(char),
+    /// The part doesn't fit the machine model.
+    UnsupportedComponent(ModelError),
+    NoSuchRotor {
+        index: usize,
+        count: usize,
+    },
+    /// Settings for all rotors at once need one per rotor.
+    WrongSettingCount {
+        expected: usize,
+        got: usize,
+    },
+    /// A rotor stack needs at least three rotors, with the stepping rotors rightmost.
+    InvalidRotorStack,
 }
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidLetter(letter) => write!(f, "'{letter}' is not part of the alphabet"),
+            Error::PositionOutOfRange { position, size } => write!(
+                f,
+                "position {position} is out of range for an alphabet of {size} symbols"
+            ),
+            Error::InvalidWiring(error) => write!(f, "invalid wiring: {error}"),
+            Error::DuplicatePlug(letter) => {
+                write!(f, "'{letter}' is already plugged to another letter")
+            }
+            Error::UnsupportedComponent(error) => error.fmt(f),
+            Error::NoSuchRotor { index, count } => {
+                write!(f, "there is no rotor {index} in a stack of {count} rotors")
+            }
+            Error::WrongSettingCount { expected, got } => {
+                write!(
+                    f,
+                    "expected a setting for each of the {expected} rotors but got {got}"
+                )
+            }
+            Error::InvalidRotorStack => write!(
+                f,
+                "a rotor stack needs at least three rotors, with the stepping rotors rightmost"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<WiringError> for Error {
+    fn from(error: WiringError) -> Self {
+        Error::InvalidWiring(error)
+    }
+}
+
+impl From<ModelError> for Error {
+    fn from(error: ModelError) -> Self {
+        Error::UnsupportedComponent(error)
+    }
+}
+
+/// Why a rotor, reflector or plugboard wiring given as text was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WiringError {
+    WrongLength {
+        expected: usize,
+        got: usize,
+    },
+    NotInAlphabet(char),
+    /// The symbol appears twice, so the wiring isn't a permutation.
+    Repeated(char),
+    /// Reflectors must swap pairs of symbols; this one maps somewhere that doesn't map back.
+    NotReciprocal(char),
+    /// Reflectors can't connect a symbol to itself.
+    SelfConnected(char),
+    /// A wiring table lists its symbols out of alphabet order.
+    OutOfOrder {
+        expected: char,
+        found: char,
+    },
+}
+
+impl Display for WiringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiringError::WrongLength { expected, got } => {
+                write!(f, "expected a wiring of {expected} symbols but got {got}")
+            }
+            WiringError::NotInAlphabet(symbol) => {
+                write!(f, "'{symbol}' is not part of the alphabet")
+            }
+            WiringError::Repeated(symbol) => write!(f, "'{symbol}' is wired twice"),
+            WiringError::NotReciprocal(symbol) => {
+                write!(
+                    f,
+                    "'{symbol}' isn't wired back by the symbol it is wired to"
+                )
+            }
+            WiringError::SelfConnected(symbol) => write!(f, "'{symbol}' is wired to itself"),
+            WiringError::OutOfOrder { expected, found } => {
+                write!(
+                    f,
+                    "expected the table entry for '{expected}' but found '{found}'"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for WiringError {}
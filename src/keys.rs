@@ -0,0 +1,679 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::Enigma;
+use crate::alphabet::LATIN;
+use crate::catalogue::CatalogueError;
+use crate::error::Error;
+use crate::models::{MachineModel, ModelError};
+use crate::reflectors::ReflectorType;
+use crate::rotor::RotorType;
+
+const ROTORS: usize = 3;
+const PLUGBOARD_CABLES: usize = 10;
+const DAYS_IN_MONTH: usize = 31;
+const KENNGRUPPEN: usize = 4;
+const KENNGRUPPE_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyError {
+    MissingField(&'static str),
+    UnknownField(String),
+    RepeatedField(String),
+    /// A field or line that isn't written as `name = value`.
+    MalformedField(String),
+    InvalidValue {
+        field: &'static str,
+        value: String,
+    },
+    /// Plugs were given for a model without a plugboard.
+    NoPlugboard(MachineModel),
+    /// A reflector position was given for a model whose reflector can't be turned.
+    FixedReflector(MachineModel),
+    /// The model has too few rotors for a month without repeating a wheel order.
+    TooFewRotors(MachineModel),
+    Catalogue(CatalogueError),
+    Model(ModelError),
+    /// The key's settings don't fit the machine, e.g. a position outside the alphabet.
+    Machine(Error),
+}
+
+impl Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::MissingField(field) => write!(f, "missing field '{field}'"),
+            KeyError::UnknownField(field) => write!(f, "unknown field '{field}'"),
+            KeyError::RepeatedField(field) => write!(f, "field '{field}' is given twice"),
+            KeyError::MalformedField(text) => write!(f, "expected 'name = value' but got '{text}'"),
+            KeyError::InvalidValue { field, value } => {
+                write!(f, "invalid value '{value}' for field '{field}'")
+            }
+            KeyError::NoPlugboard(model) => write!(f, "the {model} has no plugboard"),
+            KeyError::FixedReflector(model) => write!(f, "the {model} has a fixed reflector"),
+            KeyError::TooFewRotors(model) => {
+                write!(
+                    f,
+                    "the {model} has too few rotors for a month of wheel orders"
+                )
+            }
+            KeyError::Catalogue(error) => error.fmt(f),
+            KeyError::Model(error) => error.fmt(f),
+            KeyError::Machine(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<CatalogueError> for KeyError {
+    fn from(error: CatalogueError) -> Self {
+        KeyError::Catalogue(error)
+    }
+}
+
+impl From<ModelError> for KeyError {
+    fn from(error: ModelError) -> Self {
+        KeyError::Model(error)
+    }
+}
+
+impl From<Error> for KeyError {
+    fn from(error: Error) -> Self {
+        KeyError::Machine(error)
+    }
+}
+
+///
+/// The settings of a three-rotor machine for one day, as printed on a key sheet.
+///
+/// A key is written as `name = value` fields, separated by semicolons or line breaks, e.g.
+/// `model = M3; reflector = B; rotors = I IV III; rings = 16 26 08; plugboard = AD CN ET;
+/// positions = QWE`. Only `model` and `rotors` are required; the reflector defaults to the model's
+/// first one and rings and positions to `A`. Lines starting with `#` are comments.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyKey {
+    pub model: MachineModel,
+    pub wheel_order: [RotorType; ROTORS],
+    pub reflector: ReflectorType,
+    pub reflector_position: Option<char>,
+    pub ring_settings: [char; ROTORS],
+    pub plugboard: Vec<(char, char)>,
+    pub positions: [char; ROTORS],
+}
+
+impl DailyKey {
+    ///
+    /// Draws random settings the model accepts, with ten plugboard cables when it has a plugboard.
+    /// Fails with [`ModelError::WrongAlphabet`] for models that don't encipher letters.
+    ///
+    pub fn random(model: MachineModel, rng: &mut Rng) -> Result<Self, ModelError> {
+        if model.alphabet_size() != LATIN.size() {
+            return Err(ModelError::WrongAlphabet);
+        }
+
+        let mut rotors = model.allowed_rotors().to_vec();
+        rng.shuffle(&mut rotors);
+        let reflectors = model.allowed_reflectors();
+
+        let mut letters = *LATIN.symbols();
+        rng.shuffle(&mut letters);
+        let plugboard = match model.has_plugboard() {
+            true => letters
+                .chunks(2)
+                .take(PLUGBOARD_CABLES)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            false => Vec::new(),
+        };
+
+        Ok(Self {
+            model,
+            wheel_order: [rotors[0], rotors[1], rotors[2]],
+            reflector: reflectors[rng.below(reflectors.len())],
+            reflector_position: model.has_settable_reflector().then(|| random_letter(rng)),
+            ring_settings: std::array::from_fn(|_| random_letter(rng)),
+            plugboard,
+            positions: std::array::from_fn(|_| random_letter(rng)),
+        })
+    }
+
+    /// Assembles a machine of the key's model and sets it up with the key.
+    pub fn create_enigma(&self) -> Result<Enigma, KeyError> {
+        let mut enigma = self.model.create(self.wheel_order, self.reflector)?;
+        enigma.set_ring_settings(&self.ring_settings)?;
+        enigma.set_rotor_positions(&self.positions)?;
+        if let Some(position) = self.reflector_position {
+            enigma.set_reflector_position(position)?;
+        }
+        for &(first, second) in &self.plugboard {
+            enigma.set_transposition(first, second)?;
+        }
+
+        Ok(enigma)
+    }
+}
+
+impl Display for DailyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [left, middle, right] = self.wheel_order;
+        write!(
+            f,
+            "model = {}; reflector = {}; rotors = {left} {middle} {right}; rings = {}",
+            self.model,
+            self.reflector,
+            self.ring_settings
+                .map(|ring| format!("{:02}", ring as u8 - b'A' + 1))
+                .join(" ")
+        )?;
+        if let Some(position) = self.reflector_position {
+            write!(f, "; reflector-position = {position}")?;
+        }
+        if !self.plugboard.is_empty() {
+            let plugs: Vec<String> = self
+                .plugboard
+                .iter()
+                .map(|&(first, second)| format!("{first}{second}"))
+                .collect();
+            write!(f, "; plugboard = {}", plugs.join(" "))?;
+        }
+        write!(f, "; positions = {}", String::from_iter(self.positions))
+    }
+}
+
+impl FromStr for DailyKey {
+    type Err = KeyError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut fields: Vec<(&str, &str)> = Vec::new();
+        for field in text
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|field| !field.is_empty() && !field.starts_with('#'))
+        {
+            let (name, value) = field
+                .split_once('=')
+                .ok_or_else(|| KeyError::MalformedField(field.to_string()))?;
+            let name = name.trim();
+            if fields
+                .iter()
+                .any(|&(known, _)| known.eq_ignore_ascii_case(name))
+            {
+                return Err(KeyError::RepeatedField(name.to_string()));
+            }
+            fields.push((name, value.trim()));
+        }
+
+        let mut take = |field: &'static str| {
+            fields
+                .iter()
+                .position(|(name, _)| name.eq_ignore_ascii_case(field))
+                .map(|index| fields.remove(index).1)
+        };
+
+        let model: MachineModel = take("model")
+            .ok_or(KeyError::MissingField("model"))?
+            .parse()?;
+        let wheel_order = parse_rotors(take("rotors").ok_or(KeyError::MissingField("rotors"))?)?;
+        let reflector = match take("reflector") {
+            Some(name) => name.parse()?,
+            None => model.allowed_reflectors()[0],
+        };
+        let reflector_position = take("reflector-position")
+            .map(|value| parse_letter("reflector-position", value))
+            .transpose()?;
+        let ring_settings = match take("rings") {
+            Some(value) => parse_rings(value)?,
+            None => ['A'; ROTORS],
+        };
+        let plugboard = match take("plugboard") {
+            Some(value) => parse_plugboard(value)?,
+            None => Vec::new(),
+        };
+        let positions = match take("positions") {
+            Some(value) => parse_positions(value)?,
+            None => ['A'; ROTORS],
+        };
+
+        if let Some(&(name, _)) = fields.first() {
+            return Err(KeyError::UnknownField(name.to_string()));
+        }
+        if !plugboard.is_empty() && !model.has_plugboard() {
+            return Err(KeyError::NoPlugboard(model));
+        }
+        if reflector_position.is_some() && !model.has_settable_reflector() {
+            return Err(KeyError::FixedReflector(model));
+        }
+
+        Ok(Self {
+            model,
+            wheel_order,
+            reflector,
+            reflector_position,
+            ring_settings,
+            plugboard,
+            positions,
+        })
+    }
+}
+
+fn parse_rotors(value: &str) -> Result<[RotorType; ROTORS], KeyError> {
+    let rotors = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<RotorType>, _>>()?;
+
+    rotors.try_into().map_err(|_| KeyError::InvalidValue {
+        field: "rotors",
+        value: value.to_string(),
+    })
+}
+
+/// Rings are given as numbers from 01 to 26, the way key sheets print them, or as letters.
+fn parse_rings(value: &str) -> Result<[char; ROTORS], KeyError> {
+    let invalid = |value: &str| KeyError::InvalidValue {
+        field: "rings",
+        value: value.to_string(),
+    };
+
+    crate::parse_rings(value)
+        .map_err(|error| invalid(error.value))?
+        .try_into()
+        .map_err(|_| invalid(value))
+}
+
+fn parse_plugboard(value: &str) -> Result<Vec<(char, char)>, KeyError> {
+    crate::parse_plugboard(value).map_err(|invalid| KeyError::InvalidValue {
+        field: "plugboard",
+        value: invalid.value.to_string(),
+    })
+}
+
+fn parse_positions(value: &str) -> Result<[char; ROTORS], KeyError> {
+    let positions = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| parse_letter("positions", &c.to_string()))
+        .collect::<Result<Vec<char>, _>>()?;
+
+    positions.try_into().map_err(|_| KeyError::InvalidValue {
+        field: "positions",
+        value: value.to_string(),
+    })
+}
+
+fn parse_letter(field: &'static str, value: &str) -> Result<char, KeyError> {
+    let mut chars = value.chars().map(|c| c.to_ascii_uppercase());
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if LATIN.contains(letter) => Ok(letter),
+        _ => Err(KeyError::InvalidValue {
+            field,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// One day of a [`KeySheet`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetDay {
+    pub day: usize,
+    pub key: DailyKey,
+    /// The groups that mark a message as enciphered with this day's key.
+    pub kenngruppen: [String; KENNGRUPPEN],
+}
+
+///
+/// A month of daily keys, printed as a table with [`Display`]. Like the historical sheets, the table
+/// lists the last day first, so the used days can be cut off the bottom.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySheet {
+    pub model: MachineModel,
+    pub days: Vec<SheetDay>,
+}
+
+///
+/// Generates a month of keys for `model`, reproducible from `seed`.
+///
+/// The keys follow the historical rules for issuing keys: no wheel order is used twice in the
+/// month, no rotor stays in the same slot on consecutive days, and the ten plugboard cables never
+/// join letters that are next to each other in the alphabet. The Kenngruppen are unique in the
+/// month.
+///
+pub fn generate_month(seed: u64, model: MachineModel) -> Result<KeySheet, KeyError> {
+    if model.alphabet_size() != LATIN.size() {
+        return Err(KeyError::Model(ModelError::WrongAlphabet));
+    }
+
+    let mut rng = Rng::new(seed);
+    let wheel_orders = month_of_wheel_orders(model, &mut rng)?;
+    let mut used_groups: Vec<String> = Vec::new();
+    let days = wheel_orders
+        .into_iter()
+        .enumerate()
+        .map(|(index, wheel_order)| {
+            let reflectors = model.allowed_reflectors();
+            let key = DailyKey {
+                model,
+                wheel_order,
+                reflector: reflectors[rng.below(reflectors.len())],
+                reflector_position: model
+                    .has_settable_reflector()
+                    .then(|| random_letter(&mut rng)),
+                ring_settings: std::array::from_fn(|_| random_letter(&mut rng)),
+                plugboard: match model.has_plugboard() {
+                    true => non_adjacent_plugs(&mut rng),
+                    false => Vec::new(),
+                },
+                positions: std::array::from_fn(|_| random_letter(&mut rng)),
+            };
+            let kenngruppen = std::array::from_fn(|_| {
+                loop {
+                    let group: String = (0..KENNGRUPPE_LENGTH)
+                        .map(|_| random_letter(&mut rng))
+                        .collect();
+                    if !used_groups.contains(&group) {
+                        used_groups.push(group.clone());
+                        break group;
+                    }
+                }
+            });
+
+            SheetDay {
+                day: index + 1,
+                key,
+                kenngruppen,
+            }
+        })
+        .collect();
+
+    Ok(KeySheet { model, days })
+}
+
+/// Picks a wheel order for every day of the month, backtracking when the rules leave none.
+fn month_of_wheel_orders(
+    model: MachineModel,
+    rng: &mut Rng,
+) -> Result<Vec<[RotorType; ROTORS]>, KeyError> {
+    let rotors = model.allowed_rotors();
+    let mut candidates: Vec<[RotorType; ROTORS]> = Vec::new();
+    for &left in rotors {
+        for &middle in rotors.iter().filter(|&&middle| middle != left) {
+            for &right in rotors
+                .iter()
+                .filter(|&&right| right != left && right != middle)
+            {
+                candidates.push([left, middle, right]);
+            }
+        }
+    }
+    if candidates.len() < DAYS_IN_MONTH {
+        return Err(KeyError::TooFewRotors(model));
+    }
+    rng.shuffle(&mut candidates);
+
+    let mut month = Vec::with_capacity(DAYS_IN_MONTH);
+    match extend_month(&candidates, &mut month) {
+        true => Ok(month),
+        false => Err(KeyError::TooFewRotors(model)),
+    }
+}
+
+fn extend_month(candidates: &[[RotorType; ROTORS]], month: &mut Vec<[RotorType; ROTORS]>) -> bool {
+    if month.len() == DAYS_IN_MONTH {
+        return true;
+    }
+
+    for candidate in candidates {
+        let same_slot = month.last().is_some_and(|previous| {
+            previous
+                .iter()
+                .zip(candidate)
+                .any(|(previous, rotor)| previous == rotor)
+        });
+        if same_slot || month.contains(candidate) {
+            continue;
+        }
+
+        month.push(*candidate);
+        if extend_month(candidates, month) {
+            return true;
+        }
+        month.pop();
+    }
+
+    false
+}
+
+fn non_adjacent_plugs(rng: &mut Rng) -> Vec<(char, char)> {
+    loop {
+        let mut letters = *LATIN.symbols();
+        rng.shuffle(&mut letters);
+        let plugs: Vec<(char, char)> = letters
+            .chunks(2)
+            .take(PLUGBOARD_CABLES)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+
+        if plugs
+            .iter()
+            .all(|&(first, second)| (first as u8).abs_diff(second as u8) != 1)
+        {
+            return plugs;
+        }
+    }
+}
+
+impl Display for KeySheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plugboard_width = 3 * PLUGBOARD_CABLES - 1;
+        writeln!(f, "{} key sheet", self.model)?;
+        writeln!(
+            f,
+            "Day | UKW        | Walzenlage     | Ringstellung | {:plugboard_width$} | \
+             Grundstellung | Kenngruppen",
+            "Steckerverbindungen"
+        )?;
+
+        for sheet_day in self.days.iter().rev() {
+            let key = &sheet_day.key;
+            let [left, middle, right] = key.wheel_order.map(|rotor| rotor.to_string());
+            let rings = key
+                .ring_settings
+                .map(|ring| format!("{:02}", ring as u8 - b'A' + 1))
+                .join(" ");
+            let plugs: Vec<String> = key
+                .plugboard
+                .iter()
+                .map(|&(first, second)| format!("{first}{second}"))
+                .collect();
+            writeln!(
+                f,
+                "{:>3} | {:10} | {left:4} {middle:4} {right:4} | {rings:12} | {:plugboard_width$} | \
+                 {:13} | {}",
+                sheet_day.day,
+                key.reflector.to_string(),
+                plugs.join(" "),
+                String::from_iter(key.positions),
+                sheet_day.kenngruppen.join(" ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn random_letter(rng: &mut Rng) -> char {
+    LATIN.symbol(rng.below(LATIN.size()))
+}
+
+///
+/// A small seedable pseudo-random generator (SplitMix64), so generated keys can be reproduced from
+/// their seed without an external dependency. It is not suitable for real cryptographic keys.
+///
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`. Panics when `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.below(index + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_should_parse_and_set_up_the_machine() {
+        let key: DailyKey =
+            "model = Enigma-I; rotors = I II III; reflector = B; rings = 01 01 01; positions = AAA"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            key.create_enigma().unwrap().encrypt_str("AAAAA"),
+            Ok("BDZGO".to_string())
+        );
+    }
+
+    #[test]
+    fn random_keys_should_only_be_drawn_for_latin_models() {
+        let mut rng = Rng::new(1939);
+
+        assert_eq!(
+            DailyKey::random(MachineModel::Z30, &mut rng),
+            Err(ModelError::WrongAlphabet)
+        );
+        assert_eq!(
+            generate_month(1939, MachineModel::Z30).err(),
+            Some(KeyError::Model(ModelError::WrongAlphabet))
+        );
+    }
+
+    #[test]
+    fn key_should_round_trip_through_its_display() {
+        let mut rng = Rng::new(1939);
+        for model in [MachineModel::M3, MachineModel::CommercialK] {
+            let key = DailyKey::random(model, &mut rng).unwrap();
+
+            assert_eq!(key.to_string().parse(), Ok(key.clone()));
+            assert!(key.create_enigma().is_ok());
+        }
+    }
+
+    #[test]
+    fn key_file_should_allow_comments_and_lines() {
+        let key: DailyKey =
+            "# Day 14\nmodel = M3\nrotors = VI II V\nrings = A L V\nplugboard = ab cq"
+                .parse()
+                .unwrap();
+
+        assert_eq!(key.ring_settings, ['A', 'L', 'V']);
+        assert_eq!(key.plugboard, vec![('A', 'B'), ('C', 'Q')]);
+    }
+
+    #[test]
+    fn invalid_keys_should_be_rejected() {
+        assert_eq!(
+            "rotors = I II III".parse::<DailyKey>(),
+            Err(KeyError::MissingField("model"))
+        );
+        assert_eq!(
+            "model = M3; rotors = I II III; plugboard = AB BC".parse::<DailyKey>(),
+            Err(KeyError::InvalidValue {
+                field: "plugboard",
+                value: "BC".to_string()
+            })
+        );
+        assert_eq!(
+            "model = Enigma-D; rotors = D-I D-II D-III; plugboard = AB".parse::<DailyKey>(),
+            Err(KeyError::NoPlugboard(MachineModel::CommercialD))
+        );
+        assert_eq!(
+            "model = M3; rotors = I II III; colour = red".parse::<DailyKey>(),
+            Err(KeyError::UnknownField("colour".to_string()))
+        );
+    }
+
+    #[test]
+    fn rng_should_be_reproducible_from_its_seed() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+
+        assert!((0..100).all(|_| first.next_u64() == second.next_u64()));
+        assert!((0..1000).all(|_| first.below(26) < 26));
+    }
+
+    #[test]
+    fn month_should_follow_the_historical_rules() {
+        let sheet = generate_month(1941, MachineModel::M3).unwrap();
+
+        assert_eq!(sheet.days.len(), DAYS_IN_MONTH);
+        for (index, day) in sheet.days.iter().enumerate() {
+            let key = &day.key;
+            assert!(key.create_enigma().is_ok());
+            assert_eq!(key.plugboard.len(), PLUGBOARD_CABLES);
+            assert!(
+                key.plugboard
+                    .iter()
+                    .all(|&(first, second)| (first as u8).abs_diff(second as u8) != 1)
+            );
+            assert!(
+                sheet.days[..index]
+                    .iter()
+                    .all(|earlier| earlier.key.wheel_order != key.wheel_order)
+            );
+            if index > 0 {
+                let previous = sheet.days[index - 1].key.wheel_order;
+                assert!((0..ROTORS).all(|slot| previous[slot] != key.wheel_order[slot]));
+            }
+        }
+
+        let mut groups: Vec<&String> = sheet.days.iter().flat_map(|day| &day.kenngruppen).collect();
+        groups.sort();
+        groups.dedup();
+        assert_eq!(groups.len(), DAYS_IN_MONTH * KENNGRUPPEN);
+    }
+
+    #[test]
+    fn month_should_be_reproducible_and_printable() {
+        let sheet = generate_month(7, MachineModel::EnigmaI).unwrap();
+
+        assert_eq!(sheet, generate_month(7, MachineModel::EnigmaI).unwrap());
+        assert_ne!(sheet, generate_month(8, MachineModel::EnigmaI).unwrap());
+
+        let table = sheet.to_string();
+        assert_eq!(table.lines().count(), DAYS_IN_MONTH + 2);
+        assert!(table.lines().nth(2).unwrap().starts_with(" 31 | "));
+    }
+
+    #[test]
+    fn month_should_need_enough_rotors() {
+        assert_eq!(
+            generate_month(1, MachineModel::CommercialD),
+            Err(KeyError::TooFewRotors(MachineModel::CommercialD))
+        );
+    }
+}
@@ -1,44 +1,152 @@
-use crate::consts::{ALPHABET_SIZE, FIRST_LETTER, LAST_LETTER};
+use crate::alphabet::{Alphabet, LATIN};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::WiringError;
 pub(crate) mod utils;
 
-#[derive(Debug, Clone, Copy)]
-pub struct LetterPermutation<'a> {
-    permutation: &'a [(char, char); ALPHABET_SIZE],
+/// Declares a static permutation table together with its inverse. The size defaults to the Latin alphabet.
+macro_rules! bidir_map {
+    ($forward:ident, $inverse:ident, {$($k:expr => $v:expr),*$(,)?}) => {
+        $crate::letter_permutation::bidir_map!($forward, $inverse, $crate::consts::ALPHABET_SIZE, {$($k => $v),*});
+    };
+    ($forward:ident, $inverse:ident, $size:expr, {$($k:expr => $v:expr),*$(,)?}) => {
+        static $forward: [(char, char); $size] = [
+            $(($k, $v)),*
+        ];
+
+        static $inverse: [(char, char); $size] =
+            $crate::letter_permutation::utils::reverse_permutation($forward);
+    };
+}
+
+pub(crate) use bidir_map;
+
+///
+/// A bijective map of an alphabet onto itself, kept as the contact index every symbol maps to.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LetterPermutation<const N: usize = ALPHABET_SIZE> {
+    mapping: [u8; N],
+    alphabet: &'static Alphabet<N>,
 }
 
 #[derive(PartialEq, Debug)]
 pub(crate) enum PermutationError {
-    NotUppercaseLetter,
+    NotInAlphabet,
 }
 
-impl<'a> LetterPermutation<'a> {
-    pub(crate) fn new(permutation: &'a [(char, char); ALPHABET_SIZE]) -> Self {
-        let mut values_exist = [false; ALPHABET_SIZE];
+impl LetterPermutation {
+    pub(crate) fn new(permutation: &[(char, char); ALPHABET_SIZE]) -> Result<Self, WiringError> {
+        Self::with_alphabet(permutation, &LATIN)
+    }
 
-        permutation.iter().zip(FIRST_LETTER..=LAST_LETTER).for_each(|(&(key, value), alphabet_letter)| {
-            if key != alphabet_letter {
-                panic!("Expected permutation keys to be ordered alphabetically uppercase letters. Found {key}, expected {alphabet_letter}.");
-            }
+    ///
+    /// Creates one of the crate's own wiring tables, which the tests check. Panics on an invalid
+    /// table, so use [`new`](Self::new) for anything else.
+    ///
+    pub(crate) fn from_table(permutation: &[(char, char); ALPHABET_SIZE]) -> Self {
+        Self::new(permutation).unwrap_or_else(|error| panic!("Invalid wiring table: {error}"))
+    }
+}
+
+impl<const N: usize> LetterPermutation<N> {
+    /// Creates a permutation from a table of `(symbol, mapped)` pairs in alphabet order.
+    pub(crate) fn with_alphabet(
+        permutation: &[(char, char); N],
+        alphabet: &'static Alphabet<N>,
+    ) -> Result<Self, WiringError> {
+        if let Some((&(found, _), &expected)) = permutation
+            .iter()
+            .zip(alphabet.symbols())
+            .find(|&(&(key, _), symbol)| key != *symbol)
+        {
+            return Err(WiringError::OutOfOrder { expected, found });
+        }
+
+        let values: String = permutation.iter().map(|&(_, value)| value).collect();
+        Self::from_wiring(&values, alphabet)
+    }
+
+    /// Like [`from_table`](LetterPermutation::from_table), for any alphabet.
+    pub(crate) fn from_table_with_alphabet(
+        permutation: &[(char, char); N],
+        alphabet: &'static Alphabet<N>,
+    ) -> Self {
+        Self::with_alphabet(permutation, alphabet)
+            .unwrap_or_else(|error| panic!("Invalid wiring table: {error}"))
+    }
 
-            if values_exist[LetterPermutation::get_letter_index(value)] {
-                panic!("The letter {value} is mapped to twice. Permutations should be bijective maps.");
+    ///
+    /// Creates a permutation from its wiring, the symbols the alphabet's symbols map to in order.
+    /// Lowercase letters are accepted for uppercase ones.
+    ///
+    pub(crate) fn from_wiring(
+        wiring: &str,
+        alphabet: &'static Alphabet<N>,
+    ) -> Result<Self, WiringError> {
+        let length = wiring.chars().count();
+        if length != N {
+            return Err(WiringError::WrongLength {
+                expected: N,
+                got: length,
+            });
+        }
+
+        let mut mapping = [0; N];
+        let mut values_exist = [false; N];
+        for (slot, value) in mapping.iter_mut().zip(wiring.chars()) {
+            let index = alphabet
+                .index_of(value)
+                .or_else(|| alphabet.index_of(value.to_ascii_uppercase()))
+                .ok_or(WiringError::NotInAlphabet(value))?;
+            if values_exist[index] {
+                return Err(WiringError::Repeated(value));
             }
 
-            values_exist[LetterPermutation::get_letter_index(value)] = true;
-        });
+            values_exist[index] = true;
+            *slot = index as u8;
+        }
+
+        Ok(Self { mapping, alphabet })
+    }
 
-        Self { permutation }
+    /// Creates a permutation from the contact index every contact maps to, which must be bijective.
+    pub(crate) fn from_mapping(mapping: [u8; N], alphabet: &'static Alphabet<N>) -> Self {
+        Self { mapping, alphabet }
     }
 
-    fn get_letter_index(letter: char) -> usize {
-        (letter as u8 - FIRST_LETTER as u8) as usize
+    pub(crate) fn alphabet(&self) -> &'static Alphabet<N> {
+        self.alphabet
     }
 
     pub(crate) fn get(&self, letter: char) -> Result<char, PermutationError> {
-        letter
-            .is_ascii_uppercase()
-            .then(|| self.permutation[LetterPermutation::get_letter_index(letter)].1)
-            .ok_or(PermutationError::NotUppercaseLetter)
+        self.alphabet
+            .index_of(letter)
+            .map(|index| self.alphabet.symbol(self.get_index(index)))
+            .ok_or(PermutationError::NotInAlphabet)
+    }
+
+    /// Maps a contact index rather than a symbol.
+    pub(crate) fn get_index(&self, index: usize) -> usize {
+        self.mapping[index] as usize
+    }
+
+    pub(crate) fn inverse(&self) -> Self {
+        let mut mapping = [0; N];
+        for (index, &mapped) in self.mapping.iter().enumerate() {
+            mapping[mapped as usize] = index as u8;
+        }
+
+        Self {
+            mapping,
+            alphabet: self.alphabet,
+        }
+    }
+
+    /// The symbols the alphabet's symbols map to, in alphabet order.
+    pub(crate) fn wiring(&self) -> String {
+        (0..N)
+            .map(|index| self.alphabet.symbol(self.get_index(index)))
+            .collect()
     }
 }
 
@@ -47,8 +155,7 @@ mod tests {
     use super::*;
 
     #[test]
-    #[should_panic]
-    fn should_panic_when_not_alphabetic() {
+    fn should_reject_keys_out_of_order() {
         let perm = [
             ('A', 'F'),
             ('B', 'V'),
@@ -78,12 +185,17 @@ mod tests {
             ('Z', 'L'),
         ];
 
-        LetterPermutation::new(&perm);
+        assert_eq!(
+            LetterPermutation::new(&perm),
+            Err(WiringError::OutOfOrder {
+                expected: 'H',
+                found: '8'
+            })
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn should_panic_when_not_uppercase() {
+    fn should_reject_lowercase_keys() {
         let perm = [
             ('A', 'F'),
             ('B', 'V'),
@@ -113,21 +225,26 @@ mod tests {
             ('Z', 'L'),
         ];
 
-        LetterPermutation::new(&perm);
+        assert_eq!(
+            LetterPermutation::new(&perm),
+            Err(WiringError::OutOfOrder {
+                expected: 'C',
+                found: 'c'
+            })
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn should_panic_when_not_bijective() {
+    fn should_reject_permutations_that_are_not_bijective() {
         let perm = [
             ('A', 'F'),
             ('B', 'V'),
-            ('c', 'P'),
+            ('C', 'P'),
             ('D', 'V'),
             ('E', 'I'),
             ('F', 'A'),
             ('G', 'O'),
-            ('8', 'Y'),
+            ('H', 'Y'),
             ('I', 'E'),
             ('J', 'D'),
             ('K', 'R'),
@@ -148,7 +265,10 @@ mod tests {
             ('Z', 'L'),
         ];
 
-        LetterPermutation::new(&perm);
+        assert_eq!(
+            LetterPermutation::new(&perm),
+            Err(WiringError::Repeated('V'))
+        );
     }
 
     #[test]
@@ -182,8 +302,8 @@ mod tests {
             ('Z', 'L'),
         ];
 
-        let perm = LetterPermutation::new(&perm);
-        assert_eq!(perm.get('t'), Err(PermutationError::NotUppercaseLetter))
+        let perm = LetterPermutation::new(&perm).unwrap();
+        assert_eq!(perm.get('t'), Err(PermutationError::NotInAlphabet))
     }
 
     #[test]
@@ -217,7 +337,7 @@ mod tests {
             ('Z', 'L'),
         ];
 
-        let perm = LetterPermutation::new(&perm);
+        let perm = LetterPermutation::new(&perm).unwrap();
         assert_eq!(perm.get('J'), Ok('D'))
     }
 }
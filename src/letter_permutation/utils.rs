@@ -1,29 +1,21 @@
-use crate::consts::ALPHABET_SIZE;
+///
+/// Inverts a permutation table whose keys are ordered like its alphabet, keeping that key order.
+///
+pub(crate) const fn reverse_permutation<const N: usize>(
+    permutation: [(char, char); N],
+) -> [(char, char); N] {
+    let mut inverse = permutation;
 
-pub(crate) const fn reverse_permutation(
-    mut permutation: [(char, char); ALPHABET_SIZE],
-) -> [(char, char); ALPHABET_SIZE] {
     let mut i = 0;
-    while i < ALPHABET_SIZE {
-        permutation[i] = (permutation[i].1, permutation[i].0);
-        i += 1;
-    }
-
-    let mut i = 0;
-
-    while i < ALPHABET_SIZE {
+    while i < N {
         let mut j = 0;
-        while j + 1 < ALPHABET_SIZE - i {
-            if permutation[j].0 > permutation[j + 1].0 {
-                let temp = permutation[j];
-                permutation[j] = permutation[j + 1];
-                permutation[j + 1] = temp;
-            }
+        while permutation[j].0 != permutation[i].1 {
             j += 1;
         }
 
+        inverse[j] = (permutation[i].1, permutation[i].0);
         i += 1;
     }
 
-    permutation
+    inverse
 }
@@ -1,54 +1,214 @@
+pub mod alphabet;
+pub mod analysis;
+pub mod builder;
+pub mod catalogue;
+pub mod config;
 mod consts;
+pub mod entry_wheels;
 pub mod error;
+pub mod keys;
 mod letter_permutation;
+pub mod models;
+pub mod observer;
+pub mod permutation;
 pub mod reflectors;
 pub mod rotor;
 pub mod rotors;
 pub mod rotors_controller;
-
+pub mod search;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod stepping;
+pub mod trace;
+pub mod typex;
+
+use alphabet::Alphabet;
+use builder::EnigmaBuilder;
+use entry_wheels::EntryWheel;
 use reflectors::Reflector;
 use rotor::Rotor;
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use consts::ALPHABET_SIZE;
 use error::Error;
 
-use crate::rotors_controller::RotorsController;
-
-#[derive(Debug)]
-pub struct Enigma {
-    rotor_controller: RotorsController,
-    reflector: Reflector,
+use crate::models::MachineModel;
+use crate::observer::{Observer, SettingsChange};
+use crate::permutation::Permutation;
+use crate::rotors_controller::{RotorRole, RotorsController};
+use crate::stepping::SteppingStrategy;
+use crate::trace::{Stage, Trace};
+
+#[derive(Debug, Clone)]
+pub struct Enigma<const N: usize = ALPHABET_SIZE> {
+    rotor_controller: RotorsController<N>,
+    reflector: Reflector<N>,
+    entry_wheel: EntryWheel<N>,
     transpositions: HashMap<char, char>,
+    observer: Option<Arc<dyn Observer>>,
+    model: Option<MachineModel>,
 }
 
 impl Enigma {
+    /// Starts assembling a machine from the names of its parts, see [`EnigmaBuilder`].
+    pub fn builder() -> EnigmaBuilder {
+        EnigmaBuilder::new()
+    }
+
     pub fn new(
         left_rotor: Rotor,
         middle_rotor: Rotor,
         right_rotor: Rotor,
         reflector: Reflector,
+    ) -> Self {
+        Self::with_entry_wheel(
+            left_rotor,
+            middle_rotor,
+            right_rotor,
+            reflector,
+            entry_wheels::create_entry_wheel_identity(),
+        )
+    }
+}
+
+impl<const N: usize> Enigma<N> {
+    ///
+    /// Creates a machine for any alphabet. The entry wheel decides the alphabet the keyboard uses,
+    /// so it must match the rotors' and reflector's.
+    ///
+    pub fn with_entry_wheel(
+        left_rotor: Rotor<N>,
+        middle_rotor: Rotor<N>,
+        right_rotor: Rotor<N>,
+        reflector: Reflector<N>,
+        entry_wheel: EntryWheel<N>,
+    ) -> Self {
+        Self::with_controller(
+            RotorsController::new(left_rotor, middle_rotor, right_rotor),
+            reflector,
+            entry_wheel,
+        )
+    }
+
+    ///
+    /// Creates a machine with any number of rotors, leftmost first, such as the four-rotor M4 with
+    /// its greek wheel. See [`RotorsController::with_roles`] for how roles may be arranged.
+    ///
+    pub fn with_rotor_stack(
+        rotors: Vec<(Rotor<N>, RotorRole)>,
+        reflector: Reflector<N>,
+        entry_wheel: EntryWheel<N>,
+    ) -> Result<Self, Error> {
+        Ok(Self::with_controller(
+            RotorsController::with_roles(rotors)?,
+            reflector,
+            entry_wheel,
+        ))
+    }
+
+    fn with_controller(
+        rotor_controller: RotorsController<N>,
+        reflector: Reflector<N>,
+        entry_wheel: EntryWheel<N>,
     ) -> Self {
         Self {
-            rotor_controller: RotorsController::new(left_rotor, middle_rotor, right_rotor),
+            rotor_controller,
             reflector,
+            entry_wheel,
             transpositions: HashMap::new(),
+            observer: None,
+            model: None,
         }
     }
 
     pub fn encrypt_char(&mut self, letter: char) -> Result<char, Error> {
-        let letter = letter.to_ascii_uppercase();
+        let reflector_steps = self.rotor_controller.increment();
+        self.reflector.increment_by(reflector_steps);
 
-        let enciphered = *self.transpositions.get(&letter).unwrap_or(&letter);
-        let enciphered = self.rotor_controller.increment_and_map(enciphered)?;
+        let output = self.peak_without_increment(letter)?;
+        if let Some(observer) = &self.observer {
+            observer.on_keypress(letter.to_ascii_uppercase(), output);
+        }
 
-        let enciphered = self
-            .reflector
-            .map
-            .get(enciphered)
-            .map_err(|_| Error::NonAlphabetic)?;
+        Ok(output)
+    }
 
-        let enciphered = self.rotor_controller.inverse_map_letter(enciphered)?;
-        Ok(*self.transpositions.get(&enciphered).unwrap_or(&enciphered))
+    ///
+    /// Reports key presses, rotor steps and settings changes to `observer` from now on, replacing
+    /// any previous observer.
+    ///
+    pub fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.rotor_controller.set_observer(observer.clone());
+        self.observer = Some(observer);
+    }
+
+    pub fn clear_observer(&mut self) -> Option<Arc<dyn Observer>> {
+        self.rotor_controller.clear_observer();
+        self.observer.take()
+    }
+
+    ///
+    /// Encrypts a letter like [`Enigma::encrypt_char`] and records how it got there: which rotors
+    /// stepped and why, the positions after stepping, and the letter leaving every stage.
+    ///
+    pub fn encrypt_char_traced(&mut self, letter: char) -> Result<Trace, Error> {
+        let reflector_before = self.reflector.get_position();
+        let (reflector_steps, steps) = self.rotor_controller.increment_traced();
+        self.reflector.increment_by(reflector_steps);
+        let positions = self.rotor_controller.get_positions();
+
+        let mut stages = Vec::with_capacity(2 * positions.len() + 5);
+        let mut pass = |stage: Stage, letter: Result<char, Error>| {
+            let letter = letter?;
+            stages.push((stage, letter));
+            Ok::<char, Error>(letter)
+        };
+
+        let input = letter.to_ascii_uppercase();
+        let mut enciphered = pass(
+            Stage::PlugboardIn,
+            Ok(*self.transpositions.get(&input).unwrap_or(&input)),
+        )?;
+        enciphered = pass(Stage::EntryWheelIn, self.entry_wheel.map_letter(enciphered))?;
+        for index in (0..self.rotor_controller.len()).rev() {
+            enciphered = pass(
+                Stage::RotorForward(index),
+                self.rotor_controller
+                    .get_rotor(index)
+                    .map_letter(enciphered),
+            )?;
+        }
+        enciphered = pass(Stage::Reflector, self.reflector.map_letter(enciphered))?;
+        for index in 0..self.rotor_controller.len() {
+            enciphered = pass(
+                Stage::RotorBackward(index),
+                self.rotor_controller
+                    .get_rotor(index)
+                    .inverse_map_letter(enciphered),
+            )?;
+        }
+        enciphered = pass(
+            Stage::EntryWheelOut,
+            self.entry_wheel.inverse_map_letter(enciphered),
+        )?;
+        let output = pass(
+            Stage::PlugboardOut,
+            Ok(*self.transpositions.get(&enciphered).unwrap_or(&enciphered)),
+        )?;
+        if let Some(observer) = &self.observer {
+            observer.on_keypress(input, output);
+        }
+
+        Ok(Trace {
+            input,
+            steps,
+            reflector_stepped: self.reflector.get_position() != reflector_before,
+            positions,
+            reflector_position: self.reflector.get_position(),
+            stages,
+            output,
+        })
     }
 
     pub fn encrypt_string(&mut self, text: String) -> Result<String, Error> {
@@ -64,7 +224,8 @@ impl Enigma {
     }
 
     pub fn increment_by(&mut self, amount: usize) {
-        self.rotor_controller.increment_by(amount);
+        let reflector_steps = self.rotor_controller.increment_by(amount);
+        self.reflector.increment_by(reflector_steps);
     }
 
     ///
@@ -72,19 +233,20 @@ impl Enigma {
     ///
     /// The encryption works the same - increment rotor first, then calculate the character through all the permutations.
     /// At the end of the encryption process the rotors are returned to their original location.
+    /// The observer isn't told about the peek.
     ///
     pub fn peak_cipher(&mut self, char: char) -> Result<char, Error> {
-        let rotor_positions = (
-            self.rotor_controller.get_left_position(),
-            self.rotor_controller.get_middle_position(),
-            self.rotor_controller.get_right_position(),
-        );
+        let observer = self.clear_observer();
+        let rotor_controller = self.rotor_controller.clone();
+        let reflector = self.reflector;
 
         let encryption_result = self.encrypt_char(char);
 
-        self.set_left_rotor_position_from_char(rotor_positions.0);
-        self.set_middle_rotor_position_from_char(rotor_positions.1);
-        self.set_right_rotor_position_from_char(rotor_positions.2);
+        self.rotor_controller = rotor_controller;
+        self.reflector = reflector;
+        if let Some(observer) = observer {
+            self.set_observer(observer);
+        }
 
         encryption_result
     }
@@ -96,39 +258,54 @@ impl Enigma {
         let letter = letter.to_ascii_uppercase();
 
         let enciphered = *self.transpositions.get(&letter).unwrap_or(&letter);
+        let enciphered = self.entry_wheel.map_letter(enciphered)?;
         let enciphered = self.rotor_controller.map_letter(enciphered)?;
 
-        let enciphered = self
-            .reflector
-            .map
-            .get(enciphered)
-            .map_err(|_| Error::NonAlphabetic)?;
+        let enciphered = self.reflector.map_letter(enciphered)?;
 
         let enciphered = self.rotor_controller.inverse_map_letter(enciphered)?;
+        let enciphered = self.entry_wheel.inverse_map_letter(enciphered)?;
 
         Ok(*self.transpositions.get(&enciphered).unwrap_or(&enciphered))
     }
 
-    pub fn set_transposition(&mut self, first: char, second: char) {
-        let (first, second) = match (first.is_alphabetic(), second.is_alphabetic()) {
-            (true, true) => (first.to_ascii_uppercase(), second.to_ascii_uppercase()),
-            (false, _) => panic!("Transposition characters must be letters but got '{first}'"),
-            (_, false) => panic!("Transposition characters must be letters but got '{second}'"),
-        };
-
-        if first == second {
-            return;
+    ///
+    /// Returns the substitution the whole machine, plugboard included, applies to a key pressed
+    /// while the rotors show `positions`, leftmost first. The rotors are taken as already stepped
+    /// and the machine itself isn't changed.
+    ///
+    pub fn permutation_at(&self, positions: &[char]) -> Result<Permutation<N>, Error> {
+        let mut machine = self.clone();
+        machine.clear_observer();
+        machine.set_rotor_positions(positions)?;
+
+        let alphabet = self.entry_wheel.alphabet();
+        let mut mapping = [0; N];
+        for (index, image) in mapping.iter_mut().enumerate() {
+            let enciphered = machine.peak_without_increment(alphabet.symbol(index))?;
+            *image = alphabet
+                .index_of(enciphered)
+                .ok_or(Error::InvalidLetter(enciphered))? as u8;
         }
 
-        if let Some(value) = self.transpositions.remove(&first) {
-            self.transpositions.remove(&value);
-        }
-        if let Some(value) = self.transpositions.remove(&second) {
-            self.transpositions.remove(&value);
+        Ok(Permutation::from_mapping(mapping, alphabet))
+    }
+
+    ///
+    /// Plugs a cable between the two letters. Fails when either letter already has a cable to
+    /// another letter; unplug it with [`clear_transposition`](Self::clear_transposition) first.
+    ///
+    pub fn set_transposition(&mut self, first: char, second: char) -> Result<(), Error> {
+        if let Some((first, second)) = plug(
+            &mut self.transpositions,
+            self.entry_wheel.alphabet(),
+            first,
+            second,
+        )? {
+            self.notify(SettingsChange::Transposition { first, second });
         }
 
-        self.transpositions.insert(first, second);
-        self.transpositions.insert(second, first);
+        Ok(())
     }
 
     pub fn get_transpositions(&self) -> &HashMap<char, char> {
@@ -138,6 +315,7 @@ impl Enigma {
     pub fn clear_transposition(&mut self, letter: char) -> Option<char> {
         if let Some(removed) = self.transpositions.remove(&letter) {
             self.transpositions.remove(&removed);
+            self.notify(SettingsChange::TranspositionCleared { letter });
             return Some(removed);
         }
 
@@ -146,22 +324,82 @@ impl Enigma {
 
     pub fn clear_transpositions(&mut self) {
         self.transpositions.clear();
+        self.notify(SettingsChange::TranspositionsCleared);
+    }
+
+    pub fn rotor_count(&self) -> usize {
+        self.rotor_controller.len()
+    }
+
+    /// Replaces the rotor at `index`, counting from the leftmost rotor.
+    pub fn set_rotor(&mut self, index: usize, rotor: Rotor<N>) -> Result<(), Error> {
+        self.rotor_controller.set_rotor(index, rotor)
+    }
+
+    /// Sets the position of every rotor, leftmost first.
+    pub fn set_rotor_positions(&mut self, positions: &[char]) -> Result<(), Error> {
+        self.rotor_controller.set_positions(positions)
+    }
+
+    pub fn get_rotor_positions(&self) -> Vec<char> {
+        self.rotor_controller.get_positions()
+    }
+
+    /// Sets the ring setting of every rotor, leftmost first.
+    pub fn set_ring_settings(&mut self, ring_settings: &[char]) -> Result<(), Error> {
+        self.rotor_controller.set_ring_settings(ring_settings)
+    }
+
+    pub fn get_ring_settings(&self) -> Vec<char> {
+        self.rotor_controller.get_ring_settings()
     }
 
-    pub fn set_left_rotor(&mut self, rotor: Rotor) {
+    pub fn set_left_rotor(&mut self, rotor: Rotor<N>) {
         self.rotor_controller.set_left_rotor(rotor);
     }
 
-    pub fn set_middle_rotor(&mut self, rotor: Rotor) {
+    pub fn set_middle_rotor(&mut self, rotor: Rotor<N>) {
         self.rotor_controller.set_middle_rotor(rotor);
     }
 
-    pub fn set_right_rotor(&mut self, rotor: Rotor) {
+    pub fn set_right_rotor(&mut self, rotor: Rotor<N>) {
         self.rotor_controller.set_right_rotor(rotor);
     }
 
-    pub fn set_reflector(&mut self, reflector: Reflector) {
+    pub fn set_reflector(&mut self, reflector: Reflector<N>) {
         self.reflector = reflector;
+        self.notify(SettingsChange::Reflector);
+    }
+
+    pub fn set_reflector_position(&mut self, position: char) -> Result<(), Error> {
+        self.reflector.set_position(position)?;
+        self.notify(SettingsChange::ReflectorPosition {
+            position: self.reflector.get_position(),
+        });
+        Ok(())
+    }
+
+    pub fn get_reflector_position(&self) -> char {
+        self.reflector.get_position()
+    }
+
+    pub fn set_entry_wheel(&mut self, entry_wheel: EntryWheel<N>) {
+        self.entry_wheel = entry_wheel;
+        self.notify(SettingsChange::EntryWheel);
+    }
+
+    pub fn set_stepping_strategy<S: SteppingStrategy<N> + 'static>(&mut self, stepping: S) {
+        self.rotor_controller
+            .set_stepping_strategy(Arc::new(stepping));
+    }
+
+    pub fn get_stepping_strategy(&self) -> &dyn SteppingStrategy<N> {
+        self.rotor_controller.get_stepping_strategy()
+    }
+
+    /// The model the machine was assembled as, if it was created through [`MachineModel`].
+    pub fn get_model(&self) -> Option<MachineModel> {
+        self.model
     }
 
     pub fn get_left_rotor_position(&self) -> char {
@@ -176,44 +414,177 @@ impl Enigma {
         self.rotor_controller.get_right_position()
     }
 
-    pub fn set_left_rotor_position_from_char(&mut self, position: char) {
+    pub fn set_left_rotor_position_from_char(&mut self, position: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_left_rotor_position_from_char(position);
+            .set_left_rotor_position_from_char(position)
     }
 
-    pub fn set_middle_rotor_position_from_char(&mut self, position: char) {
+    pub fn set_middle_rotor_position_from_char(&mut self, position: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_middle_rotor_position_from_char(position);
+            .set_middle_rotor_position_from_char(position)
     }
 
-    pub fn set_right_rotor_position_from_char(&mut self, position: char) {
+    pub fn set_right_rotor_position_from_char(&mut self, position: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_right_rotor_position_from_char(position);
+            .set_right_rotor_position_from_char(position)
     }
 
-    pub fn set_left_rotor_position_from_int(&mut self, position: usize) {
+    pub fn set_left_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_left_rotor_position_from_int(position);
+            .set_left_rotor_ring_setting(ring_setting)
     }
 
-    pub fn set_middle_rotor_position_from_int(&mut self, position: usize) {
+    pub fn set_middle_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_middle_rotor_position_from_int(position);
+            .set_middle_rotor_ring_setting(ring_setting)
     }
 
-    pub fn set_right_rotor_position_from_int(&mut self, position: usize) {
+    pub fn set_right_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
         self.rotor_controller
-            .set_right_rotor_position_from_int(position);
+            .set_right_rotor_ring_setting(ring_setting)
+    }
+
+    pub fn get_left_rotor_ring_setting(&self) -> char {
+        self.rotor_controller.get_left_ring_setting()
+    }
+
+    pub fn get_middle_rotor_ring_setting(&self) -> char {
+        self.rotor_controller.get_middle_ring_setting()
+    }
+
+    pub fn get_right_rotor_ring_setting(&self) -> char {
+        self.rotor_controller.get_right_ring_setting()
+    }
+
+    pub fn set_left_rotor_position_from_int(&mut self, position: usize) -> Result<(), Error> {
+        self.rotor_controller
+            .set_left_rotor_position_from_int(position)
+    }
+
+    pub fn set_middle_rotor_position_from_int(&mut self, position: usize) -> Result<(), Error> {
+        self.rotor_controller
+            .set_middle_rotor_position_from_int(position)
+    }
+
+    pub fn set_right_rotor_position_from_int(&mut self, position: usize) -> Result<(), Error> {
+        self.rotor_controller
+            .set_right_rotor_position_from_int(position)
+    }
+
+    fn notify(&self, change: SettingsChange) {
+        if let Some(observer) = &self.observer {
+            observer.on_settings_change(change);
+        }
     }
 }
 
+///
+/// Plugs a cable between two letters of `alphabet` and returns the letters it joined, or `None`
+/// when there is nothing to plug because they are the same letter or already joined.
+///
+pub(crate) fn plug<const N: usize>(
+    transpositions: &mut HashMap<char, char>,
+    alphabet: &Alphabet<N>,
+    first: char,
+    second: char,
+) -> Result<Option<(char, char)>, Error> {
+    for letter in [first, second] {
+        if !alphabet.contains(letter.to_ascii_uppercase()) {
+            return Err(Error::InvalidLetter(letter));
+        }
+    }
+
+    let (first, second) = (first.to_ascii_uppercase(), second.to_ascii_uppercase());
+    if first == second || transpositions.get(&first) == Some(&second) {
+        return Ok(None);
+    }
+    if let Some(&letter) = [first, second]
+        .iter()
+        .find(|letter| transpositions.contains_key(letter))
+    {
+        return Err(Error::DuplicatePlug(letter));
+    }
+
+    transpositions.insert(first, second);
+    transpositions.insert(second, first);
+    Ok(Some((first, second)))
+}
+
+/// A plugboard cable or ring setting that couldn't be read, and the machine's reason if any.
+#[derive(Debug, PartialEq)]
+pub(crate) struct InvalidSetting<'a> {
+    pub value: &'a str,
+    pub error: Option<Error>,
+}
+
+///
+/// Reads plugboard cables written as letter pairs, e.g. "AD CN ET", and returns them in upper case.
+/// Fails at the first pair that isn't two different letters, or plugs a letter a second time.
+///
+pub(crate) fn parse_plugboard(cables: &str) -> Result<Vec<(char, char)>, InvalidSetting<'_>> {
+    let mut transpositions = HashMap::new();
+    cables
+        .split_whitespace()
+        .map(|pair| {
+            let plugged = match pair.chars().collect::<Vec<char>>()[..] {
+                [first, second] => plug(&mut transpositions, &alphabet::LATIN, first, second),
+                _ => Ok(None),
+            };
+            match plugged {
+                Ok(Some(cable)) => Ok(cable),
+                Ok(None) => Err(InvalidSetting {
+                    value: pair,
+                    error: None,
+                }),
+                Err(error) => Err(InvalidSetting {
+                    value: pair,
+                    error: Some(error),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Reads ring settings given as letters, or as numbers from 01 to 26 separated by spaces.
+pub(crate) fn parse_rings(rings: &str) -> Result<Vec<char>, InvalidSetting<'_>> {
+    let mut letters = Vec::new();
+    for ring in rings.split_whitespace() {
+        match ring.parse::<u8>() {
+            Ok(number @ 1..=26) => letters.push((b'A' + number - 1) as char),
+            Ok(_) => {
+                return Err(InvalidSetting {
+                    value: ring,
+                    error: None,
+                });
+            }
+            Err(_) => {
+                for letter in ring.chars() {
+                    match alphabet::LATIN.contains(letter.to_ascii_uppercase()) {
+                        true => letters.push(letter.to_ascii_uppercase()),
+                        false => {
+                            return Err(InvalidSetting {
+                                value: ring,
+                                error: Some(Error::InvalidLetter(letter)),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(letters)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
     use crate::error::Error;
     use crate::rotors;
-    use crate::{Enigma, reflectors};
+    use crate::rotors_controller::RotorRole;
+    use crate::trace::{RotorStep, Stage, StepCause};
+    use crate::{Enigma, entry_wheels, reflectors, stepping};
 
     #[test]
     fn enigma_encrypts() {
@@ -283,9 +654,9 @@ Outside, the street continued being a street with admirable consistency. Cars pa
 
         let encrypted = enigma.encrypt_string(String::from("HelloWorld")).unwrap();
 
-        enigma.set_left_rotor_position_from_char('A');
-        enigma.set_middle_rotor_position_from_char('A');
-        enigma.set_right_rotor_position_from_char('A');
+        enigma.set_left_rotor_position_from_char('A').unwrap();
+        enigma.set_middle_rotor_position_from_char('A').unwrap();
+        enigma.set_right_rotor_position_from_char('A').unwrap();
 
         let plain = enigma.encrypt_string(encrypted).unwrap();
 
@@ -300,11 +671,11 @@ Outside, the street continued being a street with admirable consistency. Cars pa
             rotors::create_rotor_3(),
             reflectors::create_reflector_a(),
         );
-        enigma.set_transposition('H', 'G');
-        enigma.set_transposition('W', 'B');
-        enigma.set_transposition('L', 'T');
-        enigma.set_transposition('D', 'K');
-        enigma.set_transposition('O', 'E');
+        enigma.set_transposition('H', 'G').unwrap();
+        enigma.set_transposition('W', 'B').unwrap();
+        enigma.set_transposition('L', 'T').unwrap();
+        enigma.set_transposition('D', 'K').unwrap();
+        enigma.set_transposition('O', 'E').unwrap();
 
         assert_eq!(
             "UFMKLHQKUY",
@@ -341,7 +712,7 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let mut enigma = Enigma::new(left, middle, right, reflector);
 
         let r = enigma.encrypt_char(' ');
-        assert_eq!(r.err().unwrap(), Error::NonAlphabetic)
+        assert_eq!(r.err().unwrap(), Error::InvalidLetter(' '))
     }
 
     #[test]
@@ -354,25 +725,25 @@ Outside, the street continued being a street with admirable consistency. Cars pa
 
         let mut enigma = Enigma::new(left, mid, right, reflector);
 
-        enigma.set_transposition('H', 'G');
-        enigma.set_transposition('I', 'D');
-        enigma.set_transposition('Z', 'U');
-        enigma.set_transposition('B', 'X');
-        enigma.set_transposition('F', 'W');
-        enigma.set_transposition('A', 'M');
-        enigma.set_transposition('Q', 'V');
-        enigma.set_transposition('K', 'N');
-        enigma.set_transposition('P', 'E');
+        enigma.set_transposition('H', 'G').unwrap();
+        enigma.set_transposition('I', 'D').unwrap();
+        enigma.set_transposition('Z', 'U').unwrap();
+        enigma.set_transposition('B', 'X').unwrap();
+        enigma.set_transposition('F', 'W').unwrap();
+        enigma.set_transposition('A', 'M').unwrap();
+        enigma.set_transposition('Q', 'V').unwrap();
+        enigma.set_transposition('K', 'N').unwrap();
+        enigma.set_transposition('P', 'E').unwrap();
 
-        enigma.set_left_rotor_position_from_char('G');
-        enigma.set_middle_rotor_position_from_char('I');
-        enigma.set_right_rotor_position_from_char('I');
+        enigma.set_left_rotor_position_from_char('G').unwrap();
+        enigma.set_middle_rotor_position_from_char('I').unwrap();
+        enigma.set_right_rotor_position_from_char('I').unwrap();
 
         let cipher = enigma.encrypt_string(String::from("internal")).unwrap();
 
-        enigma.set_left_rotor_position_from_char('G');
-        enigma.set_middle_rotor_position_from_char('I');
-        enigma.set_right_rotor_position_from_char('I');
+        enigma.set_left_rotor_position_from_char('G').unwrap();
+        enigma.set_middle_rotor_position_from_char('I').unwrap();
+        enigma.set_right_rotor_position_from_char('I').unwrap();
 
         let decipher = enigma.encrypt_string(cipher).unwrap();
 
@@ -389,7 +760,7 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let mut enigma = Enigma::new(left, middle, right, reflector);
 
         let encrypted: String = enigma
-            .encrypt_str_iter(&"HelloWorld")
+            .encrypt_str_iter("HelloWorld")
             .map(|r| r.unwrap())
             .collect();
 
@@ -429,7 +800,7 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let reflector = reflectors::create_reflector_b();
 
         let mut enigma = Enigma::new(left, middle, right, reflector);
-        enigma.set_transposition('A', 'B');
+        enigma.set_transposition('A', 'B').unwrap();
         enigma.clear_transposition('A');
 
         assert!(enigma.transpositions.is_empty())
@@ -443,7 +814,7 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let reflector = reflectors::create_reflector_b();
 
         let mut enigma = Enigma::new(left, middle, right, reflector);
-        enigma.set_transposition('A', 'B');
+        enigma.set_transposition('A', 'B').unwrap();
         enigma.clear_transposition('B');
 
         assert!(enigma.transpositions.is_empty())
@@ -457,38 +828,47 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let reflector = reflectors::create_reflector_b();
 
         let mut enigma = Enigma::new(left, middle, right, reflector);
-        enigma.set_transposition('A', 'A');
+        enigma.set_transposition('A', 'A').unwrap();
 
         assert!(enigma.transpositions.is_empty())
     }
 
     #[test]
-    fn resetting_transposition_with_different_value_should_remove_existing() {
+    fn resetting_transposition_with_different_value_should_fail() {
         let left = rotors::create_rotor_3();
         let middle = rotors::create_rotor_2();
         let right = rotors::create_rotor_1();
         let reflector = reflectors::create_reflector_b();
 
         let mut enigma = Enigma::new(left, middle, right, reflector);
-        enigma.set_transposition('A', 'B');
-        enigma.set_transposition('A', 'C');
+        enigma.set_transposition('A', 'B').unwrap();
 
+        assert_eq!(
+            enigma.set_transposition('A', 'C'),
+            Err(Error::DuplicatePlug('A'))
+        );
         assert_eq!(
             enigma.transpositions,
-            HashMap::from([('A', 'C'), ('C', 'A')])
+            HashMap::from([('A', 'B'), ('B', 'A')])
         )
     }
 
     #[test]
-    fn resetting_transposition_with_different_key_should_remove_existing() {
+    fn resetting_transposition_after_clearing_should_replug() {
         let left = rotors::create_rotor_3();
         let middle = rotors::create_rotor_2();
         let right = rotors::create_rotor_1();
         let reflector = reflectors::create_reflector_b();
 
         let mut enigma = Enigma::new(left, middle, right, reflector);
-        enigma.set_transposition('A', 'B');
-        enigma.set_transposition('B', 'C');
+        enigma.set_transposition('A', 'B').unwrap();
+
+        assert_eq!(
+            enigma.set_transposition('C', 'B'),
+            Err(Error::DuplicatePlug('B'))
+        );
+        enigma.clear_transposition('B');
+        enigma.set_transposition('B', 'C').unwrap();
 
         assert_eq!(
             enigma.transpositions,
@@ -496,6 +876,168 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         )
     }
 
+    #[test]
+    fn shared_parsers_should_read_cables_and_rings() {
+        assert_eq!(
+            crate::parse_plugboard("ab CQ"),
+            Ok(vec![('A', 'B'), ('C', 'Q')])
+        );
+        assert_eq!(
+            crate::parse_plugboard("AB AB"),
+            Err(crate::InvalidSetting {
+                value: "AB",
+                error: None
+            })
+        );
+        assert_eq!(
+            crate::parse_plugboard("AB CA"),
+            Err(crate::InvalidSetting {
+                value: "CA",
+                error: Some(Error::DuplicatePlug('A'))
+            })
+        );
+        assert_eq!(crate::parse_rings("01 l 22"), Ok(vec!['A', 'L', 'V']));
+        assert_eq!(
+            crate::parse_rings("01 27 01"),
+            Err(crate::InvalidSetting {
+                value: "27",
+                error: None
+            })
+        );
+    }
+
+    #[test]
+    fn ring_settings_should_match_historical_vector() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+
+        assert_eq!(enigma.encrypt_str("AAAAA").unwrap(), "BDZGO");
+
+        enigma.set_left_rotor_ring_setting('B').unwrap();
+        enigma.set_middle_rotor_ring_setting('B').unwrap();
+        enigma.set_right_rotor_ring_setting('B').unwrap();
+        enigma.set_left_rotor_position_from_char('A').unwrap();
+        enigma.set_middle_rotor_position_from_char('A').unwrap();
+        enigma.set_right_rotor_position_from_char('A').unwrap();
+
+        assert_eq!(enigma.encrypt_str("AAAAA").unwrap(), "EWTYX");
+    }
+
+    #[test]
+    fn commercial_enigma_should_decrypt_own_ciphertext() {
+        let create_commercial_enigma = || {
+            let mut enigma = Enigma::new(
+                rotors::create_rotor_commercial_d_3(),
+                rotors::create_rotor_commercial_d_1(),
+                rotors::create_rotor_commercial_d_2(),
+                reflectors::create_reflector_commercial(),
+            );
+            enigma.set_entry_wheel(entry_wheels::create_entry_wheel_qwertz());
+            enigma.set_reflector_position('F').unwrap();
+            enigma.set_left_rotor_position_from_char('Q').unwrap();
+            enigma.set_middle_rotor_position_from_char('E').unwrap();
+            enigma.set_right_rotor_position_from_char('V').unwrap();
+            enigma
+        };
+
+        let cipher = create_commercial_enigma()
+            .encrypt_str("DIPLOMATISCHERVERKEHR")
+            .unwrap();
+        let plain = create_commercial_enigma().encrypt_str(&cipher).unwrap();
+
+        assert_ne!(cipher, "DIPLOMATISCHERVERKEHR");
+        assert_eq!(plain, "DIPLOMATISCHERVERKEHR");
+    }
+
+    #[test]
+    fn entry_wheel_and_reflector_position_should_change_encryption() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_3(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_1(),
+            reflectors::create_reflector_b(),
+        );
+        let military = enigma.peak_without_increment('H').unwrap();
+
+        enigma.set_entry_wheel(entry_wheels::create_entry_wheel_qwertz());
+        let with_entry_wheel = enigma.peak_without_increment('H').unwrap();
+        enigma.set_reflector_position('M').unwrap();
+        let with_reflector_position = enigma.peak_without_increment('H').unwrap();
+
+        assert_ne!(with_entry_wheel, military);
+        assert_ne!(with_reflector_position, with_entry_wheel);
+        assert_eq!(enigma.get_reflector_position(), 'M');
+    }
+
+    fn create_abwehr_enigma() -> Enigma {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_abwehr_g_1(),
+            rotors::create_rotor_abwehr_g_2(),
+            rotors::create_rotor_abwehr_g_3(),
+            reflectors::create_reflector_abwehr_g(),
+        );
+        enigma.set_entry_wheel(entry_wheels::create_entry_wheel_qwertz());
+        enigma.set_stepping_strategy(stepping::Cogwheel);
+        enigma.set_reflector_position('K').unwrap();
+        enigma
+    }
+
+    #[test]
+    fn abwehr_enigma_should_step_reflector_and_decrypt_own_ciphertext() {
+        let plain = "A".repeat(2_000);
+
+        let mut enigma = create_abwehr_enigma();
+        let cipher = enigma.encrypt_str(&plain).unwrap();
+        assert_ne!(enigma.get_reflector_position(), 'K');
+
+        assert_eq!(create_abwehr_enigma().encrypt_str(&cipher).unwrap(), plain);
+    }
+
+    #[test]
+    fn abwehr_enigma_should_drive_the_reflector_through_the_notches() {
+        let mut enigma = create_abwehr_enigma();
+
+        enigma.encrypt_char('A').unwrap();
+
+        // Every rotor has a notch at B, so the first key press moves the whole stack.
+        assert_eq!(enigma.get_rotor_positions(), vec!['B', 'B', 'B']);
+        assert_eq!(enigma.get_reflector_position(), 'L');
+
+        // The rotors have 11, 15 and 17 notches, right to left. In 26^3 key presses the right
+        // rotor turns 676 times and carries 7,436 steps, the middle rotor turns 286 times and
+        // carries 4,290 and the left rotor turns 165 times, carrying the reflector 2,805 steps -
+        // 23 steps short of a full number of turns.
+        let mut enigma = create_abwehr_enigma();
+        (0..26 * 26 * 26).for_each(|_| {
+            enigma.encrypt_char('A').unwrap();
+        });
+
+        assert_eq!(enigma.get_rotor_positions(), vec!['A', 'A', 'A']);
+        assert_eq!(enigma.get_reflector_position(), 'H');
+    }
+
+    #[test]
+    fn abwehr_enigma_increment_by_should_match_encryption() {
+        let mut stepped_by = create_abwehr_enigma();
+        stepped_by.increment_by(2_000);
+
+        let mut encrypted = create_abwehr_enigma();
+        let _ = encrypted.encrypt_str(&"A".repeat(2_000));
+
+        assert_eq!(
+            stepped_by.get_reflector_position(),
+            encrypted.get_reflector_position()
+        );
+        assert_eq!(
+            stepped_by.peak_cipher('Q').unwrap(),
+            encrypted.peak_cipher('Q').unwrap()
+        );
+    }
+
     #[test]
     fn increment_by_should_work_for_every_position() {
         let mut increment_by_result = String::new();
@@ -519,9 +1061,9 @@ Outside, the street continued being a street with admirable consistency. Cars pa
         let middle_rotor_position_after_inc_by = enigma.get_middle_rotor_position();
         let right_rotor_position_after_inc_by = enigma.get_right_rotor_position();
 
-        enigma.set_left_rotor_position_from_char('A');
-        enigma.set_middle_rotor_position_from_char('A');
-        enigma.set_right_rotor_position_from_char('A');
+        enigma.set_left_rotor_position_from_char('A').unwrap();
+        enigma.set_middle_rotor_position_from_char('A').unwrap();
+        enigma.set_right_rotor_position_from_char('A').unwrap();
         let mut encrypt_result = String::new();
 
         for _ in 0..INCREMENT_CYCLES {
@@ -546,4 +1088,209 @@ Outside, the street continued being a street with admirable consistency. Cars pa
 
         assert_eq!(increment_by_result, encrypt_result);
     }
+
+    #[test]
+    fn m4_with_greek_wheel_at_a_should_match_three_rotor_machine() {
+        let plugboard = [('A', 'T'), ('B', 'L'), ('D', 'F'), ('G', 'J'), ('H', 'M')];
+        let text = "VONVONJLOOKSJHFFTTTEINSEINSDREIZWOYYQNNSNEUNINHALTXXBEIANGRIFFUNTERWASSER";
+        let machines = [
+            (
+                rotors::create_rotor_beta(),
+                reflectors::create_reflector_b_thin(),
+                reflectors::create_reflector_b(),
+            ),
+            (
+                rotors::create_rotor_gamma(),
+                reflectors::create_reflector_c_thin(),
+                reflectors::create_reflector_c(),
+            ),
+        ];
+
+        for (greek_wheel, thin_reflector, reflector) in machines {
+            let mut m4 = Enigma::with_rotor_stack(
+                vec![
+                    (greek_wheel, RotorRole::GreekWheel),
+                    (rotors::create_rotor_2(), RotorRole::Stepping),
+                    (rotors::create_rotor_4(), RotorRole::Stepping),
+                    (rotors::create_rotor_1(), RotorRole::Stepping),
+                ],
+                thin_reflector,
+                entry_wheels::create_entry_wheel_identity(),
+            )
+            .unwrap();
+            let mut m3 = Enigma::new(
+                rotors::create_rotor_2(),
+                rotors::create_rotor_4(),
+                rotors::create_rotor_1(),
+                reflector,
+            );
+            for &(first, second) in &plugboard {
+                m4.set_transposition(first, second).unwrap();
+                m3.set_transposition(first, second).unwrap();
+            }
+            m4.set_ring_settings(&['A', 'A', 'A', 'V']).unwrap();
+            m3.set_ring_settings(&['A', 'A', 'V']).unwrap();
+            m4.set_rotor_positions(&['A', 'V', 'J', 'N']).unwrap();
+            m3.set_rotor_positions(&['V', 'J', 'N']).unwrap();
+
+            assert_eq!(m4.encrypt_str(text), m3.encrypt_str(text));
+            assert_eq!(m4.get_rotor_positions()[0], 'A');
+        }
+    }
+
+    #[test]
+    fn peak_cipher_should_restore_every_rotor() {
+        let mut enigma = Enigma::with_rotor_stack(
+            vec![
+                (rotors::create_rotor_beta(), RotorRole::GreekWheel),
+                (rotors::create_rotor_1(), RotorRole::Stepping),
+                (rotors::create_rotor_2(), RotorRole::Stepping),
+                (rotors::create_rotor_3(), RotorRole::Stepping),
+            ],
+            reflectors::create_reflector_b_thin(),
+            entry_wheels::create_entry_wheel_identity(),
+        )
+        .unwrap();
+        enigma.set_rotor_positions(&['K', 'A', 'E', 'V']).unwrap();
+
+        let peaked = enigma.peak_cipher('A').unwrap();
+
+        assert_eq!(enigma.get_rotor_positions(), vec!['K', 'A', 'E', 'V']);
+        assert_eq!(enigma.encrypt_char('A'), Ok(peaked));
+    }
+
+    #[test]
+    fn traced_encryption_should_match_plain_encryption() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_transposition('A', 'Q').unwrap();
+        let mut traced = enigma.clone();
+
+        for letter in "ATTACKATDAWN".chars() {
+            let trace = traced.encrypt_char_traced(letter).unwrap();
+
+            assert_eq!(Ok(trace.output), enigma.encrypt_char(letter));
+            assert_eq!(trace.positions, enigma.get_rotor_positions());
+            assert_eq!(trace.stages.len(), 11);
+            assert_eq!(trace.letter_after(Stage::PlugboardOut), Some(trace.output));
+        }
+    }
+
+    #[test]
+    fn trace_should_report_the_double_step() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_stepping_strategy(stepping::DoubleStep);
+        enigma.set_rotor_positions(&['A', 'E', 'W']).unwrap();
+
+        let trace = enigma.encrypt_char_traced('A').unwrap();
+
+        // Rotor II stands at its notch, so it steps again and carries rotor I.
+        assert_eq!(trace.positions, vec!['B', 'F', 'X']);
+        assert_eq!(
+            trace.steps,
+            vec![
+                RotorStep {
+                    rotor: 0,
+                    cause: StepCause::Carry { from: 1 }
+                },
+                RotorStep {
+                    rotor: 1,
+                    cause: StepCause::DoubleStep
+                },
+                RotorStep {
+                    rotor: 2,
+                    cause: StepCause::KeyPress
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_should_follow_the_historical_path_and_explain_steps() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_rotor_positions(&['A', 'A', 'V']).unwrap();
+
+        let trace = enigma.encrypt_char_traced('A').unwrap();
+
+        // Rotor III steps onto W, its step position, and carries rotor II.
+        assert_eq!(trace.positions, vec!['A', 'B', 'W']);
+        assert_eq!(
+            trace.steps,
+            vec![
+                RotorStep {
+                    rotor: 1,
+                    cause: StepCause::Carry { from: 2 }
+                },
+                RotorStep {
+                    rotor: 2,
+                    cause: StepCause::KeyPress
+                },
+            ]
+        );
+        assert!(!trace.reflector_stepped);
+        let path: String = trace.stages.iter().map(|&(_, letter)| letter).collect();
+        assert_eq!(path.len(), 11);
+        assert_eq!(
+            trace.letter_after(Stage::RotorForward(2)),
+            enigma.rotor_controller.get_rotor(2).map_letter('A').ok()
+        );
+    }
+
+    #[test]
+    fn permutation_at_should_match_encryption() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_transposition('B', 'Q').unwrap();
+
+        let permutation = enigma.permutation_at(&['A', 'A', 'B']).unwrap();
+
+        assert!(permutation.is_involution());
+        assert!(permutation.fixed_points().is_empty());
+        assert_eq!(permutation.cycle_type(), vec![2; 13]);
+        assert_eq!(enigma.get_rotor_positions(), vec!['A', 'A', 'A']);
+        assert_eq!(
+            permutation.apply('A').unwrap(),
+            enigma.encrypt_char('A').unwrap()
+        );
+        assert_eq!(
+            enigma.permutation_at(&['A', 'A']).err(),
+            Some(Error::WrongSettingCount {
+                expected: 3,
+                got: 2
+            })
+        );
+    }
+
+    #[test]
+    fn trace_should_stop_at_non_alphabetic_letter() {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+
+        assert_eq!(
+            enigma.encrypt_char_traced('4'),
+            Err(Error::InvalidLetter('4'))
+        );
+    }
 }
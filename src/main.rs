@@ -0,0 +1,326 @@
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use enigma::Enigma;
+use enigma::keys::{self, DailyKey, Rng};
+use enigma::models::MachineModel;
+
+const GROUP_SIZE: usize = 5;
+
+const USAGE: &str = "\
+Usage:
+  enigma encrypt (--key KEY | --key-file PATH) [--group] [FILE...]
+  enigma decrypt (--key KEY | --key-file PATH) [--group] [FILE...]
+  enigma generate [--model MODEL] [--days N] [--seed N]
+  enigma sheet [--model MODEL] [--seed N]
+
+encrypt and decrypt read the files in turn, or stdin when none are given. Letters are enciphered
+and everything else is copied unchanged; --group drops everything but letters and prints the
+ciphertext in groups of five. The final rotor positions are printed to stderr.
+
+A key is written as fields like \"model = M3; rotors = I IV III; rings = 16 26 08;
+plugboard = AD CN ET; positions = QWE\". A key file holds the same fields, one per line.
+
+generate prints random daily keys for a model (M3 by default), one per line. sheet prints a month
+of keys as a key sheet table, following the historical rules for issuing keys.";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Encipher {
+        key: KeySource,
+        group: bool,
+        files: Vec<String>,
+    },
+    Generate {
+        model: MachineModel,
+        days: usize,
+        seed: Option<u64>,
+    },
+    Sheet {
+        model: MachineModel,
+        seed: Option<u64>,
+    },
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+enum KeySource {
+    Inline(String),
+    File(String),
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, options)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+    let mut options = options.iter();
+
+    match command.as_str() {
+        "encrypt" | "decrypt" => {
+            let (mut key, mut group, mut files) = (None, false, Vec::new());
+            while let Some(arg) = options.next() {
+                match arg.as_str() {
+                    "--key" => key = Some(KeySource::Inline(value_of(&mut options, "--key")?)),
+                    "--key-file" => {
+                        key = Some(KeySource::File(value_of(&mut options, "--key-file")?))
+                    }
+                    "--group" => group = true,
+                    option if option.starts_with("--") => {
+                        return Err(format!("unknown option {option}"));
+                    }
+                    _ => files.push(arg.clone()),
+                }
+            }
+
+            Ok(Command::Encipher {
+                key: key.ok_or("a key is required, use --key or --key-file")?,
+                group,
+                files,
+            })
+        }
+        "generate" => {
+            let (mut model, mut days, mut seed) = (MachineModel::M3, 1, None);
+            while let Some(arg) = options.next() {
+                match arg.as_str() {
+                    "--model" => model = parse_model(&value_of(&mut options, "--model")?)?,
+                    "--days" => days = parse_number(&value_of(&mut options, "--days")?)?,
+                    "--seed" => seed = Some(parse_number(&value_of(&mut options, "--seed")?)?),
+                    option => return Err(format!("unknown option {option}")),
+                }
+            }
+
+            Ok(Command::Generate { model, days, seed })
+        }
+        "sheet" => {
+            let (mut model, mut seed) = (MachineModel::M3, None);
+            while let Some(arg) = options.next() {
+                match arg.as_str() {
+                    "--model" => model = parse_model(&value_of(&mut options, "--model")?)?,
+                    "--seed" => seed = Some(parse_number(&value_of(&mut options, "--seed")?)?),
+                    option => return Err(format!("unknown option {option}")),
+                }
+            }
+
+            Ok(Command::Sheet { model, seed })
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        command => Err(format!("unknown command {command}")),
+    }
+}
+
+fn value_of(options: &mut std::slice::Iter<String>, option: &str) -> Result<String, String> {
+    options
+        .next()
+        .cloned()
+        .ok_or_else(|| format!("{option} needs a value"))
+}
+
+fn parse_model(name: &str) -> Result<MachineModel, String> {
+    name.parse().map_err(|error| format!("{error}"))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a number but got '{value}'"))
+}
+
+///
+/// Enciphers the letters of `text` and copies everything else, which the machine has no key for.
+/// When grouping, only the letters are kept and printed in groups of five.
+///
+fn encipher(enigma: &mut Enigma, text: &str, group: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut letters = 0;
+    for c in text.chars() {
+        match (c.is_ascii_alphabetic(), group) {
+            (true, _) => {
+                if group && letters > 0 && letters % GROUP_SIZE == 0 {
+                    output.push(' ');
+                }
+                let enciphered = enigma
+                    .encrypt_char(c)
+                    .expect("ASCII letters are in the machine's alphabet");
+                output.push(match c.is_ascii_lowercase() && !group {
+                    true => enciphered.to_ascii_lowercase(),
+                    false => enciphered,
+                });
+                letters += 1;
+            }
+            (false, false) => output.push(c),
+            (false, true) => {}
+        }
+    }
+
+    output
+}
+
+fn read_key(source: &KeySource) -> Result<DailyKey, String> {
+    let text = match source {
+        KeySource::Inline(text) => text.clone(),
+        KeySource::File(path) => {
+            fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?
+        }
+    };
+
+    text.parse()
+        .map_err(|error| format!("invalid key: {error}"))
+}
+
+fn read_input(files: &[String]) -> Result<String, String> {
+    if files.is_empty() {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("can't read stdin: {error}"))?;
+        return Ok(text);
+    }
+
+    files
+        .iter()
+        .map(|path| fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}")))
+        .collect()
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Encipher { key, group, files } => {
+            let mut enigma = read_key(&key)?
+                .create_enigma()
+                .map_err(|error| format!("invalid key: {error}"))?;
+            let mut output = encipher(&mut enigma, &read_input(&files)?, group);
+            if group {
+                output.push('\n');
+            }
+
+            io::stdout()
+                .write_all(output.as_bytes())
+                .map_err(|error| error.to_string())?;
+            eprintln!(
+                "Final positions: {}",
+                String::from_iter(enigma.get_rotor_positions())
+            );
+        }
+        Command::Generate { model, days, seed } => {
+            let seed = seed.unwrap_or_else(clock_seed);
+            let mut rng = Rng::new(seed);
+            let mut sheet = String::new();
+            for day in 1..=days {
+                let key = DailyKey::random(model, &mut rng).map_err(|error| error.to_string())?;
+                let _ = writeln!(sheet, "# Day {day}\n{key}");
+            }
+
+            print!("{sheet}");
+            eprintln!("Seed: {seed}");
+        }
+        Command::Sheet { model, seed } => {
+            let seed = seed.unwrap_or_else(clock_seed);
+            let sheet = keys::generate_month(seed, model).map_err(|error| error.to_string())?;
+
+            print!("{sheet}");
+            eprintln!("Seed: {seed}");
+        }
+        Command::Help => println!("{USAGE}"),
+    }
+
+    Ok(())
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match parse_args(&args).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("enigma: {message}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    fn machine() -> Enigma {
+        "model = Enigma-I; reflector = B; rotors = I II III"
+            .parse::<DailyKey>()
+            .unwrap()
+            .create_enigma()
+            .unwrap()
+    }
+
+    #[test]
+    fn arguments_should_parse_into_commands() {
+        assert_eq!(
+            parse_args(&args("decrypt --key-file day.key --group a.txt")),
+            Ok(Command::Encipher {
+                key: KeySource::File("day.key".to_string()),
+                group: true,
+                files: vec!["a.txt".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_args(&args("generate --model Enigma-K --days 31 --seed 7")),
+            Ok(Command::Generate {
+                model: MachineModel::CommercialK,
+                days: 31,
+                seed: Some(7),
+            })
+        );
+        assert_eq!(
+            parse_args(&args("sheet --seed 1941")),
+            Ok(Command::Sheet {
+                model: MachineModel::M3,
+                seed: Some(1941),
+            })
+        );
+        assert!(parse_args(&args("encrypt a.txt")).is_err());
+        assert!(parse_args(&args("generate --days")).is_err());
+    }
+
+    #[test]
+    fn key_commands_should_reject_models_without_letters() {
+        for command in [
+            "generate --model Z30 --seed 7",
+            "sheet --model Z30 --seed 7",
+        ] {
+            assert_eq!(
+                parse_args(&args(command)).and_then(run),
+                Err("the model enciphers a different alphabet".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn punctuation_should_be_copied_and_case_kept() {
+        assert_eq!(encipher(&mut machine(), "Aa, a!\n", false), "Bd, z!\n");
+    }
+
+    #[test]
+    fn grouping_should_keep_only_letters() {
+        let grouped = encipher(&mut machine(), "AAAAA, AAAAA. AA", true);
+
+        assert_eq!(
+            grouped.replace(' ', ""),
+            encipher(&mut machine(), "AAAAAAAAAAAA", false)
+        );
+        assert_eq!(
+            grouped.split(' ').map(str::len).collect::<Vec<_>>(),
+            [5, 5, 2]
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use crate::Enigma;
+use crate::alphabet::{DIGITS, LATIN};
+use crate::entry_wheels::{self, EntryWheelType};
+use crate::reflectors::{self, ReflectorType};
+use crate::rotor::RotorType;
+use crate::rotors;
+use crate::stepping::{Cogwheel, DoubleStep, PawlAndNotch, SteppingStrategy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MachineModel {
+    EnigmaI,
+    M3,
+    CommercialD,
+    CommercialK,
+    AbwehrG,
+    Railway,
+    SwissK,
+    Tirpitz,
+    Z30,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    UnsupportedRotor(RotorType),
+    UnsupportedReflector(ReflectorType),
+    RepeatedRotor(RotorType),
+    /// The model enciphers a different alphabet than the one requested.
+    WrongAlphabet,
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::UnsupportedRotor(rotor) => {
+                write!(f, "rotor {rotor} doesn't fit this model")
+            }
+            ModelError::UnsupportedReflector(reflector) => {
+                write!(f, "reflector {reflector} doesn't fit this model")
+            }
+            ModelError::RepeatedRotor(rotor) => write!(f, "rotor {rotor} is used twice"),
+            ModelError::WrongAlphabet => write!(f, "the model enciphers a different alphabet"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl MachineModel {
+    pub fn allowed_rotors(&self) -> &'static [RotorType] {
+        match self {
+            MachineModel::EnigmaI => &[
+                RotorType::Rotor1,
+                RotorType::Rotor2,
+                RotorType::Rotor3,
+                RotorType::Rotor4,
+                RotorType::Rotor5,
+            ],
+            MachineModel::M3 => &[
+                RotorType::Rotor1,
+                RotorType::Rotor2,
+                RotorType::Rotor3,
+                RotorType::Rotor4,
+                RotorType::Rotor5,
+                RotorType::Rotor6,
+                RotorType::Rotor7,
+                RotorType::Rotor8,
+            ],
+            MachineModel::CommercialD | MachineModel::CommercialK => &[
+                RotorType::CommercialD1,
+                RotorType::CommercialD2,
+                RotorType::CommercialD3,
+            ],
+            MachineModel::AbwehrG => &[
+                RotorType::AbwehrG1,
+                RotorType::AbwehrG2,
+                RotorType::AbwehrG3,
+            ],
+            MachineModel::Railway => &[
+                RotorType::Railway1,
+                RotorType::Railway2,
+                RotorType::Railway3,
+            ],
+            MachineModel::SwissK => &[RotorType::SwissK1, RotorType::SwissK2, RotorType::SwissK3],
+            MachineModel::Tirpitz => &[
+                RotorType::Tirpitz1,
+                RotorType::Tirpitz2,
+                RotorType::Tirpitz3,
+                RotorType::Tirpitz4,
+                RotorType::Tirpitz5,
+                RotorType::Tirpitz6,
+                RotorType::Tirpitz7,
+                RotorType::Tirpitz8,
+            ],
+            MachineModel::Z30 => &[
+                RotorType::Z30Rotor1,
+                RotorType::Z30Rotor2,
+                RotorType::Z30Rotor3,
+            ],
+        }
+    }
+
+    pub fn allowed_reflectors(&self) -> &'static [ReflectorType] {
+        match self {
+            MachineModel::EnigmaI => &[
+                ReflectorType::ReflectorA,
+                ReflectorType::ReflectorB,
+                ReflectorType::ReflectorC,
+            ],
+            MachineModel::M3 => &[ReflectorType::ReflectorB, ReflectorType::ReflectorC],
+            MachineModel::CommercialD | MachineModel::CommercialK => {
+                &[ReflectorType::ReflectorCommercial]
+            }
+            MachineModel::AbwehrG => &[ReflectorType::ReflectorAbwehrG],
+            MachineModel::Railway => &[ReflectorType::ReflectorRailway],
+            MachineModel::SwissK => &[ReflectorType::ReflectorSwissK],
+            MachineModel::Tirpitz => &[ReflectorType::ReflectorTirpitz],
+            MachineModel::Z30 => &[ReflectorType::ReflectorZ30],
+        }
+    }
+
+    pub fn entry_wheel(&self) -> EntryWheelType {
+        match self {
+            MachineModel::EnigmaI | MachineModel::M3 | MachineModel::Z30 => {
+                EntryWheelType::Identity
+            }
+            MachineModel::Tirpitz => EntryWheelType::Tirpitz,
+            _ => EntryWheelType::Qwertz,
+        }
+    }
+
+    pub fn has_plugboard(&self) -> bool {
+        matches!(self, MachineModel::EnigmaI | MachineModel::M3)
+    }
+
+    /// How many symbols the model's machines encipher - the 26 letters, or the Z30's ten digits.
+    pub fn alphabet_size(&self) -> usize {
+        match self {
+            MachineModel::Z30 => DIGITS.size(),
+            _ => LATIN.size(),
+        }
+    }
+
+    /// How the rotors of the model's machines step.
+    pub fn stepping<const N: usize>(&self) -> Arc<dyn SteppingStrategy<N>> {
+        match self {
+            MachineModel::EnigmaI
+            | MachineModel::M3
+            | MachineModel::CommercialD
+            | MachineModel::CommercialK
+            | MachineModel::Railway
+            | MachineModel::SwissK
+            | MachineModel::Tirpitz => Arc::new(DoubleStep),
+            MachineModel::AbwehrG => Arc::new(Cogwheel),
+            _ => Arc::new(PawlAndNotch),
+        }
+    }
+
+    /// Whether the operator can turn the reflector to a starting position.
+    pub fn has_settable_reflector(&self) -> bool {
+        !matches!(self, MachineModel::EnigmaI | MachineModel::M3)
+    }
+
+    ///
+    /// Assembles a machine of this model, with its entry wheel and stepping, after checking the
+    /// rotors and reflector are ones the model accepts.
+    ///
+    /// Returns [`ModelError::WrongAlphabet`] for the numeric Enigma Z30, which is assembled with
+    /// [`MachineModel::create_numeric`].
+    ///
+    pub fn create(
+        &self,
+        wheel_order: [RotorType; 3],
+        reflector: ReflectorType,
+    ) -> Result<Enigma, ModelError> {
+        if self.alphabet_size() != LATIN.size() {
+            return Err(ModelError::WrongAlphabet);
+        }
+        self.validate(&wheel_order, reflector)?;
+
+        let create_rotor =
+            |typ| rotors::create_rotor(typ).map_err(|_| ModelError::UnsupportedRotor(typ));
+        let mut enigma = Enigma::new(
+            create_rotor(wheel_order[0])?,
+            create_rotor(wheel_order[1])?,
+            create_rotor(wheel_order[2])?,
+            reflectors::create_reflector(reflector)
+                .map_err(|_| ModelError::UnsupportedReflector(reflector))?,
+        );
+        enigma.set_entry_wheel(entry_wheels::create_entry_wheel(self.entry_wheel()));
+        enigma
+            .rotor_controller
+            .set_stepping_strategy(self.stepping());
+        enigma.model = Some(*self);
+
+        Ok(enigma)
+    }
+
+    /// Assembles a machine of a model that enciphers the digits 1-0, which is only the Enigma Z30.
+    pub fn create_numeric(
+        &self,
+        wheel_order: [RotorType; 3],
+        reflector: ReflectorType,
+    ) -> Result<Enigma<10>, ModelError> {
+        if self.alphabet_size() != DIGITS.size() {
+            return Err(ModelError::WrongAlphabet);
+        }
+        self.validate(&wheel_order, reflector)?;
+
+        let create_rotor = |typ| match typ {
+            RotorType::Z30Rotor1 => rotors::create_rotor_z30_1(),
+            RotorType::Z30Rotor2 => rotors::create_rotor_z30_2(),
+            // `validate` only lets Z30 rotors through.
+            _ => rotors::create_rotor_z30_3(),
+        };
+
+        let mut enigma = Enigma::with_entry_wheel(
+            create_rotor(wheel_order[0]),
+            create_rotor(wheel_order[1]),
+            create_rotor(wheel_order[2]),
+            reflectors::create_reflector_z30(),
+            entry_wheels::create_entry_wheel_numeric(),
+        );
+        enigma
+            .rotor_controller
+            .set_stepping_strategy(self.stepping());
+        enigma.model = Some(*self);
+
+        Ok(enigma)
+    }
+
+    pub(crate) fn validate(
+        &self,
+        wheel_order: &[RotorType],
+        reflector: ReflectorType,
+    ) -> Result<(), ModelError> {
+        for (index, &rotor) in wheel_order.iter().enumerate() {
+            if !self.allowed_rotors().contains(&rotor) {
+                return Err(ModelError::UnsupportedRotor(rotor));
+            }
+            if wheel_order[..index].contains(&rotor) {
+                return Err(ModelError::RepeatedRotor(rotor));
+            }
+        }
+        if !self.allowed_reflectors().contains(&reflector) {
+            return Err(ModelError::UnsupportedReflector(reflector));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn model_should_reject_foreign_rotors_and_reflectors() {
+        assert_eq!(
+            MachineModel::Railway
+                .create(
+                    [RotorType::Railway1, RotorType::Rotor2, RotorType::Railway3],
+                    ReflectorType::ReflectorRailway,
+                )
+                .err(),
+            Some(ModelError::UnsupportedRotor(RotorType::Rotor2))
+        );
+        assert_eq!(
+            MachineModel::SwissK
+                .create(
+                    [RotorType::SwissK1, RotorType::SwissK2, RotorType::SwissK3],
+                    ReflectorType::ReflectorB,
+                )
+                .err(),
+            Some(ModelError::UnsupportedReflector(ReflectorType::ReflectorB))
+        );
+        assert_eq!(
+            MachineModel::SwissK
+                .create(
+                    [RotorType::SwissK1, RotorType::SwissK2, RotorType::SwissK1],
+                    ReflectorType::ReflectorSwissK,
+                )
+                .err(),
+            Some(ModelError::RepeatedRotor(RotorType::SwissK1))
+        );
+    }
+
+    ///
+    /// Presses `keys` keys and records the rotor positions after each one, leftmost rotor first.
+    ///
+    fn positions_after_each_key(enigma: &mut Enigma, keys: usize) -> Vec<String> {
+        (0..keys)
+            .map(|_| {
+                enigma.increment_by(1);
+                enigma.get_rotor_positions().into_iter().collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commercial_enigmas_should_double_step() {
+        for model in [MachineModel::CommercialD, MachineModel::CommercialK] {
+            let mut enigma = model
+                .create(
+                    [
+                        RotorType::CommercialD1,
+                        RotorType::CommercialD2,
+                        RotorType::CommercialD3,
+                    ],
+                    ReflectorType::ReflectorCommercial,
+                )
+                .unwrap();
+            enigma.set_rotor_positions(&['A', 'D', 'N']).unwrap();
+
+            // The rotors turn over at Y, E and N, so the middle rotor steps again on the key
+            // press after it reaches E and takes the left rotor along.
+            assert_eq!(
+                positions_after_each_key(&mut enigma, 3),
+                ["AEO", "BFP", "BFQ"]
+            );
+        }
+    }
+
+    #[test]
+    fn railway_enigma_should_decipher_across_the_double_step() {
+        let create = || {
+            let mut enigma = MachineModel::Railway
+                .create(
+                    [
+                        RotorType::Railway3,
+                        RotorType::Railway1,
+                        RotorType::Railway2,
+                    ],
+                    ReflectorType::ReflectorRailway,
+                )
+                .unwrap();
+            enigma.set_reflector_position('R').unwrap();
+            enigma.set_rotor_positions(&['B', 'M', 'D']).unwrap();
+            enigma
+        };
+
+        let ciphertext = create().encrypt_str("REICHSBAHNDIREKTION").unwrap();
+
+        assert!(
+            ciphertext
+                .chars()
+                .zip("REICHSBAHNDIREKTION".chars())
+                .all(|(c, p)| c != p)
+        );
+        assert_eq!(
+            create().encrypt_str(&ciphertext).unwrap(),
+            "REICHSBAHNDIREKTION"
+        );
+    }
+
+    #[test]
+    fn railway_enigma_should_double_step() {
+        let mut enigma = MachineModel::Railway
+            .create(
+                [
+                    RotorType::Railway3,
+                    RotorType::Railway1,
+                    RotorType::Railway2,
+                ],
+                ReflectorType::ReflectorRailway,
+            )
+            .unwrap();
+        enigma.set_rotor_positions(&['A', 'M', 'E']).unwrap();
+
+        // Railway rotors II and III turn over at N and E.
+        assert_eq!(
+            positions_after_each_key(&mut enigma, 3),
+            ["ANF", "BOG", "BOH"]
+        );
+    }
+
+    #[test]
+    fn swiss_k_should_decipher_across_the_double_step() {
+        let create = || {
+            let mut enigma = MachineModel::SwissK
+                .create(
+                    [RotorType::SwissK1, RotorType::SwissK2, RotorType::SwissK3],
+                    ReflectorType::ReflectorSwissK,
+                )
+                .unwrap();
+            enigma.set_reflector_position('S').unwrap();
+            enigma.set_rotor_positions(&['K', 'D', 'M']).unwrap();
+            enigma
+        };
+
+        let ciphertext = create().encrypt_str("EIDGENOSSENSCHAFT").unwrap();
+
+        assert!(
+            ciphertext
+                .chars()
+                .zip("EIDGENOSSENSCHAFT".chars())
+                .all(|(c, p)| c != p)
+        );
+        assert_eq!(
+            create().encrypt_str(&ciphertext).unwrap(),
+            "EIDGENOSSENSCHAFT"
+        );
+    }
+
+    #[test]
+    fn swiss_k_should_double_step() {
+        let mut enigma = MachineModel::SwissK
+            .create(
+                [RotorType::SwissK1, RotorType::SwissK2, RotorType::SwissK3],
+                ReflectorType::ReflectorSwissK,
+            )
+            .unwrap();
+        enigma.set_rotor_positions(&['A', 'D', 'N']).unwrap();
+
+        // Swiss-K rotors II and III turn over at E and N.
+        assert_eq!(
+            positions_after_each_key(&mut enigma, 3),
+            ["AEO", "BFP", "BFQ"]
+        );
+    }
+
+    #[test]
+    fn tirpitz_rotors_should_have_five_step_positions() {
+        for &rotor in MachineModel::Tirpitz.allowed_rotors() {
+            let rotor = rotors::create_rotor(rotor).unwrap();
+            assert_eq!(rotor.get_props().get_step_positions().len(), 5);
+        }
+    }
+
+    #[test]
+    fn enigma_t_should_double_step() {
+        let mut enigma = MachineModel::Tirpitz
+            .create(
+                [
+                    RotorType::Tirpitz4,
+                    RotorType::Tirpitz7,
+                    RotorType::Tirpitz1,
+                ],
+                ReflectorType::ReflectorTirpitz,
+            )
+            .unwrap();
+        enigma.set_rotor_positions(&['A', 'B', 'E']).unwrap();
+
+        // Rotor VII turns over at C, F, K, R and Y, rotor I at E, K, Q, W and Z.
+        assert_eq!(
+            positions_after_each_key(&mut enigma, 3),
+            ["ACF", "BDG", "BDH"]
+        );
+    }
+
+    #[test]
+    fn enigma_t_should_match_reference_vector() {
+        let mut enigma = MachineModel::Tirpitz
+            .create(
+                [
+                    RotorType::Tirpitz4,
+                    RotorType::Tirpitz7,
+                    RotorType::Tirpitz1,
+                ],
+                ReflectorType::ReflectorTirpitz,
+            )
+            .unwrap();
+        enigma.set_reflector_position('J').unwrap();
+        enigma.set_left_rotor_position_from_char('T').unwrap();
+        enigma.set_middle_rotor_position_from_char('O').unwrap();
+        enigma.set_right_rotor_position_from_char('K').unwrap();
+
+        assert_eq!(
+            enigma.encrypt_str("TOKYOBERLINVERBINDUNG").unwrap(),
+            "LZTMXKLPVNOCGBVBBPNMI"
+        );
+    }
+
+    #[test]
+    fn z30_should_encipher_digits_and_decipher_back() {
+        let wheel_order = [
+            RotorType::Z30Rotor2,
+            RotorType::Z30Rotor3,
+            RotorType::Z30Rotor1,
+        ];
+        let create = || {
+            let mut enigma = MachineModel::Z30
+                .create_numeric(wheel_order, ReflectorType::ReflectorZ30)
+                .unwrap();
+            enigma.set_reflector_position('7').unwrap();
+            enigma.set_left_rotor_position_from_char('3').unwrap();
+            enigma.set_middle_rotor_position_from_char('0').unwrap();
+            enigma.set_right_rotor_position_from_char('8').unwrap();
+            enigma
+        };
+
+        let ciphertext = create().encrypt_str("19390901").unwrap();
+
+        assert!(ciphertext.chars().all(|digit| digit.is_ascii_digit()));
+        assert!(
+            ciphertext
+                .chars()
+                .zip("19390901".chars())
+                .all(|(c, p)| c != p)
+        );
+        assert_eq!(create().encrypt_str(&ciphertext).unwrap(), "19390901");
+        assert_eq!(create().encrypt_str("12A"), Err(Error::InvalidLetter('A')));
+    }
+
+    #[test]
+    fn z30_rotors_should_step_through_ten_positions() {
+        let mut enigma = MachineModel::Z30
+            .create_numeric(
+                [
+                    RotorType::Z30Rotor1,
+                    RotorType::Z30Rotor2,
+                    RotorType::Z30Rotor3,
+                ],
+                ReflectorType::ReflectorZ30,
+            )
+            .unwrap();
+
+        enigma.increment_by(10 * 10 + 3);
+
+        assert_eq!(
+            (
+                enigma.get_left_rotor_position(),
+                enigma.get_middle_rotor_position(),
+                enigma.get_right_rotor_position()
+            ),
+            ('2', '1', '4')
+        );
+    }
+
+    #[test]
+    fn numeric_and_latin_models_should_not_mix() {
+        let wheel_order = [
+            RotorType::Z30Rotor1,
+            RotorType::Z30Rotor2,
+            RotorType::Z30Rotor3,
+        ];
+
+        assert_eq!(
+            MachineModel::Z30
+                .create(wheel_order, ReflectorType::ReflectorZ30)
+                .err(),
+            Some(ModelError::WrongAlphabet)
+        );
+        assert_eq!(
+            MachineModel::EnigmaI
+                .create_numeric(wheel_order, ReflectorType::ReflectorZ30)
+                .err(),
+            Some(ModelError::WrongAlphabet)
+        );
+    }
+
+    #[test]
+    fn enigma_i_model_should_match_historical_vector() {
+        let mut enigma = MachineModel::EnigmaI
+            .create(
+                [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3],
+                ReflectorType::ReflectorB,
+            )
+            .unwrap();
+
+        assert_eq!(enigma.encrypt_str("AAAAA").unwrap(), "BDZGO");
+    }
+}
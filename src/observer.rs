@@ -0,0 +1,187 @@
+use std::fmt::Debug;
+
+/// A setting of the machine that was changed by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsChange {
+    /// The rotor at this index, counting from the leftmost rotor, was replaced.
+    Rotor {
+        index: usize,
+    },
+    RotorPosition {
+        index: usize,
+        position: char,
+    },
+    RingSetting {
+        index: usize,
+        ring_setting: char,
+    },
+    Reflector,
+    ReflectorPosition {
+        position: char,
+    },
+    EntryWheel,
+    SteppingStrategy,
+    /// A plugboard cable was plugged between the two letters.
+    Transposition {
+        first: char,
+        second: char,
+    },
+    /// The plugboard cable on this letter was removed.
+    TranspositionCleared {
+        letter: char,
+    },
+    TranspositionsCleared,
+}
+
+///
+/// Receives the events of a running machine, e.g. to animate a replica or keep an audit log.
+///
+/// Every method does nothing by default, so an observer only implements what it is interested in.
+/// Methods take `&self` because the machine shares its observer with clones of itself; observers
+/// that record events keep them behind a `Mutex` or similar. Machines without an observer skip all
+/// of the bookkeeping needed to report events.
+///
+pub trait Observer: Debug + Send + Sync {
+    /// The rotor at `rotor`, counting from the leftmost rotor, stepped onto `position`.
+    fn on_step(&self, _rotor: usize, _position: char) {}
+
+    /// The rotor at `from` reached a step position and carried the rotor at `to` along.
+    fn on_notch_carry(&self, _from: usize, _to: usize) {}
+
+    /// A key was pressed and `output` lit up on the lamp board.
+    fn on_keypress(&self, _input: char, _output: char) {}
+
+    fn on_settings_change(&self, _change: SettingsChange) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::stepping::DoubleStep;
+    use crate::{Enigma, reflectors, rotors};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Step(usize, char),
+        Carry(usize, usize),
+        Keypress(char, char),
+        Settings(SettingsChange),
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<Event>>);
+
+    impl Recorder {
+        fn take(&self) -> Vec<Event> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    impl Observer for Recorder {
+        fn on_step(&self, rotor: usize, position: char) {
+            self.0.lock().unwrap().push(Event::Step(rotor, position));
+        }
+
+        fn on_notch_carry(&self, from: usize, to: usize) {
+            self.0.lock().unwrap().push(Event::Carry(from, to));
+        }
+
+        fn on_keypress(&self, input: char, output: char) {
+            self.0.lock().unwrap().push(Event::Keypress(input, output));
+        }
+
+        fn on_settings_change(&self, change: SettingsChange) {
+            self.0.lock().unwrap().push(Event::Settings(change));
+        }
+    }
+
+    fn enigma_with_recorder() -> (Enigma, Arc<Recorder>) {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        let recorder = Arc::new(Recorder::default());
+        enigma.set_observer(recorder.clone());
+        (enigma, recorder)
+    }
+
+    #[test]
+    fn observer_should_see_steps_carries_and_keypresses() {
+        let (mut enigma, recorder) = enigma_with_recorder();
+        enigma.set_right_rotor_position_from_char('V').unwrap();
+        recorder.take();
+
+        let output = enigma.encrypt_char('a').unwrap();
+
+        assert_eq!(
+            recorder.take(),
+            vec![
+                Event::Carry(2, 1),
+                Event::Step(1, 'B'),
+                Event::Step(2, 'W'),
+                Event::Keypress('A', output),
+            ]
+        );
+    }
+
+    #[test]
+    fn observer_should_not_see_a_carry_for_the_double_step() {
+        let (mut enigma, recorder) = enigma_with_recorder();
+        enigma.set_stepping_strategy(DoubleStep);
+        enigma.set_rotor_positions(&['A', 'E', 'W']).unwrap();
+        recorder.take();
+
+        let output = enigma.encrypt_char('a').unwrap();
+
+        assert_eq!(
+            recorder.take(),
+            vec![
+                Event::Carry(1, 0),
+                Event::Step(0, 'B'),
+                Event::Step(1, 'F'),
+                Event::Step(2, 'X'),
+                Event::Keypress('A', output),
+            ]
+        );
+    }
+
+    #[test]
+    fn observer_should_see_settings_changes_but_not_peeks() {
+        let (mut enigma, recorder) = enigma_with_recorder();
+
+        enigma.set_transposition('a', 'q').unwrap();
+        enigma.set_middle_rotor_ring_setting('C').unwrap();
+        enigma.peak_cipher('A').unwrap();
+        enigma.clear_transpositions();
+
+        assert_eq!(
+            recorder.take(),
+            vec![
+                Event::Settings(SettingsChange::Transposition {
+                    first: 'A',
+                    second: 'Q'
+                }),
+                Event::Settings(SettingsChange::RingSetting {
+                    index: 1,
+                    ring_setting: 'C'
+                }),
+                Event::Settings(SettingsChange::TranspositionsCleared),
+            ]
+        );
+    }
+
+    #[test]
+    fn cleared_observer_should_hear_nothing() {
+        let (mut enigma, recorder) = enigma_with_recorder();
+        enigma.clear_observer();
+
+        enigma.encrypt_str("HELLO").unwrap();
+        enigma.increment_by(100);
+
+        assert!(recorder.take().is_empty());
+    }
+}
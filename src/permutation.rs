@@ -0,0 +1,223 @@
+use std::fmt::{self, Display};
+
+use crate::alphabet::{Alphabet, LATIN};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::{Error, WiringError};
+use crate::letter_permutation::LetterPermutation;
+
+///
+/// A permutation of an alphabet, such as the substitution a whole machine applies at one position.
+///
+/// Products read left to right, the way the Polish cryptanalysts wrote them: `a.compose(&b)`
+/// applies `a` first and then `b`. Permutations are displayed in cycle notation without their fixed
+/// points, e.g. `(AB)(CDE)`, and the identity as `()`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permutation<const N: usize = ALPHABET_SIZE>(LetterPermutation<N>);
+
+impl Permutation {
+    /// Creates a permutation of the Latin alphabet from the letters A to Z map to, in order.
+    pub fn new(wiring: &str) -> Result<Self, WiringError> {
+        Self::with_alphabet(wiring, &LATIN)
+    }
+
+    pub fn identity() -> Self {
+        Self::identity_of(&LATIN)
+    }
+}
+
+impl<const N: usize> Permutation<N> {
+    /// Creates a permutation from the symbols the alphabet's symbols map to, in order.
+    pub fn with_alphabet(
+        wiring: &str,
+        alphabet: &'static Alphabet<N>,
+    ) -> Result<Self, WiringError> {
+        LetterPermutation::from_wiring(wiring, alphabet).map(Self)
+    }
+
+    pub fn identity_of(alphabet: &'static Alphabet<N>) -> Self {
+        Self::from_mapping(std::array::from_fn(|index| index as u8), alphabet)
+    }
+
+    /// Creates a permutation from the contact index every contact maps to, which must be bijective.
+    pub(crate) fn from_mapping(mapping: [u8; N], alphabet: &'static Alphabet<N>) -> Self {
+        Self(LetterPermutation::from_mapping(mapping, alphabet))
+    }
+
+    pub fn alphabet(&self) -> &'static Alphabet<N> {
+        self.0.alphabet()
+    }
+
+    /// Maps a symbol. Lowercase letters are accepted for uppercase ones.
+    pub fn apply(&self, symbol: char) -> Result<char, Error> {
+        let alphabet = self.alphabet();
+        alphabet
+            .index_of(symbol)
+            .or_else(|| alphabet.index_of(symbol.to_ascii_uppercase()))
+            .map(|index| alphabet.symbol(self.0.get_index(index)))
+            .ok_or(Error::InvalidLetter(symbol))
+    }
+
+    /// The symbols the alphabet's symbols map to, in alphabet order.
+    pub fn wiring(&self) -> String {
+        self.0.wiring()
+    }
+
+    /// Applies this permutation and then `then`.
+    pub fn compose(&self, then: &Self) -> Self {
+        let mapping = std::array::from_fn(|index| then.0.get_index(self.0.get_index(index)) as u8);
+        Self::from_mapping(mapping, self.alphabet())
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self(self.0.inverse())
+    }
+
+    ///
+    /// Relabels the permutation's symbols with `by`: where this permutation maps `x` to `y`, the
+    /// conjugate maps `by(x)` to `by(y)`. Conjugates have the same cycle type.
+    ///
+    pub fn conjugate(&self, by: &Self) -> Self {
+        by.inverse().compose(self).compose(by)
+    }
+
+    ///
+    /// The cycles of the permutation, fixed points included. Every cycle starts at its first symbol
+    /// in alphabet order, and the cycles are ordered by that symbol.
+    ///
+    pub fn cycles(&self) -> Vec<Vec<char>> {
+        let mut visited = [false; N];
+        let mut cycles = Vec::new();
+
+        for start in 0..N {
+            let mut cycle = Vec::new();
+            let mut current = start;
+            while !visited[current] {
+                visited[current] = true;
+                cycle.push(self.alphabet().symbol(current));
+                current = self.0.get_index(current);
+            }
+
+            if !cycle.is_empty() {
+                cycles.push(cycle);
+            }
+        }
+
+        cycles
+    }
+
+    /// The lengths of the cycles, fixed points included, from longest to shortest.
+    pub fn cycle_type(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.cycles().iter().map(Vec::len).collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths
+    }
+
+    pub fn fixed_points(&self) -> Vec<char> {
+        (0..N)
+            .filter(|&index| self.0.get_index(index) == index)
+            .map(|index| self.alphabet().symbol(index))
+            .collect()
+    }
+
+    /// Whether the permutation is its own inverse, like every substitution of an Enigma.
+    pub fn is_involution(&self) -> bool {
+        (0..N).all(|index| self.0.get_index(self.0.get_index(index)) == index)
+    }
+}
+
+impl<const N: usize> Display for Permutation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cycles: Vec<Vec<char>> = self
+            .cycles()
+            .into_iter()
+            .filter(|cycle| cycle.len() > 1)
+            .collect();
+        if cycles.is_empty() {
+            return f.write_str("()");
+        }
+
+        for cycle in cycles {
+            write!(f, "({})", String::from_iter(cycle))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::DIGITS;
+
+    const ROTOR_I: &str = "EKMFLGDQVZNTOWYHXUSPAIBRCJ";
+    const REFLECTOR_B: &str = "YRUHQSLDPXNGOKMIEBFZCWVJAT";
+
+    #[test]
+    fn permutation_should_display_in_cycle_notation() {
+        let permutation = Permutation::new(ROTOR_I).unwrap();
+
+        assert_eq!(
+            permutation.to_string(),
+            "(AELTPHQXRU)(BKNW)(CMOY)(DFG)(IV)(JZ)"
+        );
+        assert_eq!(permutation.fixed_points(), vec!['S']);
+        assert_eq!(permutation.cycle_type(), vec![10, 4, 4, 3, 2, 2, 1]);
+        assert_eq!(Permutation::identity().to_string(), "()");
+        assert_eq!(permutation.apply('a'), Ok('E'));
+        assert_eq!(permutation.apply('1'), Err(Error::InvalidLetter('1')));
+    }
+
+    #[test]
+    fn composing_with_the_inverse_should_give_the_identity() {
+        let permutation = Permutation::new(ROTOR_I).unwrap();
+
+        assert_eq!(
+            permutation.compose(&permutation.inverse()),
+            Permutation::identity()
+        );
+        assert_eq!(
+            permutation.inverse().compose(&permutation),
+            Permutation::identity()
+        );
+        assert_eq!(permutation.inverse().wiring(), "UWYGADFPVZBECKMTHXSLRINQOJ");
+    }
+
+    #[test]
+    fn composition_should_apply_the_left_permutation_first() {
+        let rotor = Permutation::new(ROTOR_I).unwrap();
+        let reflector = Permutation::new(REFLECTOR_B).unwrap();
+
+        let product = rotor.compose(&reflector);
+
+        assert_eq!(product.apply('A'), reflector.apply('E'));
+        assert!(reflector.is_involution());
+        assert!(!product.is_involution());
+    }
+
+    #[test]
+    fn conjugation_should_relabel_cycles() {
+        let rotor = Permutation::new(ROTOR_I).unwrap();
+        let reflector = Permutation::new(REFLECTOR_B).unwrap();
+
+        let conjugate = reflector.conjugate(&rotor);
+
+        assert_eq!(conjugate.cycle_type(), reflector.cycle_type());
+        assert!(conjugate.is_involution());
+        assert_eq!(
+            conjugate.apply(rotor.apply('A').unwrap()),
+            rotor.apply(reflector.apply('A').unwrap())
+        );
+    }
+
+    #[test]
+    fn permutations_should_work_on_any_alphabet() {
+        let permutation = Permutation::with_alphabet("2134567890", &DIGITS).unwrap();
+
+        assert_eq!(permutation.to_string(), "(12)");
+        assert_eq!(permutation.fixed_points().len(), 8);
+        assert_eq!(
+            Permutation::with_alphabet("2234567890", &DIGITS),
+            Err(WiringError::Repeated('2'))
+        );
+    }
+}
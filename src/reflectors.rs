@@ -1,16 +1,82 @@
-use crate::{consts::ALPHABET_SIZE, letter_permutation::LetterPermutation};
+use crate::alphabet::{DIGITS, LATIN};
+use crate::consts::ALPHABET_SIZE;
+use crate::error::{Error, WiringError};
+use crate::letter_permutation::LetterPermutation;
+use crate::models::ModelError;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ReflectorType {
     ReflectorA,
     ReflectorB,
     ReflectorC,
+    ReflectorBThin,
+    ReflectorCThin,
+    ReflectorCommercial,
+    ReflectorAbwehrG,
+    ReflectorRailway,
+    ReflectorSwissK,
+    ReflectorTirpitz,
+    ReflectorTypexExample,
+    ReflectorZ30,
+    /// A reflector wired by the user rather than taken from a historical machine.
+    Custom,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Reflector {
-    pub map: LetterPermutation<'static>,
+pub struct Reflector<const N: usize = ALPHABET_SIZE> {
+    pub map: LetterPermutation<N>,
     pub typ: ReflectorType,
+    position: usize,
+}
+
+impl<const N: usize> Reflector<N> {
+    pub(crate) fn new(map: LetterPermutation<N>, typ: ReflectorType) -> Self {
+        Self {
+            map,
+            typ,
+            position: 0,
+        }
+    }
+
+    ///
+    /// Maps a letter through the reflector, taking its position into account.
+    ///
+    /// Military reflectors stay at position 'A', where this is the plain wiring. Commercial machines
+    /// let the operator turn the reflector to any of its positions.
+    ///
+    pub fn map_letter(&self, letter: char) -> Result<char, Error> {
+        let index = self
+            .map
+            .alphabet()
+            .index_of(letter.to_ascii_uppercase())
+            .ok_or(Error::InvalidLetter(letter))?;
+
+        let mapped = self.map.get_index((index + self.position) % N);
+
+        Ok(self.map.alphabet().symbol((mapped + N - self.position) % N))
+    }
+
+    pub fn set_position(&mut self, position: char) -> Result<(), Error> {
+        self.position = self
+            .map
+            .alphabet()
+            .index_of(position.to_ascii_uppercase())
+            .ok_or(Error::InvalidLetter(position))?;
+        Ok(())
+    }
+
+    pub fn get_position(&self) -> char {
+        self.map.alphabet().symbol(self.position)
+    }
+
+    /// The symbols the alphabet's symbols are wired to at position 'A', in alphabet order.
+    pub fn get_wiring(&self) -> String {
+        self.map.wiring()
+    }
+
+    pub(crate) fn increment_by(&mut self, amount: usize) {
+        self.position = (self.position + amount) % N;
+    }
 }
 
 static REFLECTOR_A_MAP: [(char, char); ALPHABET_SIZE] = [
@@ -100,23 +166,402 @@ static REFLECTOR_C_MAP: [(char, char); ALPHABET_SIZE] = [
     ('Z', 'L'),
 ];
 
+static REFLECTOR_B_THIN_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'E'),
+    ('B', 'N'),
+    ('C', 'K'),
+    ('D', 'Q'),
+    ('E', 'A'),
+    ('F', 'U'),
+    ('G', 'Y'),
+    ('H', 'W'),
+    ('I', 'J'),
+    ('J', 'I'),
+    ('K', 'C'),
+    ('L', 'O'),
+    ('M', 'P'),
+    ('N', 'B'),
+    ('O', 'L'),
+    ('P', 'M'),
+    ('Q', 'D'),
+    ('R', 'X'),
+    ('S', 'Z'),
+    ('T', 'V'),
+    ('U', 'F'),
+    ('V', 'T'),
+    ('W', 'H'),
+    ('X', 'R'),
+    ('Y', 'G'),
+    ('Z', 'S'),
+];
+
+static REFLECTOR_C_THIN_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'R'),
+    ('B', 'D'),
+    ('C', 'O'),
+    ('D', 'B'),
+    ('E', 'J'),
+    ('F', 'N'),
+    ('G', 'T'),
+    ('H', 'K'),
+    ('I', 'V'),
+    ('J', 'E'),
+    ('K', 'H'),
+    ('L', 'M'),
+    ('M', 'L'),
+    ('N', 'F'),
+    ('O', 'C'),
+    ('P', 'W'),
+    ('Q', 'Z'),
+    ('R', 'A'),
+    ('S', 'X'),
+    ('T', 'G'),
+    ('U', 'Y'),
+    ('V', 'I'),
+    ('W', 'P'),
+    ('X', 'S'),
+    ('Y', 'U'),
+    ('Z', 'Q'),
+];
+
+static REFLECTOR_COMMERCIAL_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'I'),
+    ('B', 'M'),
+    ('C', 'E'),
+    ('D', 'T'),
+    ('E', 'C'),
+    ('F', 'G'),
+    ('G', 'F'),
+    ('H', 'R'),
+    ('I', 'A'),
+    ('J', 'Y'),
+    ('K', 'S'),
+    ('L', 'Q'),
+    ('M', 'B'),
+    ('N', 'Z'),
+    ('O', 'X'),
+    ('P', 'W'),
+    ('Q', 'L'),
+    ('R', 'H'),
+    ('S', 'K'),
+    ('T', 'D'),
+    ('U', 'V'),
+    ('V', 'U'),
+    ('W', 'P'),
+    ('X', 'O'),
+    ('Y', 'J'),
+    ('Z', 'N'),
+];
+
+static REFLECTOR_ABWEHR_G_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'R'),
+    ('B', 'U'),
+    ('C', 'L'),
+    ('D', 'Q'),
+    ('E', 'M'),
+    ('F', 'Z'),
+    ('G', 'J'),
+    ('H', 'S'),
+    ('I', 'Y'),
+    ('J', 'G'),
+    ('K', 'O'),
+    ('L', 'C'),
+    ('M', 'E'),
+    ('N', 'T'),
+    ('O', 'K'),
+    ('P', 'W'),
+    ('Q', 'D'),
+    ('R', 'A'),
+    ('S', 'H'),
+    ('T', 'N'),
+    ('U', 'B'),
+    ('V', 'X'),
+    ('W', 'P'),
+    ('X', 'V'),
+    ('Y', 'I'),
+    ('Z', 'F'),
+];
+
+static REFLECTOR_RAILWAY_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'Q'),
+    ('B', 'Y'),
+    ('C', 'H'),
+    ('D', 'O'),
+    ('E', 'G'),
+    ('F', 'N'),
+    ('G', 'E'),
+    ('H', 'C'),
+    ('I', 'V'),
+    ('J', 'P'),
+    ('K', 'U'),
+    ('L', 'Z'),
+    ('M', 'T'),
+    ('N', 'F'),
+    ('O', 'D'),
+    ('P', 'J'),
+    ('Q', 'A'),
+    ('R', 'X'),
+    ('S', 'W'),
+    ('T', 'M'),
+    ('U', 'K'),
+    ('V', 'I'),
+    ('W', 'S'),
+    ('X', 'R'),
+    ('Y', 'B'),
+    ('Z', 'L'),
+];
+
+static REFLECTOR_TIRPITZ_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'G'),
+    ('B', 'E'),
+    ('C', 'K'),
+    ('D', 'P'),
+    ('E', 'B'),
+    ('F', 'T'),
+    ('G', 'A'),
+    ('H', 'U'),
+    ('I', 'M'),
+    ('J', 'O'),
+    ('K', 'C'),
+    ('L', 'N'),
+    ('M', 'I'),
+    ('N', 'L'),
+    ('O', 'J'),
+    ('P', 'D'),
+    ('Q', 'X'),
+    ('R', 'Z'),
+    ('S', 'Y'),
+    ('T', 'F'),
+    ('U', 'H'),
+    ('V', 'W'),
+    ('W', 'V'),
+    ('X', 'Q'),
+    ('Y', 'S'),
+    ('Z', 'R'),
+];
+
+static REFLECTOR_TYPEX_EXAMPLE_MAP: [(char, char); ALPHABET_SIZE] = [
+    ('A', 'N'),
+    ('B', 'C'),
+    ('C', 'B'),
+    ('D', 'K'),
+    ('E', 'I'),
+    ('F', 'G'),
+    ('G', 'F'),
+    ('H', 'M'),
+    ('I', 'E'),
+    ('J', 'X'),
+    ('K', 'D'),
+    ('L', 'U'),
+    ('M', 'H'),
+    ('N', 'A'),
+    ('O', 'R'),
+    ('P', 'Y'),
+    ('Q', 'W'),
+    ('R', 'O'),
+    ('S', 'T'),
+    ('T', 'S'),
+    ('U', 'L'),
+    ('V', 'Z'),
+    ('W', 'Q'),
+    ('X', 'J'),
+    ('Y', 'P'),
+    ('Z', 'V'),
+];
+
+static REFLECTOR_Z30_MAP: [(char, char); 10] = [
+    ('1', '5'),
+    ('2', '0'),
+    ('3', '7'),
+    ('4', '9'),
+    ('5', '1'),
+    ('6', '8'),
+    ('7', '3'),
+    ('8', '6'),
+    ('9', '4'),
+    ('0', '2'),
+];
+
 pub fn create_reflector_a() -> Reflector {
-    Reflector {
-        map: LetterPermutation::new(&REFLECTOR_A_MAP),
-        typ: ReflectorType::ReflectorA,
-    }
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_A_MAP),
+        ReflectorType::ReflectorA,
+    )
 }
 
 pub fn create_reflector_b() -> Reflector {
-    Reflector {
-        map: LetterPermutation::new(&REFLECTOR_B_MAP),
-        typ: ReflectorType::ReflectorB,
-    }
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_B_MAP),
+        ReflectorType::ReflectorB,
+    )
 }
 
 pub fn create_reflector_c() -> Reflector {
-    Reflector {
-        map: LetterPermutation::new(&REFLECTOR_C_MAP),
-        typ: ReflectorType::ReflectorC,
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_C_MAP),
+        ReflectorType::ReflectorC,
+    )
+}
+
+/// The thin reflector B of the M4, which makes room for the greek wheel.
+pub fn create_reflector_b_thin() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_B_THIN_MAP),
+        ReflectorType::ReflectorBThin,
+    )
+}
+
+/// The thin reflector C of the M4, which makes room for the greek wheel.
+pub fn create_reflector_c_thin() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_C_THIN_MAP),
+        ReflectorType::ReflectorCThin,
+    )
+}
+
+/// The reflector of the commercial Enigma D and K, which can be set to any position.
+pub fn create_reflector_commercial() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_COMMERCIAL_MAP),
+        ReflectorType::ReflectorCommercial,
+    )
+}
+
+///
+/// The reflector of the Abwehr Enigma G, which is driven by the left rotor. Like the rotors, it is
+/// wired like that of machine G312 rather than the G31.
+///
+pub fn create_reflector_abwehr_g() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_ABWEHR_G_MAP),
+        ReflectorType::ReflectorAbwehrG,
+    )
+}
+
+/// The settable reflector of the Reichsbahn (Rocket) Enigma.
+pub fn create_reflector_railway() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_RAILWAY_MAP),
+        ReflectorType::ReflectorRailway,
+    )
+}
+
+/// The settable reflector of the Swiss-K, wired like the commercial reflector.
+pub fn create_reflector_swiss_k() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_COMMERCIAL_MAP),
+        ReflectorType::ReflectorSwissK,
+    )
+}
+
+/// The settable reflector of the Enigma T (Tirpitz).
+pub fn create_reflector_tirpitz() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_TIRPITZ_MAP),
+        ReflectorType::ReflectorTirpitz,
+    )
+}
+
+///
+/// An example reflector for the Typex, wired like the example reflector of CyberChef's Typex
+/// operation. It is not the wiring of a historical Typex reflector.
+///
+pub fn create_reflector_typex_example() -> Reflector {
+    Reflector::new(
+        LetterPermutation::from_table(&REFLECTOR_TYPEX_EXAMPLE_MAP),
+        ReflectorType::ReflectorTypexExample,
+    )
+}
+
+/// The settable reflector of the numeric Enigma Z30.
+pub fn create_reflector_z30() -> Reflector<10> {
+    Reflector::new(
+        LetterPermutation::from_table_with_alphabet(&REFLECTOR_Z30_MAP, &DIGITS),
+        ReflectorType::ReflectorZ30,
+    )
+}
+
+///
+/// Creates a reflector of the Latin alphabet with a custom wiring, given as the letters A to Z are
+/// wired to. Like a rewirable UKW-D, it must swap the letters in pairs.
+///
+pub fn create_custom_reflector(wiring: &str) -> Result<Reflector, WiringError> {
+    let map = LetterPermutation::from_wiring(wiring, &LATIN)?;
+    for index in 0..ALPHABET_SIZE {
+        let mapped = map.get_index(index);
+        if mapped == index {
+            return Err(WiringError::SelfConnected(LATIN.symbol(index)));
+        }
+        if map.get_index(mapped) != index {
+            return Err(WiringError::NotReciprocal(LATIN.symbol(index)));
+        }
+    }
+
+    Ok(Reflector::new(map, ReflectorType::Custom))
+}
+
+///
+/// Creates a reflector of the Latin alphabet by its type. The Z30's numeric reflector and custom
+/// reflectors have factories of their own and are rejected with [`Error::UnsupportedComponent`].
+///
+pub fn create_reflector(typ: ReflectorType) -> Result<Reflector, Error> {
+    let reflector = match typ {
+        ReflectorType::ReflectorA => create_reflector_a(),
+        ReflectorType::ReflectorB => create_reflector_b(),
+        ReflectorType::ReflectorC => create_reflector_c(),
+        ReflectorType::ReflectorBThin => create_reflector_b_thin(),
+        ReflectorType::ReflectorCThin => create_reflector_c_thin(),
+        ReflectorType::ReflectorCommercial => create_reflector_commercial(),
+        ReflectorType::ReflectorAbwehrG => create_reflector_abwehr_g(),
+        ReflectorType::ReflectorRailway => create_reflector_railway(),
+        ReflectorType::ReflectorSwissK => create_reflector_swiss_k(),
+        ReflectorType::ReflectorTirpitz => create_reflector_tirpitz(),
+        ReflectorType::ReflectorTypexExample => create_reflector_typex_example(),
+        ReflectorType::ReflectorZ30 | ReflectorType::Custom => {
+            return Err(Error::UnsupportedComponent(
+                ModelError::UnsupportedReflector(typ),
+            ));
+        }
+    };
+
+    Ok(reflector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_reflector_should_reject_reflectors_with_their_own_factories() {
+        assert_eq!(
+            create_reflector(ReflectorType::ReflectorZ30).unwrap_err(),
+            Error::UnsupportedComponent(ModelError::UnsupportedReflector(
+                ReflectorType::ReflectorZ30
+            ))
+        );
+        assert!(create_reflector(ReflectorType::Custom).is_err());
+        assert!(create_reflector(ReflectorType::ReflectorB).is_ok());
+    }
+
+    #[test]
+    fn reflector_should_stay_an_involution_at_every_position() {
+        let mut reflector = create_reflector_commercial();
+
+        for position in 'A'..='Z' {
+            reflector.set_position(position).unwrap();
+            for letter in 'A'..='Z' {
+                let reflected = reflector.map_letter(letter).unwrap();
+                assert_ne!(reflected, letter);
+                assert_eq!(reflector.map_letter(reflected).unwrap(), letter);
+            }
+        }
+    }
+
+    #[test]
+    fn set_position_fails_when_not_alphabetic() {
+        assert_eq!(
+            create_reflector_commercial().set_position('3'),
+            Err(Error::InvalidLetter('3'))
+        );
     }
 }
@@ -1,207 +1,440 @@
 use std::fmt::Debug;
 
+use crate::alphabet::Alphabet;
 use crate::consts::ALPHABET_SIZE;
+use crate::error::Error;
 use crate::letter_permutation::LetterPermutation;
-use crate::{consts, error::Error};
 
 type PositionType = usize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RotorType {
     Rotor1,
     Rotor2,
     Rotor3,
     Rotor4,
     Rotor5,
+    Rotor6,
+    Rotor7,
+    Rotor8,
+    Beta,
+    Gamma,
+    CommercialD1,
+    CommercialD2,
+    CommercialD3,
+    SwissK1,
+    SwissK2,
+    SwissK3,
+    AbwehrG1,
+    AbwehrG2,
+    AbwehrG3,
+    Railway1,
+    Railway2,
+    Railway3,
+    Tirpitz1,
+    Tirpitz2,
+    Tirpitz3,
+    Tirpitz4,
+    Tirpitz5,
+    Tirpitz6,
+    Tirpitz7,
+    Tirpitz8,
+    TypexExample1,
+    TypexExample2,
+    TypexExample3,
+    TypexExample4,
+    TypexExample5,
+    TypexExample6,
+    TypexExample7,
+    Z30Rotor1,
+    Z30Rotor2,
+    Z30Rotor3,
+    /// A rotor wired by the user rather than taken from a historical machine.
+    Custom,
+}
+
+/// Which way round a rotor is inserted into the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Orientation {
+    #[default]
+    Forward,
+    Reversed,
 }
 
 #[derive(Clone)]
-pub struct RotorProps {
-    permutation: LetterPermutation<'static>,
-    inverse: LetterPermutation<'static>,
-    step_position: PositionType,
+pub struct RotorProps<const N: usize = ALPHABET_SIZE> {
+    permutation: LetterPermutation<N>,
+    inverse: LetterPermutation<N>,
+    step_positions: u32,
     typ: RotorType,
 }
 
-impl Debug for RotorProps {
+impl<const N: usize> Debug for RotorProps<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RotorProps")
-            .field("step_position", &self.step_position)
+            .field("step_positions", &self.get_step_positions())
             .field("typ", &self.typ)
             .finish()
     }
 }
 
-impl RotorProps {
+impl<const N: usize> RotorProps<N> {
+    ///
+    /// `step_positions` lists the symbols the rotor steps onto when it carries the next rotor.
+    /// They are kept as a bitmask, so alphabets may have at most 32 symbols.
+    ///
     pub(crate) fn new(
-        permutation: LetterPermutation<'static>,
-        inverse: LetterPermutation<'static>,
-        step_position: char,
+        permutation: LetterPermutation<N>,
+        inverse: LetterPermutation<N>,
+        step_positions: &str,
         typ: RotorType,
     ) -> Self {
-        let step_position = step_position as PositionType - consts::FIRST_LETTER as PositionType;
+        assert!(
+            N <= u32::BITS as usize,
+            "Rotors support at most 32 positions"
+        );
+
+        let alphabet = permutation.alphabet();
+        let step_positions = step_positions.chars().fold(0, |mask, position| {
+            let index = alphabet
+                .index_of(position)
+                .unwrap_or_else(|| panic!("Step position {position} is not part of the alphabet"));
+            mask | 1 << index
+        });
 
         Self {
             permutation,
             inverse,
-            step_position,
+            step_positions,
             typ,
         }
     }
+
+    /// The positions the rotor steps onto when it carries the next rotor along.
+    pub fn get_step_positions(&self) -> Vec<char> {
+        (0..N)
+            .filter(|&position| self.is_step_position(position))
+            .map(|position| self.get_alphabet().symbol(position))
+            .collect()
+    }
+
+    pub(crate) fn is_step_position(&self, position: PositionType) -> bool {
+        self.step_positions & (1 << position) != 0
+    }
+
+    pub fn get_type(&self) -> RotorType {
+        self.typ
+    }
+
+    /// The symbols the alphabet's symbols are wired to, in alphabet order, e.g. "EKMF..." for rotor I.
+    pub fn get_wiring(&self) -> String {
+        self.permutation.wiring()
+    }
+
+    pub fn get_alphabet(&self) -> &'static Alphabet<N> {
+        self.permutation.alphabet()
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct Rotor {
-    rotor_props: RotorProps,
+pub struct Rotor<const N: usize = ALPHABET_SIZE> {
+    rotor_props: RotorProps<N>,
     position: PositionType,
     ring_setting: PositionType,
+    core_reversed: bool,
+    orientation: Orientation,
 }
 
-impl Rotor {
-    pub(super) fn new(props: RotorProps, position: char, ring_setting: char) -> Self {
-        if !ring_setting.is_alphabetic() || !position.is_alphabetic() {
-            panic!("Position and ring setting must letters")
-        }
-
-        let position = position.to_ascii_uppercase();
-        let ring_setting = ring_setting.to_ascii_uppercase();
-
-        let position = (position as u8 - consts::FIRST_LETTER as u8) as PositionType;
-        let ring_setting = (ring_setting as u8 - consts::FIRST_LETTER as u8) as PositionType;
-
+impl<const N: usize> Rotor<N> {
+    /// A rotor at the first position of its alphabet, with its ring at the first position too.
+    pub(super) fn new(props: RotorProps<N>) -> Self {
         Self {
             rotor_props: props,
-            position,
-            ring_setting,
+            position: 0,
+            ring_setting: 0,
+            core_reversed: false,
+            orientation: Orientation::Forward,
         }
     }
 
+    pub fn get_props(&self) -> &RotorProps<N> {
+        &self.rotor_props
+    }
+
     pub fn map_letter(&self, letter: char) -> Result<char, Error> {
-        self.calculate_mapped_letter_by_ring_setting(
-            letter,
-            self.rotor_props.permutation,
-            self.ring_setting,
-        )
+        match self.core_reversed {
+            false => self.calculate_mapped_letter_by_ring_setting(
+                letter,
+                self.rotor_props.permutation,
+                false,
+            ),
+            true => {
+                self.calculate_mapped_letter_by_ring_setting(letter, self.rotor_props.inverse, true)
+            }
+        }
     }
 
     pub fn inverse_map_letter(&self, letter: char) -> Result<char, Error> {
-        self.calculate_mapped_letter_by_ring_setting(
-            letter,
-            self.rotor_props.inverse,
-            0 - self.ring_setting,
-        )
+        match self.core_reversed {
+            false => self.calculate_mapped_letter_by_ring_setting(
+                letter,
+                self.rotor_props.inverse,
+                false,
+            ),
+            true => self.calculate_mapped_letter_by_ring_setting(
+                letter,
+                self.rotor_props.permutation,
+                true,
+            ),
+        }
     }
 
-    pub(crate) fn increment(&mut self) -> bool {
-        self.position += 1;
-        self.position %= consts::ALPHABET_SIZE;
-        self.position == self.rotor_props.step_position
+    ///
+    /// Returns the rotor inserted the other way round.
+    ///
+    /// The whole rotor is flipped: its contacts are mirrored and its wiring inverted, and its ring
+    /// and notches end up mirrored as well. Positions, ring settings and step positions of a reversed
+    /// rotor are read in the machine's direction of rotation, so it still steps through the alphabet
+    /// and behaves like a rotor wired `W'(i) = -W⁻¹(-i)`. The rotor keeps its physical setting, so
+    /// reversing twice gives back the original rotor.
+    ///
+    pub fn reversed(mut self) -> Self {
+        let mirror = |index: PositionType| (N - index) % N;
+        // Carries happen when stepping off a notch, which the mirror turns into stepping onto it.
+        let notch_mirror = |index: PositionType| (N + 2 - index) % N;
+
+        self.rotor_props.step_positions = (0..N)
+            .filter(|&position| self.rotor_props.is_step_position(position))
+            .fold(0, |mask, position| mask | 1 << notch_mirror(position));
+        self.position = mirror(self.position);
+        self.ring_setting = mirror(self.ring_setting);
+        self.core_reversed = !self.core_reversed;
+        self.orientation = match self.orientation {
+            Orientation::Forward => Orientation::Reversed,
+            Orientation::Reversed => Orientation::Forward,
+        };
+
+        self
     }
 
-    pub(crate) fn increment_by(&mut self, amount: PositionType) -> usize {
-        if amount == 0 {
-            return 0;
-        }
+    pub fn get_orientation(&self) -> Orientation {
+        self.orientation
+    }
 
-        let incremented_position = (self.position + amount) % ALPHABET_SIZE;
-
-        let mut next_rotor_increment_amount = 0;
-        let num_of_steps_to_next_stepover =
-            match (self.rotor_props.step_position as isize - self.position as isize)
-                .rem_euclid(ALPHABET_SIZE as isize) as usize
-            {
-                0 => ALPHABET_SIZE,
-                x => x,
-            };
-        if amount % ALPHABET_SIZE >= num_of_steps_to_next_stepover {
-            next_rotor_increment_amount += 1;
-        }
+    ///
+    /// Turns the wired core around inside the rotor's ring, as the Typex allowed. The core's contacts
+    /// are mirrored and its wiring inverted, while the ring and its notches stay in place.
+    ///
+    pub(crate) fn set_core_reversed(&mut self, reversed: bool) {
+        self.core_reversed = reversed;
+    }
 
-        next_rotor_increment_amount += amount / ALPHABET_SIZE;
+    pub(crate) fn is_core_reversed(&self) -> bool {
+        self.core_reversed
+    }
 
-        self.set_position_from_int(incremented_position);
+    /// Whether the next step takes the rotor onto a step position, i.e. its notch is at the pawl.
+    pub(crate) fn is_at_notch(&self) -> bool {
+        self.rotor_props.is_step_position((self.position + 1) % N)
+    }
 
-        next_rotor_increment_amount
+    /// Steps the rotor once and returns whether it stepped onto a step position.
+    pub fn increment(&mut self) -> bool {
+        self.position += 1;
+        self.position %= N;
+        self.rotor_props.is_step_position(self.position)
     }
 
-    pub fn set_position(&mut self, position: char) {
-        if !position.is_alphabetic() {
-            panic!("Unable to set current position to non-alphabetic character (got {position})");
-        }
+    /// Steps the rotor `amount` times and returns how many times it stepped onto a step position.
+    pub fn increment_by(&mut self, amount: PositionType) -> usize {
+        let full_turns = amount / N;
+        let remaining_steps = amount % N;
+
+        let mut next_rotor_increment_amount =
+            full_turns * self.rotor_props.step_positions.count_ones() as usize;
+        next_rotor_increment_amount += (1..=remaining_steps)
+            .filter(|steps| {
+                self.rotor_props
+                    .is_step_position((self.position + steps) % N)
+            })
+            .count();
+
+        self.position = (self.position + amount) % N;
 
-        self.position =
-            position.to_ascii_uppercase() as PositionType - consts::FIRST_LETTER as PositionType;
+        next_rotor_increment_amount
+    }
+
+    pub fn set_position(&mut self, position: char) -> Result<(), Error> {
+        self.position = self.index_of(position)?;
+        Ok(())
     }
 
     pub fn get_position(&self) -> char {
-        (self.position as u8 + consts::FIRST_LETTER as u8) as char
+        self.alphabet().symbol(self.position)
     }
 
     /// Sets position from an integer. Note that the 0 corresponds to 'A' and 25 corresponds to 'Z'.
-    pub fn set_position_from_int(&mut self, position: PositionType) {
-        if position >= consts::ALPHABET_SIZE {
-            panic!(
-                "Position must be a valid letter index (between 0 and {})",
-                consts::ALPHABET_SIZE - 1
-            );
+    pub fn set_position_from_int(&mut self, position: PositionType) -> Result<(), Error> {
+        if position >= N {
+            return Err(Error::PositionOutOfRange { position, size: N });
         }
 
         self.position = position;
+        Ok(())
+    }
+
+    pub fn set_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
+        self.ring_setting = self.index_of(ring_setting)?;
+        Ok(())
+    }
+
+    pub fn get_ring_setting(&self) -> char {
+        self.alphabet().symbol(self.ring_setting)
+    }
+
+    fn index_of(&self, letter: char) -> Result<PositionType, Error> {
+        self.alphabet()
+            .index_of(letter.to_ascii_uppercase())
+            .ok_or(Error::InvalidLetter(letter))
+    }
+
+    fn alphabet(&self) -> &'static Alphabet<N> {
+        self.rotor_props.get_alphabet()
     }
 
     fn calculate_mapped_letter_by_ring_setting(
         &self,
         letter: char,
-        letter_map: LetterPermutation,
-        ring_setting_number: PositionType,
+        letter_map: LetterPermutation<N>,
+        mirrored: bool,
     ) -> Result<char, Error> {
-        let letter = match letter.is_alphabetic() {
-            true => letter.to_ascii_uppercase(),
-            false => return Err(Error::NonAlphabetic),
-        };
-
-        let position_reduced_by_ring_setting =
-            (self.position - ring_setting_number).rem_euclid(consts::ALPHABET_SIZE);
+        let input_index = self.index_of(letter)?;
 
-        let input_index = (letter as i8 - consts::FIRST_LETTER as i8
-            + position_reduced_by_ring_setting as i8)
-            .rem_euclid(consts::ALPHABET_SIZE as i8) as u8;
-        let input_letter = (input_index + consts::FIRST_LETTER as u8) as char;
+        let position_reduced_by_ring_setting = (self.position + N - self.ring_setting) % N;
 
-        let mapped_letter = letter_map.get(input_letter).unwrap();
+        let core_index = (input_index + position_reduced_by_ring_setting) % N;
+        let mapped_index = match mirrored {
+            false => letter_map.get_index(core_index),
+            true => (N - letter_map.get_index((N - core_index) % N)) % N,
+        };
 
-        let mapped_letter_increased_by_ring_setting = ((mapped_letter as i8
-            - consts::FIRST_LETTER as i8
-            - position_reduced_by_ring_setting as i8)
-            .rem_euclid(consts::ALPHABET_SIZE as i8)
-            + consts::FIRST_LETTER as i8)
-            as u8;
-        Ok((mapped_letter_increased_by_ring_setting) as char)
+        Ok(self
+            .alphabet()
+            .symbol((mapped_index + N - position_reduced_by_ring_setting) % N))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::Orientation;
+    use crate::error::Error;
     use crate::rotors;
 
     #[test]
-    #[should_panic]
-    fn set_position_from_int_should_panic_when_out_of_bounds() {
+    fn set_position_from_int_should_fail_when_out_of_bounds() {
+        let mut rotor = rotors::create_rotor_1();
+
+        assert_eq!(
+            rotor.set_position_from_int(27),
+            Err(Error::PositionOutOfRange {
+                position: 27,
+                size: 26
+            })
+        );
+    }
+
+    #[test]
+    fn set_position_fails_when_not_alphabetic() {
         let mut rotor = rotors::create_rotor_1();
-        rotor.set_position_from_int(27);
+
+        assert_eq!(rotor.set_position('='), Err(Error::InvalidLetter('=')));
+        assert_eq!(rotor.set_ring_setting('='), Err(Error::InvalidLetter('=')));
+        assert_eq!(rotor.get_position(), 'A');
     }
 
     #[test]
-    #[should_panic]
-    fn set_position_panics_when_not_alphabetic() {
+    fn increment_by_should_count_every_step_position() {
+        let mut rotor = rotors::create_rotor_abwehr_g_3();
+        let notches = rotor.get_props().get_step_positions().len();
+
+        assert_eq!(notches, 11);
+        assert_eq!(rotor.increment_by(26 * 3), 3 * notches);
+        assert_eq!(rotor.get_position(), 'A');
+
+        let single_steps = (0..40).filter(|_| rotor.increment()).count();
+        rotor.set_position('A').unwrap();
+        assert_eq!(rotor.increment_by(40), single_steps);
+    }
+
+    #[test]
+    fn reversed_core_should_map_through_mirrored_inverse_wiring() {
+        let wiring = "EKMFLGDQVZNTOWYHXUSPAIBRCJ";
+        let index = |letter: char| (letter as u8 - b'A') as usize;
+        let letter = |index: usize| (b'A' + index as u8) as char;
         let mut rotor = rotors::create_rotor_1();
-        rotor.set_position('=');
+        rotor.set_core_reversed(true);
+        rotor.set_position('C').unwrap();
+        rotor.set_ring_setting('F').unwrap();
+        let offset = 26 + index('C') - index('F');
+
+        for input in 'A'..='Z' {
+            let core = (26 - (index(input) + offset) % 26) % 26;
+            let inverse = wiring.find(letter(core)).unwrap();
+            let expected = letter((26 - inverse + 26 * 2 - offset) % 26);
+
+            assert_eq!(rotor.map_letter(input), Ok(expected));
+            assert_eq!(rotor.inverse_map_letter(expected), Ok(input));
+        }
+    }
+
+    #[test]
+    fn reversed_rotor_should_mirror_wiring_ring_and_notch() {
+        let mut rotor = rotors::create_rotor_1();
+        rotor.set_position('C').unwrap();
+        rotor.set_ring_setting('F').unwrap();
+        let reversed = rotor.clone().reversed();
+
+        assert_eq!(reversed.get_orientation(), Orientation::Reversed);
+        assert_eq!(reversed.get_position(), 'Y');
+        assert_eq!(reversed.get_ring_setting(), 'V');
+        // Rotor I carries when leaving Q, whose mirror image is K.
+        assert_eq!(reversed.get_props().get_step_positions(), vec!['L']);
+
+        let mirror = |letter: char| (b'A' + (26 - (letter as u8 - b'A')) % 26) as char;
+        for input in 'A'..='Z' {
+            let through_reversed = reversed.map_letter(input).unwrap();
+            let through_back = rotor.inverse_map_letter(mirror(input)).unwrap();
+
+            assert_eq!(through_reversed, mirror(through_back));
+        }
+    }
+
+    #[test]
+    fn reversing_twice_should_give_back_the_rotor() {
+        let mut rotor = rotors::create_rotor_abwehr_g_1();
+        rotor.set_position('M').unwrap();
+        rotor.set_ring_setting('D').unwrap();
+        let twice = rotor.clone().reversed().reversed();
+
+        assert_eq!(twice.get_orientation(), Orientation::Forward);
+        assert_eq!(twice.get_position(), 'M');
+        assert_eq!(twice.get_ring_setting(), 'D');
+        assert_eq!(
+            twice.get_props().get_step_positions(),
+            rotor.get_props().get_step_positions()
+        );
+        assert!(('A'..='Z').all(|letter| twice.map_letter(letter) == rotor.map_letter(letter)));
     }
 
     #[test]
     fn set_position_from_int_should_work() {
         let mut rotor = rotors::create_rotor_1();
-        rotor.set_position_from_int(25);
+        rotor.set_position_from_int(25).unwrap();
 
         assert_eq!(rotor.get_position(), 'Z');
     }
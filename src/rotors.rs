@@ -1,17 +1,9 @@
-use crate::consts::ALPHABET_SIZE;
-use crate::letter_permutation::{LetterPermutation, utils as permutation_utils};
+use crate::alphabet::{DIGITS, LATIN};
+use crate::error::{Error, WiringError};
+use crate::letter_permutation::{LetterPermutation, bidir_map};
+use crate::models::ModelError;
 use crate::rotor::{Rotor, RotorProps, RotorType};
 
-macro_rules! bidir_map {
-    ($forward:ident, $inverse:ident, {$($k:expr => $v:expr),*$(,)?}) => {
-        static $forward: [(char, char); ALPHABET_SIZE] = [
-            $(($k, $v)),*
-        ];
-
-        static $inverse: [(char, char); ALPHABET_SIZE] = permutation_utils::reverse_permutation($forward);
-    };
-}
-
 bidir_map!(ROTOR_1_PERMUTATION, ROTOR_1_INVERSE,
 {
     'A' => 'E',
@@ -161,52 +153,1509 @@ bidir_map!(ROTOR_5_PERMUTATION, ROTOR_5_INVERSE, {
     'Z' => 'K',
 });
 
+bidir_map!(ROTOR_6_PERMUTATION, ROTOR_6_INVERSE,
+{
+    'A' => 'J',
+    'B' => 'P',
+    'C' => 'G',
+    'D' => 'V',
+    'E' => 'O',
+    'F' => 'U',
+    'G' => 'M',
+    'H' => 'F',
+    'I' => 'Y',
+    'J' => 'Q',
+    'K' => 'B',
+    'L' => 'E',
+    'M' => 'N',
+    'N' => 'H',
+    'O' => 'Z',
+    'P' => 'R',
+    'Q' => 'D',
+    'R' => 'K',
+    'S' => 'A',
+    'T' => 'S',
+    'U' => 'X',
+    'V' => 'L',
+    'W' => 'I',
+    'X' => 'C',
+    'Y' => 'T',
+    'Z' => 'W',
+});
+
+bidir_map!(ROTOR_7_PERMUTATION, ROTOR_7_INVERSE,
+{
+    'A' => 'N',
+    'B' => 'Z',
+    'C' => 'J',
+    'D' => 'H',
+    'E' => 'G',
+    'F' => 'R',
+    'G' => 'C',
+    'H' => 'X',
+    'I' => 'M',
+    'J' => 'Y',
+    'K' => 'S',
+    'L' => 'W',
+    'M' => 'B',
+    'N' => 'O',
+    'O' => 'U',
+    'P' => 'F',
+    'Q' => 'A',
+    'R' => 'I',
+    'S' => 'V',
+    'T' => 'L',
+    'U' => 'P',
+    'V' => 'E',
+    'W' => 'K',
+    'X' => 'Q',
+    'Y' => 'D',
+    'Z' => 'T',
+});
+
+bidir_map!(ROTOR_8_PERMUTATION, ROTOR_8_INVERSE,
+{
+    'A' => 'F',
+    'B' => 'K',
+    'C' => 'Q',
+    'D' => 'H',
+    'E' => 'T',
+    'F' => 'L',
+    'G' => 'X',
+    'H' => 'O',
+    'I' => 'C',
+    'J' => 'B',
+    'K' => 'J',
+    'L' => 'S',
+    'M' => 'P',
+    'N' => 'D',
+    'O' => 'Z',
+    'P' => 'R',
+    'Q' => 'A',
+    'R' => 'M',
+    'S' => 'E',
+    'T' => 'W',
+    'U' => 'N',
+    'V' => 'I',
+    'W' => 'U',
+    'X' => 'Y',
+    'Y' => 'G',
+    'Z' => 'V',
+});
+
+bidir_map!(BETA_PERMUTATION, BETA_INVERSE,
+{
+    'A' => 'L',
+    'B' => 'E',
+    'C' => 'Y',
+    'D' => 'J',
+    'E' => 'V',
+    'F' => 'C',
+    'G' => 'N',
+    'H' => 'I',
+    'I' => 'X',
+    'J' => 'W',
+    'K' => 'P',
+    'L' => 'B',
+    'M' => 'Q',
+    'N' => 'M',
+    'O' => 'D',
+    'P' => 'R',
+    'Q' => 'T',
+    'R' => 'A',
+    'S' => 'K',
+    'T' => 'Z',
+    'U' => 'G',
+    'V' => 'F',
+    'W' => 'U',
+    'X' => 'H',
+    'Y' => 'O',
+    'Z' => 'S',
+});
+
+bidir_map!(GAMMA_PERMUTATION, GAMMA_INVERSE,
+{
+    'A' => 'F',
+    'B' => 'S',
+    'C' => 'O',
+    'D' => 'K',
+    'E' => 'A',
+    'F' => 'N',
+    'G' => 'U',
+    'H' => 'E',
+    'I' => 'R',
+    'J' => 'H',
+    'K' => 'M',
+    'L' => 'B',
+    'M' => 'T',
+    'N' => 'I',
+    'O' => 'Y',
+    'P' => 'C',
+    'Q' => 'W',
+    'R' => 'L',
+    'S' => 'Q',
+    'T' => 'P',
+    'U' => 'Z',
+    'V' => 'X',
+    'W' => 'V',
+    'X' => 'G',
+    'Y' => 'J',
+    'Z' => 'D',
+});
+
+bidir_map!(COMMERCIAL_D_1_PERMUTATION, COMMERCIAL_D_1_INVERSE,
+{
+    'A' => 'L',
+    'B' => 'P',
+    'C' => 'G',
+    'D' => 'S',
+    'E' => 'Z',
+    'F' => 'M',
+    'G' => 'H',
+    'H' => 'A',
+    'I' => 'E',
+    'J' => 'O',
+    'K' => 'Q',
+    'L' => 'K',
+    'M' => 'V',
+    'N' => 'X',
+    'O' => 'R',
+    'P' => 'F',
+    'Q' => 'Y',
+    'R' => 'B',
+    'S' => 'U',
+    'T' => 'T',
+    'U' => 'N',
+    'V' => 'I',
+    'W' => 'C',
+    'X' => 'J',
+    'Y' => 'D',
+    'Z' => 'W',
+});
+
+bidir_map!(COMMERCIAL_D_2_PERMUTATION, COMMERCIAL_D_2_INVERSE,
+{
+    'A' => 'S',
+    'B' => 'L',
+    'C' => 'V',
+    'D' => 'G',
+    'E' => 'B',
+    'F' => 'T',
+    'G' => 'F',
+    'H' => 'X',
+    'I' => 'J',
+    'J' => 'Q',
+    'K' => 'O',
+    'L' => 'H',
+    'M' => 'E',
+    'N' => 'W',
+    'O' => 'I',
+    'P' => 'R',
+    'Q' => 'Z',
+    'R' => 'Y',
+    'S' => 'A',
+    'T' => 'M',
+    'U' => 'K',
+    'V' => 'P',
+    'W' => 'C',
+    'X' => 'N',
+    'Y' => 'D',
+    'Z' => 'U',
+});
+
+bidir_map!(COMMERCIAL_D_3_PERMUTATION, COMMERCIAL_D_3_INVERSE,
+{
+    'A' => 'C',
+    'B' => 'J',
+    'C' => 'G',
+    'D' => 'D',
+    'E' => 'P',
+    'F' => 'S',
+    'G' => 'H',
+    'H' => 'K',
+    'I' => 'T',
+    'J' => 'U',
+    'K' => 'R',
+    'L' => 'A',
+    'M' => 'W',
+    'N' => 'Z',
+    'O' => 'X',
+    'P' => 'F',
+    'Q' => 'M',
+    'R' => 'Y',
+    'S' => 'N',
+    'T' => 'Q',
+    'U' => 'O',
+    'V' => 'B',
+    'W' => 'V',
+    'X' => 'L',
+    'Y' => 'I',
+    'Z' => 'E',
+});
+
+bidir_map!(SWISS_K_1_PERMUTATION, SWISS_K_1_INVERSE,
+{
+    'A' => 'P',
+    'B' => 'E',
+    'C' => 'Z',
+    'D' => 'U',
+    'E' => 'O',
+    'F' => 'H',
+    'G' => 'X',
+    'H' => 'S',
+    'I' => 'C',
+    'J' => 'V',
+    'K' => 'F',
+    'L' => 'M',
+    'M' => 'T',
+    'N' => 'B',
+    'O' => 'G',
+    'P' => 'L',
+    'Q' => 'R',
+    'R' => 'I',
+    'S' => 'N',
+    'T' => 'Q',
+    'U' => 'J',
+    'V' => 'W',
+    'W' => 'A',
+    'X' => 'Y',
+    'Y' => 'D',
+    'Z' => 'K',
+});
+
+bidir_map!(SWISS_K_2_PERMUTATION, SWISS_K_2_INVERSE,
+{
+    'A' => 'Z',
+    'B' => 'O',
+    'C' => 'U',
+    'D' => 'E',
+    'E' => 'S',
+    'F' => 'Y',
+    'G' => 'D',
+    'H' => 'K',
+    'I' => 'F',
+    'J' => 'W',
+    'K' => 'P',
+    'L' => 'C',
+    'M' => 'I',
+    'N' => 'Q',
+    'O' => 'X',
+    'P' => 'H',
+    'Q' => 'M',
+    'R' => 'V',
+    'S' => 'B',
+    'T' => 'L',
+    'U' => 'G',
+    'V' => 'N',
+    'W' => 'J',
+    'X' => 'R',
+    'Y' => 'A',
+    'Z' => 'T',
+});
+
+bidir_map!(SWISS_K_3_PERMUTATION, SWISS_K_3_INVERSE,
+{
+    'A' => 'E',
+    'B' => 'H',
+    'C' => 'R',
+    'D' => 'V',
+    'E' => 'X',
+    'F' => 'G',
+    'G' => 'A',
+    'H' => 'O',
+    'I' => 'B',
+    'J' => 'Q',
+    'K' => 'U',
+    'L' => 'S',
+    'M' => 'I',
+    'N' => 'M',
+    'O' => 'Z',
+    'P' => 'F',
+    'Q' => 'L',
+    'R' => 'Y',
+    'S' => 'N',
+    'T' => 'W',
+    'U' => 'K',
+    'V' => 'T',
+    'W' => 'P',
+    'X' => 'D',
+    'Y' => 'J',
+    'Z' => 'C',
+});
+
+bidir_map!(ABWEHR_G_1_PERMUTATION, ABWEHR_G_1_INVERSE,
+{
+    'A' => 'D',
+    'B' => 'M',
+    'C' => 'T',
+    'D' => 'W',
+    'E' => 'S',
+    'F' => 'I',
+    'G' => 'L',
+    'H' => 'R',
+    'I' => 'U',
+    'J' => 'Y',
+    'K' => 'Q',
+    'L' => 'N',
+    'M' => 'K',
+    'N' => 'F',
+    'O' => 'E',
+    'P' => 'J',
+    'Q' => 'C',
+    'R' => 'A',
+    'S' => 'Z',
+    'T' => 'B',
+    'U' => 'P',
+    'V' => 'G',
+    'W' => 'X',
+    'X' => 'O',
+    'Y' => 'H',
+    'Z' => 'V',
+});
+
+bidir_map!(ABWEHR_G_2_PERMUTATION, ABWEHR_G_2_INVERSE,
+{
+    'A' => 'H',
+    'B' => 'Q',
+    'C' => 'Z',
+    'D' => 'G',
+    'E' => 'P',
+    'F' => 'J',
+    'G' => 'T',
+    'H' => 'M',
+    'I' => 'O',
+    'J' => 'B',
+    'K' => 'L',
+    'L' => 'N',
+    'M' => 'C',
+    'N' => 'I',
+    'O' => 'F',
+    'P' => 'D',
+    'Q' => 'Y',
+    'R' => 'A',
+    'S' => 'W',
+    'T' => 'V',
+    'U' => 'E',
+    'V' => 'U',
+    'W' => 'S',
+    'X' => 'R',
+    'Y' => 'K',
+    'Z' => 'X',
+});
+
+bidir_map!(ABWEHR_G_3_PERMUTATION, ABWEHR_G_3_INVERSE,
+{
+    'A' => 'U',
+    'B' => 'Q',
+    'C' => 'N',
+    'D' => 'T',
+    'E' => 'L',
+    'F' => 'S',
+    'G' => 'Z',
+    'H' => 'F',
+    'I' => 'M',
+    'J' => 'R',
+    'K' => 'E',
+    'L' => 'H',
+    'M' => 'D',
+    'N' => 'P',
+    'O' => 'X',
+    'P' => 'K',
+    'Q' => 'I',
+    'R' => 'B',
+    'S' => 'V',
+    'T' => 'Y',
+    'U' => 'G',
+    'V' => 'J',
+    'W' => 'C',
+    'X' => 'W',
+    'Y' => 'O',
+    'Z' => 'A',
+});
+
+bidir_map!(RAILWAY_1_PERMUTATION, RAILWAY_1_INVERSE,
+{
+    'A' => 'J',
+    'B' => 'G',
+    'C' => 'D',
+    'D' => 'Q',
+    'E' => 'O',
+    'F' => 'X',
+    'G' => 'U',
+    'H' => 'S',
+    'I' => 'C',
+    'J' => 'A',
+    'K' => 'M',
+    'L' => 'I',
+    'M' => 'F',
+    'N' => 'R',
+    'O' => 'V',
+    'P' => 'T',
+    'Q' => 'P',
+    'R' => 'N',
+    'S' => 'E',
+    'T' => 'W',
+    'U' => 'K',
+    'V' => 'B',
+    'W' => 'L',
+    'X' => 'Z',
+    'Y' => 'Y',
+    'Z' => 'H',
+});
+
+bidir_map!(RAILWAY_2_PERMUTATION, RAILWAY_2_INVERSE,
+{
+    'A' => 'N',
+    'B' => 'T',
+    'C' => 'Z',
+    'D' => 'P',
+    'E' => 'S',
+    'F' => 'F',
+    'G' => 'B',
+    'H' => 'O',
+    'I' => 'K',
+    'J' => 'M',
+    'K' => 'W',
+    'L' => 'R',
+    'M' => 'C',
+    'N' => 'J',
+    'O' => 'D',
+    'P' => 'I',
+    'Q' => 'V',
+    'R' => 'L',
+    'S' => 'A',
+    'T' => 'E',
+    'U' => 'Y',
+    'V' => 'U',
+    'W' => 'X',
+    'X' => 'H',
+    'Y' => 'G',
+    'Z' => 'Q',
+});
+
+bidir_map!(RAILWAY_3_PERMUTATION, RAILWAY_3_INVERSE,
+{
+    'A' => 'J',
+    'B' => 'V',
+    'C' => 'I',
+    'D' => 'U',
+    'E' => 'B',
+    'F' => 'H',
+    'G' => 'T',
+    'H' => 'C',
+    'I' => 'D',
+    'J' => 'Y',
+    'K' => 'A',
+    'L' => 'K',
+    'M' => 'E',
+    'N' => 'Q',
+    'O' => 'Z',
+    'P' => 'P',
+    'Q' => 'O',
+    'R' => 'S',
+    'S' => 'G',
+    'T' => 'X',
+    'U' => 'N',
+    'V' => 'R',
+    'W' => 'M',
+    'X' => 'W',
+    'Y' => 'F',
+    'Z' => 'L',
+});
+
+bidir_map!(TIRPITZ_1_PERMUTATION, TIRPITZ_1_INVERSE,
+{
+    'A' => 'K',
+    'B' => 'P',
+    'C' => 'T',
+    'D' => 'Y',
+    'E' => 'U',
+    'F' => 'E',
+    'G' => 'L',
+    'H' => 'O',
+    'I' => 'C',
+    'J' => 'V',
+    'K' => 'G',
+    'L' => 'R',
+    'M' => 'F',
+    'N' => 'Q',
+    'O' => 'D',
+    'P' => 'A',
+    'Q' => 'N',
+    'R' => 'J',
+    'S' => 'M',
+    'T' => 'B',
+    'U' => 'S',
+    'V' => 'W',
+    'W' => 'H',
+    'X' => 'Z',
+    'Y' => 'X',
+    'Z' => 'I',
+});
+
+bidir_map!(TIRPITZ_2_PERMUTATION, TIRPITZ_2_INVERSE,
+{
+    'A' => 'U',
+    'B' => 'P',
+    'C' => 'H',
+    'D' => 'Z',
+    'E' => 'L',
+    'F' => 'W',
+    'G' => 'E',
+    'H' => 'Q',
+    'I' => 'M',
+    'J' => 'T',
+    'K' => 'D',
+    'L' => 'J',
+    'M' => 'X',
+    'N' => 'C',
+    'O' => 'A',
+    'P' => 'K',
+    'Q' => 'S',
+    'R' => 'O',
+    'S' => 'I',
+    'T' => 'G',
+    'U' => 'V',
+    'V' => 'B',
+    'W' => 'Y',
+    'X' => 'F',
+    'Y' => 'N',
+    'Z' => 'R',
+});
+
+bidir_map!(TIRPITZ_3_PERMUTATION, TIRPITZ_3_INVERSE,
+{
+    'A' => 'Q',
+    'B' => 'U',
+    'C' => 'D',
+    'D' => 'L',
+    'E' => 'Y',
+    'F' => 'R',
+    'G' => 'F',
+    'H' => 'E',
+    'I' => 'K',
+    'J' => 'O',
+    'K' => 'N',
+    'L' => 'V',
+    'M' => 'Z',
+    'N' => 'A',
+    'O' => 'X',
+    'P' => 'W',
+    'Q' => 'H',
+    'R' => 'M',
+    'S' => 'G',
+    'T' => 'P',
+    'U' => 'J',
+    'V' => 'B',
+    'W' => 'S',
+    'X' => 'I',
+    'Y' => 'C',
+    'Z' => 'T',
+});
+
+bidir_map!(TIRPITZ_4_PERMUTATION, TIRPITZ_4_INVERSE,
+{
+    'A' => 'C',
+    'B' => 'I',
+    'C' => 'W',
+    'D' => 'T',
+    'E' => 'B',
+    'F' => 'K',
+    'G' => 'X',
+    'H' => 'N',
+    'I' => 'R',
+    'J' => 'E',
+    'K' => 'S',
+    'L' => 'P',
+    'M' => 'F',
+    'N' => 'L',
+    'O' => 'Y',
+    'P' => 'D',
+    'Q' => 'A',
+    'R' => 'G',
+    'S' => 'V',
+    'T' => 'H',
+    'U' => 'Q',
+    'V' => 'U',
+    'W' => 'O',
+    'X' => 'J',
+    'Y' => 'Z',
+    'Z' => 'M',
+});
+
+bidir_map!(TIRPITZ_5_PERMUTATION, TIRPITZ_5_INVERSE,
+{
+    'A' => 'U',
+    'B' => 'A',
+    'C' => 'X',
+    'D' => 'G',
+    'E' => 'I',
+    'F' => 'S',
+    'G' => 'N',
+    'H' => 'J',
+    'I' => 'B',
+    'J' => 'V',
+    'K' => 'E',
+    'L' => 'R',
+    'M' => 'D',
+    'N' => 'Y',
+    'O' => 'L',
+    'P' => 'F',
+    'Q' => 'Z',
+    'R' => 'W',
+    'S' => 'T',
+    'T' => 'P',
+    'U' => 'C',
+    'V' => 'K',
+    'W' => 'O',
+    'X' => 'H',
+    'Y' => 'M',
+    'Z' => 'Q',
+});
+
+bidir_map!(TIRPITZ_6_PERMUTATION, TIRPITZ_6_INVERSE,
+{
+    'A' => 'X',
+    'B' => 'F',
+    'C' => 'U',
+    'D' => 'Z',
+    'E' => 'G',
+    'F' => 'A',
+    'G' => 'L',
+    'H' => 'V',
+    'I' => 'H',
+    'J' => 'C',
+    'K' => 'N',
+    'L' => 'Y',
+    'M' => 'S',
+    'N' => 'E',
+    'O' => 'W',
+    'P' => 'Q',
+    'Q' => 'T',
+    'R' => 'D',
+    'S' => 'M',
+    'T' => 'R',
+    'U' => 'B',
+    'V' => 'K',
+    'W' => 'P',
+    'X' => 'I',
+    'Y' => 'O',
+    'Z' => 'J',
+});
+
+bidir_map!(TIRPITZ_7_PERMUTATION, TIRPITZ_7_INVERSE,
+{
+    'A' => 'B',
+    'B' => 'J',
+    'C' => 'V',
+    'D' => 'F',
+    'E' => 'T',
+    'F' => 'X',
+    'G' => 'P',
+    'H' => 'L',
+    'I' => 'N',
+    'J' => 'A',
+    'K' => 'Y',
+    'L' => 'O',
+    'M' => 'Z',
+    'N' => 'I',
+    'O' => 'K',
+    'P' => 'W',
+    'Q' => 'G',
+    'R' => 'D',
+    'S' => 'Q',
+    'T' => 'E',
+    'U' => 'R',
+    'V' => 'U',
+    'W' => 'C',
+    'X' => 'H',
+    'Y' => 'S',
+    'Z' => 'M',
+});
+
+bidir_map!(TIRPITZ_8_PERMUTATION, TIRPITZ_8_INVERSE,
+{
+    'A' => 'Y',
+    'B' => 'M',
+    'C' => 'T',
+    'D' => 'P',
+    'E' => 'N',
+    'F' => 'Z',
+    'G' => 'H',
+    'H' => 'W',
+    'I' => 'K',
+    'J' => 'O',
+    'K' => 'D',
+    'L' => 'A',
+    'M' => 'J',
+    'N' => 'X',
+    'O' => 'E',
+    'P' => 'L',
+    'Q' => 'U',
+    'R' => 'Q',
+    'S' => 'V',
+    'T' => 'G',
+    'U' => 'C',
+    'V' => 'B',
+    'W' => 'I',
+    'X' => 'S',
+    'Y' => 'F',
+    'Z' => 'R',
+});
+
+bidir_map!(Z30_1_PERMUTATION, Z30_1_INVERSE, 10,
+{
+    '1' => '6',
+    '2' => '4',
+    '3' => '1',
+    '4' => '8',
+    '5' => '2',
+    '6' => '7',
+    '7' => '0',
+    '8' => '3',
+    '9' => '5',
+    '0' => '9',
+});
+
+bidir_map!(Z30_2_PERMUTATION, Z30_2_INVERSE, 10,
+{
+    '1' => '5',
+    '2' => '8',
+    '3' => '4',
+    '4' => '1',
+    '5' => '0',
+    '6' => '9',
+    '7' => '7',
+    '8' => '6',
+    '9' => '3',
+    '0' => '2',
+});
+
+bidir_map!(Z30_3_PERMUTATION, Z30_3_INVERSE, 10,
+{
+    '1' => '3',
+    '2' => '5',
+    '3' => '8',
+    '4' => '1',
+    '5' => '6',
+    '6' => '2',
+    '7' => '0',
+    '8' => '7',
+    '9' => '9',
+    '0' => '4',
+});
+
+bidir_map!(TYPEX_EXAMPLE_1_PERMUTATION, TYPEX_EXAMPLE_1_INVERSE,
+{
+    'A' => 'M',
+    'B' => 'C',
+    'C' => 'Y',
+    'D' => 'L',
+    'E' => 'P',
+    'F' => 'Q',
+    'G' => 'U',
+    'H' => 'V',
+    'I' => 'R',
+    'J' => 'X',
+    'K' => 'G',
+    'L' => 'S',
+    'M' => 'A',
+    'N' => 'O',
+    'O' => 'W',
+    'P' => 'N',
+    'Q' => 'B',
+    'R' => 'J',
+    'S' => 'E',
+    'T' => 'Z',
+    'U' => 'D',
+    'V' => 'T',
+    'W' => 'F',
+    'X' => 'K',
+    'Y' => 'H',
+    'Z' => 'I',
+});
+
+bidir_map!(TYPEX_EXAMPLE_2_PERMUTATION, TYPEX_EXAMPLE_2_INVERSE,
+{
+    'A' => 'K',
+    'B' => 'H',
+    'C' => 'W',
+    'D' => 'E',
+    'E' => 'N',
+    'F' => 'R',
+    'G' => 'C',
+    'H' => 'B',
+    'I' => 'I',
+    'J' => 'S',
+    'K' => 'X',
+    'L' => 'J',
+    'M' => 'Q',
+    'N' => 'G',
+    'O' => 'O',
+    'P' => 'F',
+    'Q' => 'M',
+    'R' => 'A',
+    'S' => 'P',
+    'T' => 'V',
+    'U' => 'Y',
+    'V' => 'Z',
+    'W' => 'D',
+    'X' => 'L',
+    'Y' => 'T',
+    'Z' => 'U',
+});
+
+bidir_map!(TYPEX_EXAMPLE_3_PERMUTATION, TYPEX_EXAMPLE_3_INVERSE,
+{
+    'A' => 'B',
+    'B' => 'Y',
+    'C' => 'P',
+    'D' => 'D',
+    'E' => 'Z',
+    'F' => 'M',
+    'G' => 'G',
+    'H' => 'I',
+    'I' => 'K',
+    'J' => 'Q',
+    'K' => 'C',
+    'L' => 'U',
+    'M' => 'S',
+    'N' => 'A',
+    'O' => 'T',
+    'P' => 'R',
+    'Q' => 'E',
+    'R' => 'H',
+    'S' => 'O',
+    'T' => 'J',
+    'U' => 'N',
+    'V' => 'L',
+    'W' => 'F',
+    'X' => 'W',
+    'Y' => 'X',
+    'Z' => 'V',
+});
+
+bidir_map!(TYPEX_EXAMPLE_4_PERMUTATION, TYPEX_EXAMPLE_4_INVERSE,
+{
+    'A' => 'Z',
+    'B' => 'A',
+    'C' => 'N',
+    'D' => 'J',
+    'E' => 'C',
+    'F' => 'G',
+    'G' => 'D',
+    'H' => 'L',
+    'I' => 'V',
+    'J' => 'H',
+    'K' => 'I',
+    'L' => 'X',
+    'M' => 'O',
+    'N' => 'B',
+    'O' => 'R',
+    'P' => 'P',
+    'Q' => 'M',
+    'R' => 'S',
+    'S' => 'W',
+    'T' => 'Q',
+    'U' => 'U',
+    'V' => 'K',
+    'W' => 'F',
+    'X' => 'Y',
+    'Y' => 'E',
+    'Z' => 'T',
+});
+
+bidir_map!(TYPEX_EXAMPLE_5_PERMUTATION, TYPEX_EXAMPLE_5_INVERSE,
+{
+    'A' => 'Q',
+    'B' => 'X',
+    'C' => 'B',
+    'D' => 'G',
+    'E' => 'U',
+    'F' => 'T',
+    'G' => 'O',
+    'H' => 'V',
+    'I' => 'F',
+    'J' => 'C',
+    'K' => 'Z',
+    'L' => 'P',
+    'M' => 'J',
+    'N' => 'I',
+    'O' => 'H',
+    'P' => 'S',
+    'Q' => 'W',
+    'R' => 'E',
+    'S' => 'R',
+    'T' => 'Y',
+    'U' => 'N',
+    'V' => 'D',
+    'W' => 'A',
+    'X' => 'M',
+    'Y' => 'L',
+    'Z' => 'K',
+});
+
+bidir_map!(TYPEX_EXAMPLE_6_PERMUTATION, TYPEX_EXAMPLE_6_INVERSE,
+{
+    'A' => 'R',
+    'B' => 'O',
+    'C' => 'J',
+    'D' => 'M',
+    'E' => 'Q',
+    'F' => 'D',
+    'G' => 'Z',
+    'H' => 'E',
+    'I' => 'S',
+    'J' => 'K',
+    'K' => 'A',
+    'L' => 'X',
+    'M' => 'N',
+    'N' => 'P',
+    'O' => 'W',
+    'P' => 'F',
+    'Q' => 'L',
+    'R' => 'U',
+    'S' => 'Y',
+    'T' => 'I',
+    'U' => 'B',
+    'V' => 'T',
+    'W' => 'C',
+    'X' => 'H',
+    'Y' => 'V',
+    'Z' => 'G',
+});
+
+bidir_map!(TYPEX_EXAMPLE_7_PERMUTATION, TYPEX_EXAMPLE_7_INVERSE,
+{
+    'A' => 'P',
+    'B' => 'N',
+    'C' => 'U',
+    'D' => 'B',
+    'E' => 'D',
+    'F' => 'K',
+    'G' => 'S',
+    'H' => 'F',
+    'I' => 'C',
+    'J' => 'J',
+    'K' => 'R',
+    'L' => 'A',
+    'M' => 'O',
+    'N' => 'V',
+    'O' => 'E',
+    'P' => 'W',
+    'Q' => 'T',
+    'R' => 'G',
+    'S' => 'M',
+    'T' => 'L',
+    'U' => 'Q',
+    'V' => 'H',
+    'W' => 'Y',
+    'X' => 'X',
+    'Y' => 'Z',
+    'Z' => 'I',
+});
+
 pub fn create_rotor_1() -> Rotor {
     let props = RotorProps::new(
-        LetterPermutation::new(&ROTOR_1_PERMUTATION),
-        LetterPermutation::new(&ROTOR_1_INVERSE),
-        'R',
+        LetterPermutation::from_table(&ROTOR_1_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_1_INVERSE),
+        "R",
         RotorType::Rotor1,
     );
-    Rotor::new(props, 'A', 'A')
+    Rotor::new(props)
 }
 
 pub fn create_rotor_2() -> Rotor {
     let props = RotorProps::new(
-        LetterPermutation::new(&ROTOR_2_PERMUTATION),
-        LetterPermutation::new(&ROTOR_2_INVERSE),
-        'F',
+        LetterPermutation::from_table(&ROTOR_2_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_2_INVERSE),
+        "F",
         RotorType::Rotor2,
     );
-    Rotor::new(props, 'A', 'A')
+    Rotor::new(props)
 }
 
 pub fn create_rotor_3() -> Rotor {
     let props = RotorProps::new(
-        LetterPermutation::new(&ROTOR_3_PERMUTATION),
-        LetterPermutation::new(&ROTOR_3_INVERSE),
-        'W',
+        LetterPermutation::from_table(&ROTOR_3_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_3_INVERSE),
+        "W",
         RotorType::Rotor3,
     );
-    Rotor::new(props, 'A', 'A')
+    Rotor::new(props)
 }
 
 pub fn create_rotor_4() -> Rotor {
     let props = RotorProps::new(
-        LetterPermutation::new(&ROTOR_4_PERMUTATION),
-        LetterPermutation::new(&ROTOR_4_INVERSE),
-        'K',
+        LetterPermutation::from_table(&ROTOR_4_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_4_INVERSE),
+        "K",
         RotorType::Rotor4,
     );
-    Rotor::new(props, 'A', 'A')
+    Rotor::new(props)
 }
 
 pub fn create_rotor_5() -> Rotor {
     let props = RotorProps::new(
-        LetterPermutation::new(&ROTOR_5_PERMUTATION),
-        LetterPermutation::new(&ROTOR_5_INVERSE),
-        'A',
+        LetterPermutation::from_table(&ROTOR_5_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_5_INVERSE),
+        "A",
         RotorType::Rotor5,
     );
-    Rotor::new(props, 'A', 'A')
+    Rotor::new(props)
+}
+
+pub fn create_rotor_6() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ROTOR_6_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_6_INVERSE),
+        "AN",
+        RotorType::Rotor6,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_7() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ROTOR_7_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_7_INVERSE),
+        "AN",
+        RotorType::Rotor7,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_8() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ROTOR_8_PERMUTATION),
+        LetterPermutation::from_table(&ROTOR_8_INVERSE),
+        "AN",
+        RotorType::Rotor8,
+    );
+    Rotor::new(props)
+}
+
+/// The Beta greek wheel of the M4, which sits left of the three stepping rotors and never steps.
+pub fn create_rotor_beta() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&BETA_PERMUTATION),
+        LetterPermutation::from_table(&BETA_INVERSE),
+        "",
+        RotorType::Beta,
+    );
+    Rotor::new(props)
+}
+
+/// The Gamma greek wheel of the M4, which sits left of the three stepping rotors and never steps.
+pub fn create_rotor_gamma() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&GAMMA_PERMUTATION),
+        LetterPermutation::from_table(&GAMMA_INVERSE),
+        "",
+        RotorType::Gamma,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_commercial_d_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&COMMERCIAL_D_1_PERMUTATION),
+        LetterPermutation::from_table(&COMMERCIAL_D_1_INVERSE),
+        "Z",
+        RotorType::CommercialD1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_commercial_d_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&COMMERCIAL_D_2_PERMUTATION),
+        LetterPermutation::from_table(&COMMERCIAL_D_2_INVERSE),
+        "F",
+        RotorType::CommercialD2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_commercial_d_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&COMMERCIAL_D_3_PERMUTATION),
+        LetterPermutation::from_table(&COMMERCIAL_D_3_INVERSE),
+        "O",
+        RotorType::CommercialD3,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_swiss_k_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&SWISS_K_1_PERMUTATION),
+        LetterPermutation::from_table(&SWISS_K_1_INVERSE),
+        "Z",
+        RotorType::SwissK1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_swiss_k_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&SWISS_K_2_PERMUTATION),
+        LetterPermutation::from_table(&SWISS_K_2_INVERSE),
+        "F",
+        RotorType::SwissK2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_swiss_k_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&SWISS_K_3_PERMUTATION),
+        LetterPermutation::from_table(&SWISS_K_3_INVERSE),
+        "O",
+        RotorType::SwissK3,
+    );
+    Rotor::new(props)
+}
+
+///
+/// The rotors of the Abwehr Enigma G are wired like those of machine G312. The G31 and other G
+/// machines had rotors of their own, which aren't included, so their messages don't decrypt with
+/// these rotors.
+///
+pub fn create_rotor_abwehr_g_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ABWEHR_G_1_PERMUTATION),
+        LetterPermutation::from_table(&ABWEHR_G_1_INVERSE),
+        "ABCDFGHJLMPQRTVWX",
+        RotorType::AbwehrG1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_abwehr_g_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ABWEHR_G_2_PERMUTATION),
+        LetterPermutation::from_table(&ABWEHR_G_2_INVERSE),
+        "ABDEGHILNORTUWZ",
+        RotorType::AbwehrG2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_abwehr_g_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&ABWEHR_G_3_PERMUTATION),
+        LetterPermutation::from_table(&ABWEHR_G_3_INVERSE),
+        "BFGILNOSVXY",
+        RotorType::AbwehrG3,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_railway_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&RAILWAY_1_PERMUTATION),
+        LetterPermutation::from_table(&RAILWAY_1_INVERSE),
+        "O",
+        RotorType::Railway1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_railway_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&RAILWAY_2_PERMUTATION),
+        LetterPermutation::from_table(&RAILWAY_2_INVERSE),
+        "F",
+        RotorType::Railway2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_railway_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&RAILWAY_3_PERMUTATION),
+        LetterPermutation::from_table(&RAILWAY_3_INVERSE),
+        "Z",
+        RotorType::Railway3,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_1_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_1_INVERSE),
+        "AFLRX",
+        RotorType::Tirpitz1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_2_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_2_INVERSE),
+        "AGMSX",
+        RotorType::Tirpitz2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_3_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_3_INVERSE),
+        "AFLRX",
+        RotorType::Tirpitz3,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_4() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_4_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_4_INVERSE),
+        "AGMSX",
+        RotorType::Tirpitz4,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_5() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_5_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_5_INVERSE),
+        "DGLSZ",
+        RotorType::Tirpitz5,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_6() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_6_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_6_INVERSE),
+        "FJNRY",
+        RotorType::Tirpitz6,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_7() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_7_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_7_INVERSE),
+        "DGLSZ",
+        RotorType::Tirpitz7,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_tirpitz_8() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TIRPITZ_8_PERMUTATION),
+        LetterPermutation::from_table(&TIRPITZ_8_INVERSE),
+        "FJNRY",
+        RotorType::Tirpitz8,
+    );
+    Rotor::new(props)
+}
+
+///
+/// The Typex example rotors are wired and notched like the example rotors 1 to 7 of CyberChef's
+/// Typex operation. They are not the wirings of any historical Typex rotor set.
+///
+pub fn create_rotor_typex_example_1() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_1_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_1_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_2() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_2_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_2_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_3() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_3_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_3_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample3,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_4() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_4_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_4_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample4,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_5() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_5_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_5_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample5,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_6() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_6_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_6_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample6,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_typex_example_7() -> Rotor {
+    let props = RotorProps::new(
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_7_PERMUTATION),
+        LetterPermutation::from_table(&TYPEX_EXAMPLE_7_INVERSE),
+        "BFHNQUW",
+        RotorType::TypexExample7,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_z30_1() -> Rotor<10> {
+    let props = RotorProps::new(
+        LetterPermutation::from_table_with_alphabet(&Z30_1_PERMUTATION, &DIGITS),
+        LetterPermutation::from_table_with_alphabet(&Z30_1_INVERSE, &DIGITS),
+        "1",
+        RotorType::Z30Rotor1,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_z30_2() -> Rotor<10> {
+    let props = RotorProps::new(
+        LetterPermutation::from_table_with_alphabet(&Z30_2_PERMUTATION, &DIGITS),
+        LetterPermutation::from_table_with_alphabet(&Z30_2_INVERSE, &DIGITS),
+        "1",
+        RotorType::Z30Rotor2,
+    );
+    Rotor::new(props)
+}
+
+pub fn create_rotor_z30_3() -> Rotor<10> {
+    let props = RotorProps::new(
+        LetterPermutation::from_table_with_alphabet(&Z30_3_PERMUTATION, &DIGITS),
+        LetterPermutation::from_table_with_alphabet(&Z30_3_INVERSE, &DIGITS),
+        "1",
+        RotorType::Z30Rotor3,
+    );
+    Rotor::new(props)
+}
+
+///
+/// Creates a rotor of the Latin alphabet with a custom wiring, given as the letters A to Z are wired
+/// to, and the positions it steps onto when it carries the next rotor.
+///
+pub fn create_custom_rotor(wiring: &str, step_positions: &str) -> Result<Rotor, WiringError> {
+    let permutation = LetterPermutation::from_wiring(wiring, &LATIN)?;
+    let step_positions = step_positions.to_ascii_uppercase();
+    if let Some(position) = step_positions.chars().find(|&p| !LATIN.contains(p)) {
+        return Err(WiringError::NotInAlphabet(position));
+    }
+
+    let props = RotorProps::new(
+        permutation,
+        permutation.inverse(),
+        &step_positions,
+        RotorType::Custom,
+    );
+    Ok(Rotor::new(props))
+}
+
+///
+/// Creates a rotor of the Latin alphabet by its type. The Z30's numeric rotors and custom rotors
+/// have factories of their own and are rejected with [`Error::UnsupportedComponent`].
+///
+pub fn create_rotor(typ: RotorType) -> Result<Rotor, Error> {
+    let rotor = match typ {
+        RotorType::Rotor1 => create_rotor_1(),
+        RotorType::Rotor2 => create_rotor_2(),
+        RotorType::Rotor3 => create_rotor_3(),
+        RotorType::Rotor4 => create_rotor_4(),
+        RotorType::Rotor5 => create_rotor_5(),
+        RotorType::Rotor6 => create_rotor_6(),
+        RotorType::Rotor7 => create_rotor_7(),
+        RotorType::Rotor8 => create_rotor_8(),
+        RotorType::Beta => create_rotor_beta(),
+        RotorType::Gamma => create_rotor_gamma(),
+        RotorType::CommercialD1 => create_rotor_commercial_d_1(),
+        RotorType::CommercialD2 => create_rotor_commercial_d_2(),
+        RotorType::CommercialD3 => create_rotor_commercial_d_3(),
+        RotorType::SwissK1 => create_rotor_swiss_k_1(),
+        RotorType::SwissK2 => create_rotor_swiss_k_2(),
+        RotorType::SwissK3 => create_rotor_swiss_k_3(),
+        RotorType::AbwehrG1 => create_rotor_abwehr_g_1(),
+        RotorType::AbwehrG2 => create_rotor_abwehr_g_2(),
+        RotorType::AbwehrG3 => create_rotor_abwehr_g_3(),
+        RotorType::Railway1 => create_rotor_railway_1(),
+        RotorType::Railway2 => create_rotor_railway_2(),
+        RotorType::Railway3 => create_rotor_railway_3(),
+        RotorType::Tirpitz1 => create_rotor_tirpitz_1(),
+        RotorType::Tirpitz2 => create_rotor_tirpitz_2(),
+        RotorType::Tirpitz3 => create_rotor_tirpitz_3(),
+        RotorType::Tirpitz4 => create_rotor_tirpitz_4(),
+        RotorType::Tirpitz5 => create_rotor_tirpitz_5(),
+        RotorType::Tirpitz6 => create_rotor_tirpitz_6(),
+        RotorType::Tirpitz7 => create_rotor_tirpitz_7(),
+        RotorType::Tirpitz8 => create_rotor_tirpitz_8(),
+        RotorType::TypexExample1 => create_rotor_typex_example_1(),
+        RotorType::TypexExample2 => create_rotor_typex_example_2(),
+        RotorType::TypexExample3 => create_rotor_typex_example_3(),
+        RotorType::TypexExample4 => create_rotor_typex_example_4(),
+        RotorType::TypexExample5 => create_rotor_typex_example_5(),
+        RotorType::TypexExample6 => create_rotor_typex_example_6(),
+        RotorType::TypexExample7 => create_rotor_typex_example_7(),
+        RotorType::Z30Rotor1 | RotorType::Z30Rotor2 | RotorType::Z30Rotor3 | RotorType::Custom => {
+            return Err(Error::UnsupportedComponent(ModelError::UnsupportedRotor(
+                typ,
+            )));
+        }
+    };
+
+    Ok(rotor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_rotor_should_reject_rotors_with_their_own_factories() {
+        assert_eq!(
+            create_rotor(RotorType::Z30Rotor1).unwrap_err(),
+            Error::UnsupportedComponent(ModelError::UnsupportedRotor(RotorType::Z30Rotor1))
+        );
+        assert!(create_rotor(RotorType::Custom).is_err());
+        assert_eq!(
+            create_rotor(RotorType::Rotor1)
+                .unwrap()
+                .get_props()
+                .get_type(),
+            RotorType::Rotor1
+        );
+    }
 }
@@ -1,137 +1,424 @@
+use std::sync::Arc;
+
+use crate::consts::ALPHABET_SIZE;
 use crate::error::Error;
+use crate::observer::{Observer, SettingsChange};
 use crate::rotor::Rotor;
+use crate::stepping::{PawlAndNotch, SteppingStrategy};
+use crate::trace::{RotorStep, StepCause};
+
+/// How a rotor in the stack takes part in stepping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum RotorRole {
+    /// Moved by the stepping strategy.
+    Stepping,
+    /// Set by hand and never stepped, like the M4's Beta and Gamma wheels.
+    GreekWheel,
+    /// A rotor that stays in place, like the Typex stators.
+    Stator,
+}
 
-#[derive(Debug)]
-pub struct RotorsController {
-    left: Rotor,
-    middle: Rotor,
-    right: Rotor,
+///
+/// A stack of any number of rotors between the entry wheel and the reflector, leftmost first.
+///
+/// The stepping rotors must be the rightmost ones of the stack. The three-rotor accessors
+/// (`left`, `middle` and `right`) address the three rightmost rotors.
+///
+#[derive(Debug, Clone)]
+pub struct RotorsController<const N: usize = ALPHABET_SIZE> {
+    rotors: Vec<Rotor<N>>,
+    roles: Vec<RotorRole>,
+    first_stepping: usize,
+    stepping: Arc<dyn SteppingStrategy<N>>,
+    observer: Option<Arc<dyn Observer>>,
 }
 
-impl RotorsController {
-    pub fn new(left: Rotor, middle: Rotor, right: Rotor) -> Self {
+impl<const N: usize> RotorsController<N> {
+    pub fn new(left: Rotor<N>, middle: Rotor<N>, right: Rotor<N>) -> Self {
+        Self::from_stack(vec![
+            (left, RotorRole::Stepping),
+            (middle, RotorRole::Stepping),
+            (right, RotorRole::Stepping),
+        ])
+    }
+
+    ///
+    /// Creates a stack from rotors and their roles, leftmost first. Fails when the stack has fewer
+    /// than three rotors or a stepping rotor sits left of a rotor that doesn't step.
+    ///
+    pub fn with_roles(rotors: Vec<(Rotor<N>, RotorRole)>) -> Result<Self, Error> {
+        let roles: Vec<RotorRole> = rotors.iter().map(|(_, role)| *role).collect();
+        if roles.len() < 3
+            || roles[first_stepping(&roles)..]
+                .iter()
+                .any(|&role| role != RotorRole::Stepping)
+        {
+            return Err(Error::InvalidRotorStack);
+        }
+
+        Ok(Self::from_stack(rotors))
+    }
+
+    /// Creates a stack that is valid by construction, like the fixed stacks of the crate's machines.
+    pub(crate) fn from_stack(rotors: Vec<(Rotor<N>, RotorRole)>) -> Self {
+        let (rotors, roles): (Vec<_>, Vec<_>) = rotors.into_iter().unzip();
+
         Self {
-            left,
-            middle,
-            right,
+            rotors,
+            first_stepping: first_stepping(&roles),
+            roles,
+            stepping: Arc::new(PawlAndNotch),
+            observer: None,
         }
     }
 
+    pub fn set_stepping_strategy(&mut self, stepping: Arc<dyn SteppingStrategy<N>>) {
+        self.stepping = stepping;
+        self.notify(SettingsChange::SteppingStrategy);
+    }
+
+    pub fn get_stepping_strategy(&self) -> &dyn SteppingStrategy<N> {
+        self.stepping.as_ref()
+    }
+
+    /// Reports rotor steps and settings changes to `observer` from now on.
+    pub fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.observer = Some(observer);
+    }
+
+    pub fn clear_observer(&mut self) -> Option<Arc<dyn Observer>> {
+        self.observer.take()
+    }
+
     pub fn increment_and_map(&mut self, letter: char) -> Result<char, Error> {
         self.increment();
 
         self.map_letter(letter)
     }
 
-    pub fn increment(&mut self) {
-        let increment_middle = self.right.increment();
-        if increment_middle {
-            let increment_left = self.middle.increment();
-            if increment_left {
-                self.left.increment();
-            }
+    /// Steps the rotors for a single key press and returns how many steps the reflector is carried.
+    pub fn increment(&mut self) -> usize {
+        match self.observer {
+            Some(_) => self.increment_traced().0,
+            None => self
+                .stepping
+                .step(&mut self.rotors[self.first_stepping..], &mut |_| {}),
         }
     }
 
-    pub fn increment_by(&mut self, amount: usize) {
-        let steps_to_increment_middle = self.right.increment_by(amount);
-        if steps_to_increment_middle > 0 {
-            let steps_to_increment_left = self.middle.increment_by(steps_to_increment_middle);
-
-            if steps_to_increment_left > 0 {
-                self.left.increment_by(steps_to_increment_left);
+    ///
+    /// Steps the rotors for a single key press like [`increment`](Self::increment), and also
+    /// returns the rotors the stepping strategy moved, leftmost first.
+    ///
+    pub(crate) fn increment_traced(&mut self) -> (usize, Vec<RotorStep>) {
+        let first_stepping = self.first_stepping;
+        let mut steps = Vec::new();
+        let reflector_steps = self
+            .stepping
+            .step(&mut self.rotors[first_stepping..], &mut |step| {
+                steps.push(step.shifted(first_stepping))
+            });
+        steps.sort_by_key(|step| step.rotor);
+
+        if let Some(observer) = &self.observer {
+            for step in &steps {
+                if let StepCause::Carry { from } = step.cause {
+                    observer.on_notch_carry(from, step.rotor);
+                }
+                observer.on_step(step.rotor, self.rotors[step.rotor].get_position());
             }
         }
+
+        (reflector_steps, steps)
+    }
+
+    /// Steps the rotors for `amount` key presses. With an observer set, every key press is reported.
+    pub fn increment_by(&mut self, amount: usize) -> usize {
+        match self.observer {
+            Some(_) => (0..amount).map(|_| self.increment()).sum(),
+            None => self
+                .stepping
+                .step_by(&mut self.rotors[self.first_stepping..], amount),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rotors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rotors.is_empty()
+    }
+
+    pub fn get_rotor(&self, index: usize) -> &Rotor<N> {
+        &self.rotors[index]
+    }
+
+    pub fn get_role(&self, index: usize) -> RotorRole {
+        self.roles[index]
+    }
+
+    /// Replaces the rotor at `index`, keeping the role of its slot.
+    pub fn set_rotor(&mut self, index: usize, rotor: Rotor<N>) -> Result<(), Error> {
+        *self.rotor_mut(index)? = rotor;
+        self.notify(SettingsChange::Rotor { index });
+        Ok(())
+    }
+
+    pub fn set_rotor_position_from_char(
+        &mut self,
+        index: usize,
+        letter: char,
+    ) -> Result<(), Error> {
+        self.rotor_mut(index)?.set_position(letter)?;
+        self.notify_position(index);
+        Ok(())
+    }
+
+    pub fn set_rotor_position_from_int(
+        &mut self,
+        index: usize,
+        position: usize,
+    ) -> Result<(), Error> {
+        self.rotor_mut(index)?.set_position_from_int(position)?;
+        self.notify_position(index);
+        Ok(())
+    }
+
+    pub fn set_rotor_ring_setting(
+        &mut self,
+        index: usize,
+        ring_setting: char,
+    ) -> Result<(), Error> {
+        self.rotor_mut(index)?.set_ring_setting(ring_setting)?;
+        self.notify_ring_setting(index);
+        Ok(())
+    }
+
+    ///
+    /// Sets the position of every rotor, leftmost first. Fails without changing any rotor unless
+    /// there is one valid position per rotor.
+    ///
+    pub fn set_positions(&mut self, positions: &[char]) -> Result<(), Error> {
+        self.set_all(positions, Rotor::set_position)?;
+        (0..self.rotors.len()).for_each(|index| self.notify_position(index));
+        Ok(())
+    }
+
+    pub fn get_positions(&self) -> Vec<char> {
+        self.rotors.iter().map(Rotor::get_position).collect()
+    }
+
+    ///
+    /// Sets the ring setting of every rotor, leftmost first. Fails without changing any rotor unless
+    /// there is one valid ring setting per rotor.
+    ///
+    pub fn set_ring_settings(&mut self, ring_settings: &[char]) -> Result<(), Error> {
+        self.set_all(ring_settings, Rotor::set_ring_setting)?;
+        (0..self.rotors.len()).for_each(|index| self.notify_ring_setting(index));
+        Ok(())
+    }
+
+    pub fn get_ring_settings(&self) -> Vec<char> {
+        self.rotors.iter().map(Rotor::get_ring_setting).collect()
+    }
+
+    pub fn set_left_rotor_position_from_char(&mut self, letter: char) -> Result<(), Error> {
+        self.set_rotor_position_from_char(self.left(), letter)
+    }
+
+    pub fn set_middle_rotor_position_from_char(&mut self, letter: char) -> Result<(), Error> {
+        self.set_rotor_position_from_char(self.middle(), letter)
+    }
+
+    pub fn set_right_rotor_position_from_char(&mut self, letter: char) -> Result<(), Error> {
+        self.set_rotor_position_from_char(self.right(), letter)
+    }
+
+    pub fn set_left_rotor_position_from_int(&mut self, letter: usize) -> Result<(), Error> {
+        self.set_rotor_position_from_int(self.left(), letter)
+    }
+
+    pub fn set_middle_rotor_position_from_int(&mut self, letter: usize) -> Result<(), Error> {
+        self.set_rotor_position_from_int(self.middle(), letter)
+    }
+
+    pub fn set_right_rotor_position_from_int(&mut self, letter: usize) -> Result<(), Error> {
+        self.set_rotor_position_from_int(self.right(), letter)
     }
 
-    pub fn set_left_rotor_position_from_char(&mut self, letter: char) {
-        self.left.set_position(letter);
+    pub fn set_left_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
+        self.set_rotor_ring_setting(self.left(), ring_setting)
     }
 
-    pub fn set_middle_rotor_position_from_char(&mut self, letter: char) {
-        self.middle.set_position(letter);
+    pub fn set_middle_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
+        self.set_rotor_ring_setting(self.middle(), ring_setting)
     }
 
-    pub fn set_right_rotor_position_from_char(&mut self, letter: char) {
-        self.right.set_position(letter);
+    pub fn set_right_rotor_ring_setting(&mut self, ring_setting: char) -> Result<(), Error> {
+        self.set_rotor_ring_setting(self.right(), ring_setting)
     }
 
-    pub fn set_left_rotor_position_from_int(&mut self, letter: usize) {
-        self.left.set_position_from_int(letter);
+    pub fn get_left_ring_setting(&self) -> char {
+        self.rotors[self.left()].get_ring_setting()
     }
 
-    pub fn set_middle_rotor_position_from_int(&mut self, letter: usize) {
-        self.middle.set_position_from_int(letter);
+    pub fn get_middle_ring_setting(&self) -> char {
+        self.rotors[self.middle()].get_ring_setting()
     }
 
-    pub fn set_right_rotor_position_from_int(&mut self, letter: usize) {
-        self.right.set_position_from_int(letter);
+    pub fn get_right_ring_setting(&self) -> char {
+        self.rotors[self.right()].get_ring_setting()
     }
 
-    pub fn set_right_rotor(&mut self, rotor: Rotor) {
-        self.right = rotor;
+    pub fn set_right_rotor(&mut self, rotor: Rotor<N>) {
+        let index = self.right();
+        self.rotors[index] = rotor;
+        self.notify(SettingsChange::Rotor { index });
     }
 
-    pub fn set_middle_rotor(&mut self, rotor: Rotor) {
-        self.middle = rotor;
+    pub fn set_middle_rotor(&mut self, rotor: Rotor<N>) {
+        let index = self.middle();
+        self.rotors[index] = rotor;
+        self.notify(SettingsChange::Rotor { index });
     }
 
-    pub fn set_left_rotor(&mut self, rotor: Rotor) {
-        self.left = rotor;
+    pub fn set_left_rotor(&mut self, rotor: Rotor<N>) {
+        let index = self.left();
+        self.rotors[index] = rotor;
+        self.notify(SettingsChange::Rotor { index });
     }
 
     pub fn get_left_position(&self) -> char {
-        self.left.get_position()
+        self.rotors[self.left()].get_position()
     }
 
     pub fn get_middle_position(&self) -> char {
-        self.middle.get_position()
+        self.rotors[self.middle()].get_position()
     }
 
     pub fn get_right_position(&self) -> char {
-        self.right.get_position()
+        self.rotors[self.right()].get_position()
     }
 
+    /// Maps a letter through every rotor, from the entry side on the right to the reflector.
     pub fn map_letter(&self, letter: char) -> Result<char, Error> {
-        let letter = self.map_char_from_right(letter)?;
-        let letter = self.map_char_from_middle(letter)?;
-        self.map_char_from_left(letter)
+        self.rotors
+            .iter()
+            .rev()
+            .try_fold(letter, |letter, rotor| rotor.map_letter(letter))
     }
 
     pub fn map_char_from_right(&self, letter: char) -> Result<char, Error> {
-        self.right.map_letter(letter)
+        self.rotors[self.right()].map_letter(letter)
     }
 
     pub fn map_char_from_middle(&self, letter: char) -> Result<char, Error> {
-        self.middle.map_letter(letter)
+        self.rotors[self.middle()].map_letter(letter)
     }
 
     pub fn map_char_from_left(&self, letter: char) -> Result<char, Error> {
-        self.left.map_letter(letter)
+        self.rotors[self.left()].map_letter(letter)
     }
 
+    /// Maps a letter back from the reflector through every rotor.
     pub fn inverse_map_letter(&self, letter: char) -> Result<char, Error> {
-        let letter = self.inverse_map_char_from_left(letter)?;
-        let letter = self.inverse_map_char_from_middle(letter)?;
-        self.inverse_map_char_from_right(letter)
+        self.rotors
+            .iter()
+            .try_fold(letter, |letter, rotor| rotor.inverse_map_letter(letter))
     }
 
     pub fn inverse_map_char_from_right(&self, letter: char) -> Result<char, Error> {
-        self.right.inverse_map_letter(letter)
+        self.rotors[self.right()].inverse_map_letter(letter)
     }
 
     pub fn inverse_map_char_from_middle(&self, letter: char) -> Result<char, Error> {
-        self.middle.inverse_map_letter(letter)
+        self.rotors[self.middle()].inverse_map_letter(letter)
     }
 
     pub fn inverse_map_char_from_left(&self, letter: char) -> Result<char, Error> {
-        self.left.inverse_map_letter(letter)
+        self.rotors[self.left()].inverse_map_letter(letter)
+    }
+
+    fn left(&self) -> usize {
+        self.rotors.len() - 3
+    }
+
+    fn middle(&self) -> usize {
+        self.rotors.len() - 2
+    }
+
+    fn right(&self) -> usize {
+        self.rotors.len() - 1
+    }
+
+    fn notify(&self, change: SettingsChange) {
+        if let Some(observer) = &self.observer {
+            observer.on_settings_change(change);
+        }
+    }
+
+    fn notify_position(&self, index: usize) {
+        self.notify(SettingsChange::RotorPosition {
+            index,
+            position: self.rotors[index].get_position(),
+        });
+    }
+
+    fn notify_ring_setting(&self, index: usize) {
+        self.notify(SettingsChange::RingSetting {
+            index,
+            ring_setting: self.rotors[index].get_ring_setting(),
+        });
+    }
+
+    fn rotor_mut(&mut self, index: usize) -> Result<&mut Rotor<N>, Error> {
+        let count = self.rotors.len();
+        self.rotors
+            .get_mut(index)
+            .ok_or(Error::NoSuchRotor { index, count })
+    }
+
+    /// Applies one setting per rotor, only keeping the changes when every setting is valid.
+    fn set_all(
+        &mut self,
+        settings: &[char],
+        set: fn(&mut Rotor<N>, char) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if settings.len() != self.rotors.len() {
+            return Err(Error::WrongSettingCount {
+                expected: self.rotors.len(),
+                got: settings.len(),
+            });
+        }
+
+        let mut rotors = self.rotors.clone();
+        for (rotor, &setting) in rotors.iter_mut().zip(settings) {
+            set(rotor, setting)?;
+        }
+        self.rotors = rotors;
+
+        Ok(())
     }
 }
 
+fn first_stepping(roles: &[RotorRole]) -> usize {
+    roles
+        .iter()
+        .position(|&role| role == RotorRole::Stepping)
+        .unwrap_or(roles.len())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{rotors, rotors_controller::RotorsController};
+    use crate::error::Error;
+    use crate::rotors;
+    use crate::rotors_controller::{RotorRole, RotorsController};
 
     #[test]
     fn increment_by_should_work() {
@@ -145,8 +432,8 @@ mod tests {
         let first_rotor_position_after_inc_by = controller.get_right_position();
         let second_rotor_position_after_inc_by = controller.get_middle_position();
 
-        controller.set_right_rotor_position_from_char('A');
-        controller.set_middle_rotor_position_from_char('A');
+        controller.set_right_rotor_position_from_char('A').unwrap();
+        controller.set_middle_rotor_position_from_char('A').unwrap();
 
         for _ in 0..INCREMENT_AMOUNT {
             controller.increment();
@@ -170,15 +457,15 @@ mod tests {
         let left = rotors::create_rotor_3();
         let mut controller = RotorsController::new(left, middle, right);
 
-        controller.set_right_rotor_position_from_char('R');
-        controller.set_middle_rotor_position_from_char('S');
+        controller.set_right_rotor_position_from_char('R').unwrap();
+        controller.set_middle_rotor_position_from_char('S').unwrap();
 
         const INCREMENT_AMOUNT: usize = 23;
         controller.increment_by(INCREMENT_AMOUNT);
         let second_rotor_position_after_inc_by = controller.get_middle_position();
 
-        controller.set_right_rotor_position_from_char('R');
-        controller.set_middle_rotor_position_from_char('S');
+        controller.set_right_rotor_position_from_char('R').unwrap();
+        controller.set_middle_rotor_position_from_char('S').unwrap();
 
         for _ in 0..INCREMENT_AMOUNT {
             controller.increment();
@@ -194,15 +481,15 @@ mod tests {
         let left = rotors::create_rotor_3();
         let mut controller = RotorsController::new(left, middle, right);
 
-        controller.set_right_rotor_position_from_char('Z');
-        controller.set_middle_rotor_position_from_char('S');
+        controller.set_right_rotor_position_from_char('Z').unwrap();
+        controller.set_middle_rotor_position_from_char('S').unwrap();
 
         const INCREMENT_AMOUNT: usize = 3;
         controller.increment_by(INCREMENT_AMOUNT);
         let first_rotor_position_after_inc_by = controller.get_right_position();
 
-        controller.set_right_rotor_position_from_char('Z');
-        controller.set_middle_rotor_position_from_char('S');
+        controller.set_right_rotor_position_from_char('Z').unwrap();
+        controller.set_middle_rotor_position_from_char('S').unwrap();
 
         for _ in 0..INCREMENT_AMOUNT {
             controller.increment();
@@ -213,4 +500,70 @@ mod tests {
             first_rotor_position_after_inc_by
         );
     }
+
+    #[test]
+    fn greek_wheel_should_not_step() {
+        let mut controller = RotorsController::with_roles(vec![
+            (rotors::create_rotor_4(), RotorRole::GreekWheel),
+            (rotors::create_rotor_1(), RotorRole::Stepping),
+            (rotors::create_rotor_2(), RotorRole::Stepping),
+            (rotors::create_rotor_3(), RotorRole::Stepping),
+        ])
+        .unwrap();
+        let mut three_rotors = RotorsController::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+        );
+        controller.set_positions(&['C', 'A', 'D', 'U']).unwrap();
+        three_rotors.set_positions(&['A', 'D', 'U']).unwrap();
+
+        controller.increment_by(5_000);
+        (0..5_000).for_each(|_| {
+            three_rotors.increment();
+        });
+
+        assert_eq!(controller.get_positions()[0], 'C');
+        assert_eq!(
+            controller.get_positions()[1..],
+            three_rotors.get_positions()
+        );
+    }
+
+    #[test]
+    fn stepping_rotor_left_of_a_stator_should_fail() {
+        let controller = RotorsController::with_roles(vec![
+            (rotors::create_rotor_1(), RotorRole::Stepping),
+            (rotors::create_rotor_2(), RotorRole::Stator),
+            (rotors::create_rotor_3(), RotorRole::Stepping),
+        ]);
+
+        assert_eq!(controller.err(), Some(Error::InvalidRotorStack));
+    }
+
+    #[test]
+    fn wrong_number_of_positions_should_leave_rotors_untouched() {
+        let mut controller = RotorsController::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+        );
+
+        assert_eq!(
+            controller.set_positions(&['B', 'C']),
+            Err(Error::WrongSettingCount {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(
+            controller.set_positions(&['B', 'C', '7']),
+            Err(Error::InvalidLetter('7'))
+        );
+        assert_eq!(controller.get_positions(), vec!['A', 'A', 'A']);
+        assert_eq!(
+            controller.set_rotor_position_from_int(3, 0),
+            Err(Error::NoSuchRotor { index: 3, count: 3 })
+        );
+    }
 }
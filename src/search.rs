@@ -0,0 +1,414 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::thread;
+
+use crate::Enigma;
+use crate::consts::{ALPHABET_SIZE, FIRST_LETTER};
+use crate::error::Error;
+use crate::rotor::RotorType;
+use crate::rotors;
+
+const POSITIONS_PER_SETTING: usize = ALPHABET_SIZE * ALPHABET_SIZE * ALPHABET_SIZE;
+
+///
+/// The part of the key space to search. Every wheel order is tried with every ring setting and all
+/// 26^3 start positions. The reflector and plugboard are taken from the machine handed to [`search`].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSpace {
+    pub wheel_orders: Vec<[RotorType; 3]>,
+    pub ring_settings: Vec<[char; 3]>,
+}
+
+impl SearchSpace {
+    /// Every ordered choice of three distinct rotors out of `rotor_types`, with ring setting AAA.
+    pub fn new(rotor_types: &[RotorType]) -> Self {
+        let mut wheel_orders = Vec::new();
+        for &left in rotor_types {
+            for &middle in rotor_types.iter().filter(|&&middle| middle != left) {
+                for &right in rotor_types
+                    .iter()
+                    .filter(|&&right| right != left && right != middle)
+                {
+                    wheel_orders.push([left, middle, right]);
+                }
+            }
+        }
+
+        Self {
+            wheel_orders,
+            ring_settings: vec![['A'; 3]],
+        }
+    }
+
+    pub fn with_ring_settings(mut self, ring_settings: Vec<[char; 3]>) -> Self {
+        self.ring_settings = ring_settings;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.wheel_orders.len() * self.ring_settings.len() * POSITIONS_PER_SETTING
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn setting(&self, index: usize) -> ([RotorType; 3], [char; 3], [usize; 3]) {
+        let position = index % POSITIONS_PER_SETTING;
+        let setting = index / POSITIONS_PER_SETTING;
+
+        (
+            self.wheel_orders[setting / self.ring_settings.len()],
+            self.ring_settings[setting % self.ring_settings.len()],
+            [
+                position / (ALPHABET_SIZE * ALPHABET_SIZE),
+                (position / ALPHABET_SIZE) % ALPHABET_SIZE,
+                position % ALPHABET_SIZE,
+            ],
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub threads: usize,
+    pub top_k: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            top_k: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub wheel_order: [RotorType; 3],
+    pub ring_settings: [char; 3],
+    pub position: [char; 3],
+    pub score: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    /// Higher scores rank first; ties go to the candidate that comes first in the search space.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .score
+            .total_cmp(&self.score)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+///
+/// Scores every setting of `space` and returns the `top_k` best, best first.
+///
+/// The key space is split into contiguous ranges, one per worker thread. Each worker clones `base`
+/// once, without its observer, and reconfigures that clone for every candidate, so the plugboard
+/// and reflector of `base` apply throughout. The scorer receives the machine set to the
+/// candidate's start position, with the reflector at the position of `base`'s, and may encrypt
+/// with it freely. Ties are broken by the order of the space, so the result doesn't depend on the
+/// number of threads. Fails when a ring setting of the space isn't a letter.
+///
+pub fn search<F>(
+    base: &Enigma,
+    space: &SearchSpace,
+    options: SearchOptions,
+    scorer: F,
+) -> Result<Vec<Candidate>, Error>
+where
+    F: Fn(&mut Enigma) -> f64 + Sync,
+{
+    let threads = options.threads.max(1);
+    let chunk_size = space.len().div_ceil(threads).max(1);
+
+    let ranges: Vec<Vec<Candidate>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..space.len())
+            .step_by(chunk_size)
+            .map(|start| {
+                let end = (start + chunk_size).min(space.len());
+                let scorer = &scorer;
+                scope.spawn(move || search_range(base, space, start..end, options.top_k, scorer))
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Result<_, _>>()
+    })?;
+
+    let mut candidates: Vec<Candidate> = ranges.into_iter().flatten().collect();
+
+    candidates.sort();
+    candidates.truncate(options.top_k);
+    Ok(candidates)
+}
+
+fn search_range<F>(
+    base: &Enigma,
+    space: &SearchSpace,
+    range: std::ops::Range<usize>,
+    top_k: usize,
+    scorer: &F,
+) -> Result<Vec<Candidate>, Error>
+where
+    F: Fn(&mut Enigma) -> f64,
+{
+    let mut enigma = base.clone();
+    enigma.clear_observer();
+    let reflector_position = base.get_reflector_position();
+    let mut current_wheel_order = None;
+    let mut best: BinaryHeap<Candidate> = BinaryHeap::with_capacity(top_k + 1);
+
+    for index in range {
+        let (wheel_order, ring_settings, position) = space.setting(index);
+
+        if current_wheel_order != Some(wheel_order) {
+            enigma.set_left_rotor(rotors::create_rotor(wheel_order[0])?);
+            enigma.set_middle_rotor(rotors::create_rotor(wheel_order[1])?);
+            enigma.set_right_rotor(rotors::create_rotor(wheel_order[2])?);
+            current_wheel_order = Some(wheel_order);
+        }
+
+        enigma.set_left_rotor_ring_setting(ring_settings[0])?;
+        enigma.set_middle_rotor_ring_setting(ring_settings[1])?;
+        enigma.set_right_rotor_ring_setting(ring_settings[2])?;
+        enigma.set_left_rotor_position_from_int(position[0])?;
+        enigma.set_middle_rotor_position_from_int(position[1])?;
+        enigma.set_right_rotor_position_from_int(position[2])?;
+        // A driven reflector is left wherever the previous candidate's scoring moved it.
+        enigma.set_reflector_position(reflector_position)?;
+
+        let candidate = Candidate {
+            wheel_order,
+            ring_settings,
+            position: position.map(|position| (position as u8 + FIRST_LETTER as u8) as char),
+            score: scorer(&mut enigma),
+            index,
+        };
+
+        // The heap's top is the worst kept candidate, so it is the one evicted.
+        best.push(candidate);
+        if best.len() > top_k {
+            best.pop();
+        }
+    }
+
+    Ok(best.into_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::models::MachineModel;
+    use crate::observer::Observer;
+    use crate::reflectors;
+
+    const CRIB: &str = "WETTERBERICHT";
+
+    fn crib_scorer(ciphertext: String) -> impl Fn(&mut Enigma) -> f64 + Sync {
+        move |enigma| {
+            enigma
+                .encrypt_str(&ciphertext)
+                .unwrap()
+                .chars()
+                .zip(CRIB.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        }
+    }
+
+    fn intercepted_ciphertext() -> String {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_4(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_5(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_transposition('Q', 'Z').unwrap();
+        enigma.set_right_rotor_ring_setting('C').unwrap();
+        enigma.set_left_rotor_position_from_char('G').unwrap();
+        enigma.set_middle_rotor_position_from_char('O').unwrap();
+        enigma.set_right_rotor_position_from_char('D').unwrap();
+
+        enigma.encrypt_str(CRIB).unwrap()
+    }
+
+    fn base_machine() -> Enigma {
+        let mut enigma = Enigma::new(
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            reflectors::create_reflector_b(),
+        );
+        enigma.set_transposition('Q', 'Z').unwrap();
+        enigma
+    }
+
+    #[test]
+    fn search_space_should_cover_sixty_wheel_orders() {
+        let space = SearchSpace::new(&[
+            RotorType::Rotor1,
+            RotorType::Rotor2,
+            RotorType::Rotor3,
+            RotorType::Rotor4,
+            RotorType::Rotor5,
+        ]);
+
+        assert_eq!(space.wheel_orders.len(), 60);
+        assert_eq!(space.len(), 60 * 17_576);
+    }
+
+    #[test]
+    fn search_should_find_setting() {
+        let space = SearchSpace {
+            wheel_orders: vec![
+                [RotorType::Rotor4, RotorType::Rotor2, RotorType::Rotor5],
+                [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor5],
+            ],
+            ring_settings: vec![['A', 'A', 'A'], ['A', 'A', 'C']],
+        };
+        let options = SearchOptions {
+            threads: 4,
+            top_k: 3,
+        };
+
+        let candidates = search(
+            &base_machine(),
+            &space,
+            options,
+            crib_scorer(intercepted_ciphertext()),
+        )
+        .unwrap();
+
+        // A ring setting of C at position D enciphers like ring setting A at position B as long as
+        // the right rotor doesn't reach its notch, so both score perfectly.
+        assert_eq!(candidates.len(), 3);
+        assert!(candidates.iter().any(|candidate| {
+            candidate.wheel_order == [RotorType::Rotor4, RotorType::Rotor2, RotorType::Rotor5]
+                && candidate.ring_settings == ['A', 'A', 'C']
+                && candidate.position == ['G', 'O', 'D']
+                && candidate.score == CRIB.len() as f64
+        }));
+        assert_eq!(candidates[0].score, CRIB.len() as f64);
+    }
+
+    #[test]
+    fn search_result_should_not_depend_on_thread_count() {
+        let space = SearchSpace {
+            wheel_orders: vec![[RotorType::Rotor3, RotorType::Rotor1, RotorType::Rotor2]],
+            ring_settings: vec![['A', 'A', 'A']],
+        };
+        let scorer = crib_scorer(intercepted_ciphertext());
+
+        let single = search(
+            &base_machine(),
+            &space,
+            SearchOptions {
+                threads: 1,
+                top_k: 20,
+            },
+            &scorer,
+        )
+        .unwrap();
+        let parallel = search(
+            &base_machine(),
+            &space,
+            SearchOptions {
+                threads: 7,
+                top_k: 20,
+            },
+            &scorer,
+        )
+        .unwrap();
+
+        assert_eq!(single, parallel);
+    }
+
+    #[test]
+    fn search_on_a_driven_reflector_should_not_depend_on_thread_count() {
+        let mut base = MachineModel::AbwehrG
+            .create(
+                [
+                    RotorType::AbwehrG1,
+                    RotorType::AbwehrG2,
+                    RotorType::AbwehrG3,
+                ],
+                reflectors::ReflectorType::ReflectorAbwehrG,
+            )
+            .unwrap();
+        base.set_reflector_position('K').unwrap();
+        let space = SearchSpace {
+            wheel_orders: vec![[
+                RotorType::AbwehrG1,
+                RotorType::AbwehrG2,
+                RotorType::AbwehrG3,
+            ]],
+            ring_settings: vec![['A', 'A', 'A']],
+        };
+        let scorer = |enigma: &mut Enigma| {
+            let text = enigma.encrypt_str(&"A".repeat(200)).unwrap();
+            text.chars().filter(|&letter| letter == 'E').count() as f64
+        };
+
+        let results: Vec<Vec<Candidate>> = [1, 3, 7]
+            .map(|threads| {
+                search(&base, &space, SearchOptions { threads, top_k: 20 }, scorer).unwrap()
+            })
+            .into();
+
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0], results[2]);
+    }
+
+    #[derive(Debug, Default)]
+    struct KeypressCounter(AtomicUsize);
+
+    impl Observer for KeypressCounter {
+        fn on_keypress(&self, _input: char, _output: char) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn search_should_leave_the_observer_of_the_base_machine_untouched() {
+        let space = SearchSpace {
+            wheel_orders: vec![[RotorType::Rotor3, RotorType::Rotor1, RotorType::Rotor2]],
+            ring_settings: vec![['A', 'A', 'A']],
+        };
+        let counter = Arc::new(KeypressCounter::default());
+        let mut base = base_machine();
+        base.set_observer(counter.clone());
+
+        search(
+            &base,
+            &space,
+            SearchOptions {
+                threads: 2,
+                top_k: 1,
+            },
+            crib_scorer(intercepted_ciphertext()),
+        )
+        .unwrap();
+
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+        base.encrypt_char('A').unwrap();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,451 @@
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::Enigma;
+use crate::alphabet::LATIN;
+use crate::catalogue::{CUSTOM, CatalogueError};
+use crate::config::{self, ConfigError, ParseErrorKind};
+use crate::entry_wheels::{self, EntryWheelType};
+use crate::error::Error;
+use crate::models::{MachineModel, ModelError};
+use crate::reflectors::{self, Reflector, ReflectorType};
+use crate::rotor::{Orientation, Rotor, RotorType};
+use crate::rotors;
+use crate::rotors_controller::RotorRole;
+use crate::stepping;
+
+///
+/// Parts and models are written by their names in the [`catalogue`](crate::catalogue), so they read
+/// the same as in keys and configurations. `custom` names the variant for custom parts, which the
+/// catalogue can't parse.
+///
+macro_rules! by_name {
+    ($($typ:ty => $custom:expr),* $(,)?) => {$(
+        impl Serialize for $typ {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $typ {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                let custom: Option<$typ> = $custom;
+                match custom {
+                    Some(custom) if name.eq_ignore_ascii_case(CUSTOM) => Ok(custom),
+                    _ => name.parse().map_err(de::Error::custom),
+                }
+            }
+        }
+    )*};
+}
+
+by_name! {
+    RotorType => Some(RotorType::Custom),
+    ReflectorType => Some(ReflectorType::Custom),
+    EntryWheelType => None,
+    MachineModel => None,
+}
+
+///
+/// A rotor as it is written, using the keys of the configuration format. Historical rotors are
+/// given by their `type`, custom ones by their `wiring` and `step-positions`.
+///
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RotorSettings {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    typ: Option<RotorType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wiring: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step_positions: Option<String>,
+    /// Only given for the rotors of a machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<RotorRole>,
+    #[serde(default = "first_letter")]
+    ring: char,
+    #[serde(default = "first_letter")]
+    position: char,
+    #[serde(default)]
+    orientation: Orientation,
+    #[serde(default)]
+    core: Orientation,
+}
+
+impl RotorSettings {
+    fn of(rotor: &Rotor, role: Option<RotorRole>) -> Self {
+        let typ = rotor.get_props().get_type();
+        let custom = typ == RotorType::Custom;
+
+        Self {
+            typ: (!custom).then_some(typ),
+            wiring: custom.then(|| rotor.get_props().get_wiring()),
+            step_positions: custom.then(|| config::forward_step_positions(rotor)),
+            role,
+            ring: rotor.get_ring_setting(),
+            position: rotor.get_position(),
+            orientation: rotor.get_orientation(),
+            core: config::core_orientation(rotor),
+        }
+    }
+
+    fn build(self) -> Result<Rotor, ParseErrorKind> {
+        let mut rotor = match (self.typ, self.wiring) {
+            (Some(_), Some(_)) => return Err(ParseErrorKind::ConflictingKeys("type", "wiring")),
+            (None, Some(wiring)) => rotors::create_custom_rotor(
+                &wiring,
+                self.step_positions.as_deref().unwrap_or_default(),
+            )
+            .map_err(ParseErrorKind::Wiring)?,
+            (Some(_), None) if self.step_positions.is_some() => {
+                return Err(ParseErrorKind::ConflictingKeys("type", "step-positions"));
+            }
+            (None | Some(RotorType::Custom), None) => {
+                return Err(ParseErrorKind::MissingKey("wiring"));
+            }
+            (Some(typ), None) => rotors::create_rotor(typ).map_err(|_| {
+                ParseErrorKind::Catalogue(CatalogueError::NumericPart(typ.to_string()))
+            })?,
+        };
+
+        if self.orientation == Orientation::Reversed {
+            rotor = rotor.reversed();
+        }
+        if self.core == Orientation::Reversed {
+            rotor.set_core_reversed(!rotor.is_core_reversed());
+        }
+        set_letter(self.ring, |letter| rotor.set_ring_setting(letter))?;
+        set_letter(self.position, |letter| rotor.set_position(letter))?;
+
+        Ok(rotor)
+    }
+}
+
+impl Serialize for Rotor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RotorSettings::of(self, None).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rotor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let settings = RotorSettings::deserialize(deserializer)?;
+        if settings.role.is_some() {
+            return Err(de::Error::custom(
+                "a role is only given for the rotors of a machine",
+            ));
+        }
+
+        settings.build().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ReflectorSettings {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    typ: Option<ReflectorType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wiring: Option<String>,
+    #[serde(default = "first_letter")]
+    position: char,
+}
+
+impl ReflectorSettings {
+    fn of(reflector: &Reflector) -> Self {
+        let custom = reflector.typ == ReflectorType::Custom;
+
+        Self {
+            typ: (!custom).then_some(reflector.typ),
+            wiring: custom.then(|| reflector.get_wiring()),
+            position: reflector.get_position(),
+        }
+    }
+
+    fn build(self) -> Result<Reflector, ParseErrorKind> {
+        let mut reflector = match (self.typ, self.wiring) {
+            (Some(_), Some(_)) => return Err(ParseErrorKind::ConflictingKeys("type", "wiring")),
+            (None, Some(wiring)) => {
+                reflectors::create_custom_reflector(&wiring).map_err(ParseErrorKind::Wiring)?
+            }
+            (None | Some(ReflectorType::Custom), None) => {
+                return Err(ParseErrorKind::MissingKey("wiring"));
+            }
+            (Some(typ), None) => reflectors::create_reflector(typ).map_err(|_| {
+                ParseErrorKind::Catalogue(CatalogueError::NumericPart(typ.to_string()))
+            })?,
+        };
+        set_letter(self.position, |letter| reflector.set_position(letter))?;
+
+        Ok(reflector)
+    }
+}
+
+impl Serialize for Reflector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ReflectorSettings::of(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Reflector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ReflectorSettings::deserialize(deserializer)?
+            .build()
+            .map_err(de::Error::custom)
+    }
+}
+
+///
+/// A machine as it is written. Like [`config::load`], a `model` supplies the entry wheel and
+/// stepping and checks the parts against it. The plugboard is written as its cables, e.g.
+/// "AD CN ET".
+///
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MachineSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<MachineModel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry_wheel: Option<EntryWheelType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stepping: Option<String>,
+    reflector: ReflectorSettings,
+    #[serde(default)]
+    plugboard: String,
+    /// Leftmost first.
+    rotors: Vec<RotorSettings>,
+}
+
+impl MachineSettings {
+    fn of(enigma: &Enigma) -> Result<Self, ConfigError> {
+        let stepping = enigma
+            .get_stepping_strategy()
+            .config_name()
+            .ok_or(ConfigError::CustomStepping)?;
+        let controller = &enigma.rotor_controller;
+
+        Ok(Self {
+            model: enigma.get_model(),
+            entry_wheel: Some(enigma.entry_wheel.typ),
+            stepping: Some(stepping.to_string()),
+            reflector: ReflectorSettings::of(&enigma.reflector),
+            plugboard: config::plugboard_text(&enigma.transpositions),
+            rotors: (0..controller.len())
+                .map(|index| {
+                    RotorSettings::of(
+                        controller.get_rotor(index),
+                        Some(controller.get_role(index)),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    fn build(self) -> Result<Enigma, ParseErrorKind> {
+        if self
+            .model
+            .is_some_and(|model| model.alphabet_size() != LATIN.size())
+        {
+            return Err(ParseErrorKind::Model(ModelError::WrongAlphabet));
+        }
+
+        let entry_wheel = self
+            .entry_wheel
+            .or(self.model.map(|model| model.entry_wheel()))
+            .unwrap_or(EntryWheelType::Identity);
+        let stepping = match self.stepping {
+            Some(name) => {
+                stepping::from_config_name(&name).ok_or(ParseErrorKind::InvalidValue(name))?
+            }
+            None => config::default_stepping(self.model),
+        };
+        let reflector = self.reflector.build()?;
+        let plugboard = config::parse_plugboard(&self.plugboard)?;
+
+        let rotors = self
+            .rotors
+            .into_iter()
+            .map(|settings| {
+                let role = settings.role.unwrap_or(RotorRole::Stepping);
+                Ok((settings.build()?, role))
+            })
+            .collect::<Result<Vec<_>, ParseErrorKind>>()?;
+        if let Some(model) = self.model {
+            let types: Vec<RotorType> = rotors
+                .iter()
+                .map(|(rotor, _)| rotor.get_props().get_type())
+                .collect();
+            model
+                .validate(&types, reflector.typ)
+                .map_err(ParseErrorKind::Model)?;
+        }
+
+        let mut enigma = Enigma::with_rotor_stack(
+            rotors,
+            reflector,
+            entry_wheels::create_entry_wheel(entry_wheel),
+        )
+        .map_err(|_| ParseErrorKind::InvalidRotorStack)?;
+        enigma.rotor_controller.set_stepping_strategy(stepping);
+        enigma.model = self.model;
+        enigma.transpositions = plugboard;
+
+        Ok(enigma)
+    }
+}
+
+///
+/// Writes the complete state of the machine, like [`config::save`]. Fails for machines with a
+/// custom stepping strategy, which can't be written down. The observer isn't part of the state.
+///
+impl Serialize for Enigma {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MachineSettings::of(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Enigma {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MachineSettings::deserialize(deserializer)?
+            .build()
+            .map_err(de::Error::custom)
+    }
+}
+
+fn first_letter() -> char {
+    LATIN.symbol(0)
+}
+
+fn set_letter(
+    letter: char,
+    set: impl FnOnce(char) -> Result<(), Error>,
+) -> Result<(), ParseErrorKind> {
+    set(letter).map_err(|_| ParseErrorKind::InvalidValue(letter.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enigma_i() -> Enigma {
+        let mut enigma = MachineModel::EnigmaI
+            .create(
+                [RotorType::Rotor1, RotorType::Rotor2, RotorType::Rotor3],
+                ReflectorType::ReflectorB,
+            )
+            .unwrap();
+        enigma.set_ring_settings(&['B', 'U', 'L']).unwrap();
+        enigma.set_rotor_positions(&['A', 'X', 'E']).unwrap();
+        enigma.set_transposition('A', 'D').unwrap();
+        enigma.set_transposition('C', 'N').unwrap();
+        enigma
+    }
+
+    #[test]
+    fn parts_should_be_written_by_name() {
+        assert_eq!(
+            serde_json::to_string(&RotorType::Beta).unwrap(),
+            r#""Beta""#
+        );
+        assert_eq!(
+            serde_json::from_str::<ReflectorType>(r#""b-thin""#).unwrap(),
+            ReflectorType::ReflectorBThin
+        );
+        assert!(serde_json::from_str::<RotorType>(r#""IX""#).is_err());
+
+        let mut rotor = rotors::create_rotor_4();
+        rotor.set_position('Q').unwrap();
+        assert_eq!(
+            serde_json::to_string(&rotor).unwrap(),
+            r#"{"type":"IV","ring":"A","position":"Q","orientation":"forward","core":"forward"}"#
+        );
+    }
+
+    #[test]
+    fn machine_should_round_trip_mid_message() {
+        let mut enigma = enigma_i();
+        enigma.encrypt_str("WETTERBERICHT").unwrap();
+
+        let json = serde_json::to_string(&enigma).unwrap();
+        let mut restored: Enigma = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.get_model(), Some(MachineModel::EnigmaI));
+        assert_eq!(
+            restored.encrypt_str("FUNKSPRUCH").unwrap(),
+            enigma.encrypt_str("FUNKSPRUCH").unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_parts_should_round_trip() {
+        let rotor = rotors::create_custom_rotor("BDFHJLCPRTXVZNYEIWGAKMUSQO", "QZ")
+            .unwrap()
+            .reversed();
+        let reflector = reflectors::create_custom_reflector("YRUHQSLDPXNGOKMIEBFZCWVJAT").unwrap();
+
+        let restored_rotor: Rotor =
+            serde_json::from_str(&serde_json::to_string(&rotor).unwrap()).unwrap();
+        let restored_reflector: Reflector =
+            serde_json::from_str(&serde_json::to_string(&reflector).unwrap()).unwrap();
+
+        assert_eq!(
+            restored_rotor.get_props().get_wiring(),
+            rotor.get_props().get_wiring()
+        );
+        assert_eq!(
+            restored_rotor.get_props().get_step_positions(),
+            rotor.get_props().get_step_positions()
+        );
+        assert_eq!(restored_rotor.get_orientation(), Orientation::Reversed);
+        assert_eq!(restored_reflector.get_wiring(), reflector.get_wiring());
+    }
+
+    #[test]
+    fn invalid_settings_should_be_rejected_with_their_reason() {
+        let error = |json: &str| {
+            serde_json::from_str::<Enigma>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        let rotors = r#"[{"type": "I"}, {"type": "II"}, {"type": "III"}]"#;
+
+        assert!(
+            error(&format!(
+                r#"{{"reflector": {{"wiring": "ABCDEFGHIJKLMNOPQRSTUVWXYZ"}}, "rotors": {rotors}}}"#
+            ))
+            .starts_with("'A' is wired to itself")
+        );
+        assert!(
+            serde_json::from_str::<Rotor>(r#"{"wiring": "AACDEFGHIJKLMNOPQRSTUVWXYZ"}"#)
+                .unwrap_err()
+                .to_string()
+                .starts_with("'A' is wired twice")
+        );
+        assert!(
+            error(&format!(
+                r#"{{"reflector": {{"type": "B"}}, "plugboard": "AB BC", "rotors": {rotors}}}"#
+            ))
+            .starts_with("invalid value 'BC'")
+        );
+        assert!(
+            error(&format!(
+                r#"{{"model": "M3", "reflector": {{"type": "A"}}, "rotors": {rotors}}}"#
+            ))
+            .starts_with("reflector UKW-A doesn't fit this model")
+        );
+        assert!(
+            error(r#"{"reflector": {"type": "B"}, "rotors": [{"type": "I"}]}"#)
+                .starts_with("a machine needs at least 3 rotors")
+        );
+        assert!(
+            error(&format!(
+                r#"{{"reflector": {{"type": "B"}}, "rotors": {rotors}, "plugs": ""}}"#
+            ))
+            .starts_with("unknown field `plugs`")
+        );
+    }
+}
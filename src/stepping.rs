@@ -0,0 +1,250 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::consts::ALPHABET_SIZE;
+use crate::rotor::Rotor;
+use crate::trace::{RotorStep, StepCause};
+
+///
+/// Decides how the rotors move on every key press.
+///
+/// Strategies receive the stepping rotors of the stack, leftmost first - greek wheels and stators
+/// never move and are left out. Implementations return how many times the carry out of the leftmost
+/// rotor reaches the reflector. Machines with a fixed reflector simply return 0.
+///
+pub trait SteppingStrategy<const N: usize = ALPHABET_SIZE>: Debug + Send + Sync {
+    ///
+    /// Steps the rotors for a single key press. Every rotor that moves is passed to `report` along
+    /// with the reason it moved, rotor indices counting from the leftmost stepping rotor.
+    ///
+    fn step(&self, rotors: &mut [Rotor<N>], report: &mut dyn FnMut(RotorStep)) -> usize;
+
+    /// Steps as if `amount` keys were pressed. Override when a faster equivalent exists.
+    fn step_by(&self, rotors: &mut [Rotor<N>], amount: usize) -> usize {
+        (0..amount).map(|_| self.step(rotors, &mut |_| {})).sum()
+    }
+
+    /// The name the strategy is saved under in machine configurations, if it can be restored.
+    fn config_name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Creates a built-in strategy from its [`SteppingStrategy::config_name`].
+pub fn from_config_name<const N: usize>(name: &str) -> Option<Arc<dyn SteppingStrategy<N>>> {
+    match name {
+        PAWL_AND_NOTCH => Some(Arc::new(PawlAndNotch)),
+        COGWHEEL => Some(Arc::new(Cogwheel)),
+        DOUBLE_STEP => Some(Arc::new(DoubleStep)),
+        _ => None,
+    }
+}
+
+const PAWL_AND_NOTCH: &str = "pawl-and-notch";
+const COGWHEEL: &str = "cogwheel";
+const DOUBLE_STEP: &str = "double-step";
+
+///
+/// A simplified pawl-and-notch model that carries like an odometer: the right rotor steps on every
+/// key press and carries the rotor to its left along when it steps onto a step position, which in
+/// turn carries the next one. It leaves out the double step of the real machines, see
+/// [`DoubleStep`] for that; this is the default for machines assembled by hand.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PawlAndNotch;
+
+impl<const N: usize> SteppingStrategy<N> for PawlAndNotch {
+    fn step(&self, rotors: &mut [Rotor<N>], report: &mut dyn FnMut(RotorStep)) -> usize {
+        step_like_an_odometer(rotors, report);
+
+        0
+    }
+
+    fn step_by(&self, rotors: &mut [Rotor<N>], amount: usize) -> usize {
+        rotors
+            .iter_mut()
+            .rev()
+            .fold(amount, |steps, rotor| rotor.increment_by(steps));
+
+        0
+    }
+
+    fn config_name(&self) -> Option<&'static str> {
+        Some(PAWL_AND_NOTCH)
+    }
+}
+
+///
+/// The pawls and ratchets of the lever-stepped Enigmas and the Typex, double step included. A pawl
+/// sits left of every stepping rotor. The rightmost one steps the right rotor on every key press;
+/// the others only catch when the notch of the rotor to their right is in front of them, and then
+/// push both rotors. So when the middle rotor reaches its notch, it steps again on the next key
+/// press together with the left rotor, and the machine skips a middle rotor position every turn.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DoubleStep;
+
+impl<const N: usize> SteppingStrategy<N> for DoubleStep {
+    fn step(&self, rotors: &mut [Rotor<N>], report: &mut dyn FnMut(RotorStep)) -> usize {
+        let last = rotors.len().saturating_sub(1);
+        // Rotors are decided left to right, so the rotor to the right hasn't moved yet.
+        for index in 0..rotors.len() {
+            let cause = if index == last {
+                StepCause::KeyPress
+            } else if rotors[index + 1].is_at_notch() {
+                StepCause::Carry { from: index + 1 }
+            } else if index > 0 && rotors[index].is_at_notch() {
+                StepCause::DoubleStep
+            } else {
+                continue;
+            };
+            rotors[index].increment();
+            report(RotorStep {
+                rotor: index,
+                cause,
+            });
+        }
+
+        0
+    }
+
+    fn config_name(&self) -> Option<&'static str> {
+        Some(DOUBLE_STEP)
+    }
+}
+
+///
+/// The gear-driven stepping of the Abwehr Enigma G. Every rotor drives the next one through its
+/// notches like an odometer, without a double step, and the left rotor drives the reflector.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cogwheel;
+
+impl<const N: usize> SteppingStrategy<N> for Cogwheel {
+    fn step(&self, rotors: &mut [Rotor<N>], report: &mut dyn FnMut(RotorStep)) -> usize {
+        usize::from(step_like_an_odometer(rotors, report))
+    }
+
+    fn step_by(&self, rotors: &mut [Rotor<N>], amount: usize) -> usize {
+        rotors
+            .iter_mut()
+            .rev()
+            .fold(amount, |steps, rotor| rotor.increment_by(steps))
+    }
+
+    fn config_name(&self) -> Option<&'static str> {
+        Some(COGWHEEL)
+    }
+}
+
+/// Steps the right rotor and carries leftwards. Returns whether the leftmost rotor carried out.
+fn step_like_an_odometer<const N: usize>(
+    rotors: &mut [Rotor<N>],
+    report: &mut dyn FnMut(RotorStep),
+) -> bool {
+    let count = rotors.len();
+    for (index, rotor) in rotors.iter_mut().enumerate().rev() {
+        let carries = rotor.increment();
+        report(RotorStep {
+            rotor: index,
+            cause: match index + 1 == count {
+                true => StepCause::KeyPress,
+                false => StepCause::Carry { from: index + 1 },
+            },
+        });
+        if !carries {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotors;
+
+    #[test]
+    fn pawl_and_notch_should_never_step_reflector() {
+        let mut rotors = [
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+        ];
+
+        let reflector_steps: usize = (0..20_000)
+            .map(|_| PawlAndNotch.step(&mut rotors, &mut |_| {}))
+            .sum();
+
+        assert_eq!(reflector_steps, 0);
+    }
+
+    #[test]
+    fn cogwheel_step_by_should_match_single_steps() {
+        let mut rotors = [
+            rotors::create_rotor_abwehr_g_1(),
+            rotors::create_rotor_abwehr_g_2(),
+            rotors::create_rotor_abwehr_g_3(),
+        ];
+        let mut copies = rotors.clone();
+
+        let reflector_steps: usize = (0..1_000)
+            .map(|_| Cogwheel.step(&mut rotors, &mut |_| {}))
+            .sum();
+        let reflector_steps_by = Cogwheel.step_by(&mut copies, 1_000);
+
+        assert!(reflector_steps > 0);
+        assert_eq!(reflector_steps, reflector_steps_by);
+        assert_eq!(
+            rotors.map(|rotor| rotor.get_position()),
+            copies.map(|rotor| rotor.get_position())
+        );
+    }
+
+    #[test]
+    fn double_step_should_step_the_middle_rotor_twice() {
+        let mut rotors = [
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+        ];
+        for (rotor, position) in rotors.iter_mut().zip(['A', 'D', 'U']) {
+            rotor.set_position(position).unwrap();
+        }
+
+        let positions: Vec<String> = (0..3)
+            .map(|_| {
+                DoubleStep.step(&mut rotors, &mut |_| {});
+                rotors.iter().map(|rotor| rotor.get_position()).collect()
+            })
+            .collect();
+
+        assert_eq!(positions, ["ADV", "AEW", "BFX"]);
+    }
+
+    #[test]
+    fn pawl_and_notch_should_carry_through_any_number_of_rotors() {
+        let mut rotors = [
+            rotors::create_rotor_1(),
+            rotors::create_rotor_2(),
+            rotors::create_rotor_3(),
+            rotors::create_rotor_4(),
+        ];
+        let mut copies = rotors.clone();
+
+        (0..26 * 26 * 26).for_each(|_| {
+            PawlAndNotch.step(&mut rotors, &mut |_| {});
+        });
+        PawlAndNotch.step_by(&mut copies, 26 * 26 * 26);
+
+        assert_eq!(
+            rotors.map(|rotor| rotor.get_position()),
+            ['B', 'A', 'A', 'A']
+        );
+        assert_eq!(
+            copies.map(|rotor| rotor.get_position()),
+            ['B', 'A', 'A', 'A']
+        );
+    }
+}
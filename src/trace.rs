@@ -0,0 +1,75 @@
+/// A point along the signal path of a single key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    PlugboardIn,
+    EntryWheelIn,
+    /// The rotor at this index, counting from the leftmost rotor, on the way to the reflector.
+    RotorForward(usize),
+    Reflector,
+    /// The rotor at this index on the way back from the reflector.
+    RotorBackward(usize),
+    EntryWheelOut,
+    PlugboardOut,
+}
+
+/// Why a rotor moved on a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StepCause {
+    /// The rightmost stepping rotor moves on every key press.
+    KeyPress,
+    /// The rotor at this index stepped onto one of its step positions and carried this one along.
+    Carry { from: usize },
+    ///
+    /// The rotor stood at its own notch, so the pawl that drives the rotor to its left caught it as
+    /// well - the double step of the Enigma's middle rotor.
+    ///
+    DoubleStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RotorStep {
+    pub rotor: usize,
+    pub cause: StepCause,
+}
+
+///
+/// Everything that happened during one key press, for showing the signal path through the machine.
+///
+/// `stages` holds the letter leaving every stage in the order the current passes them. Rotor
+/// indices count from the leftmost rotor.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub input: char,
+    pub steps: Vec<RotorStep>,
+    pub reflector_stepped: bool,
+    pub positions: Vec<char>,
+    pub reflector_position: char,
+    pub stages: Vec<(Stage, char)>,
+    pub output: char,
+}
+
+impl Trace {
+    /// The letter leaving `stage`, if the current passed through it.
+    pub fn letter_after(&self, stage: Stage) -> Option<char> {
+        self.stages
+            .iter()
+            .find(|&&(passed, _)| passed == stage)
+            .map(|&(_, letter)| letter)
+    }
+}
+
+impl RotorStep {
+    /// The same step with rotor indices moved `offset` rotors to the right.
+    pub(crate) fn shifted(self, offset: usize) -> Self {
+        Self {
+            rotor: self.rotor + offset,
+            cause: match self.cause {
+                StepCause::Carry { from } => StepCause::Carry {
+                    from: from + offset,
+                },
+                cause => cause,
+            },
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::alphabet::LATIN;
+use crate::error::Error;
+use crate::reflectors::Reflector;
+use crate::rotor::Rotor;
+use crate::rotors_controller::{RotorRole, RotorsController};
+use crate::stepping::DoubleStep;
+
+const ROTORS: usize = 5;
+const PRINTED_GROUP_SIZE: usize = 5;
+
+///
+/// The British Typex, built from the same rotors, reflectors and stepping as the Enigma.
+///
+/// Five rotors sit between the reflector and the entry. The two leftmost are stators, which are set
+/// by hand and never move. The three to their right are driven by pawls and ratchets like the
+/// Enigma I's, double step included, see [`DoubleStep`]. Rotor indices run from 0 (leftmost) to 4
+/// (rightmost).
+///
+#[derive(Debug, Clone)]
+pub struct Typex {
+    rotor_controller: RotorsController,
+    reflector: Reflector,
+    transpositions: HashMap<char, char>,
+}
+
+impl Typex {
+    pub fn new(rotors: [Rotor; ROTORS], reflector: Reflector) -> Self {
+        let roles = [
+            RotorRole::Stator,
+            RotorRole::Stator,
+            RotorRole::Stepping,
+            RotorRole::Stepping,
+            RotorRole::Stepping,
+        ];
+
+        let mut rotor_controller =
+            RotorsController::from_stack(rotors.into_iter().zip(roles).collect());
+        rotor_controller.set_stepping_strategy(Arc::new(DoubleStep));
+
+        Self {
+            rotor_controller,
+            reflector,
+            transpositions: HashMap::new(),
+        }
+    }
+
+    pub fn encrypt_char(&mut self, letter: char) -> Result<char, Error> {
+        self.rotor_controller.increment();
+
+        self.peak_without_increment(letter)
+    }
+
+    pub fn encrypt_str(&mut self, text: &str) -> Result<String, Error> {
+        text.chars().map(|c| self.encrypt_char(c)).collect()
+    }
+
+    ///
+    /// Encrypts `text` and formats the result the way the printer tapes it, in groups of five
+    /// letters separated by spaces.
+    ///
+    pub fn print_str(&mut self, text: &str) -> Result<String, Error> {
+        let encrypted: Vec<char> = self.encrypt_str(text)?.chars().collect();
+
+        Ok(encrypted
+            .chunks(PRINTED_GROUP_SIZE)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    pub fn peak_without_increment(&self, letter: char) -> Result<char, Error> {
+        let letter = letter.to_ascii_uppercase();
+
+        let enciphered = *self.transpositions.get(&letter).unwrap_or(&letter);
+        let enciphered = self.rotor_controller.map_letter(enciphered)?;
+        let enciphered = self.reflector.map_letter(enciphered)?;
+        let enciphered = self.rotor_controller.inverse_map_letter(enciphered)?;
+
+        Ok(*self.transpositions.get(&enciphered).unwrap_or(&enciphered))
+    }
+
+    /// Sets the positions of all five rotors, leftmost first.
+    pub fn set_positions(&mut self, positions: [char; ROTORS]) -> Result<(), Error> {
+        self.rotor_controller.set_positions(&positions)
+    }
+
+    pub fn get_positions(&self) -> [char; ROTORS] {
+        let positions = self.rotor_controller.get_positions();
+        std::array::from_fn(|index| positions[index])
+    }
+
+    /// Sets the alphabet rings of all five rotors, leftmost first.
+    pub fn set_ring_settings(&mut self, ring_settings: [char; ROTORS]) -> Result<(), Error> {
+        self.rotor_controller.set_ring_settings(&ring_settings)
+    }
+
+    /// Plugs a cable between the two letters. Fails when either already has a cable to another letter.
+    pub fn set_transposition(&mut self, first: char, second: char) -> Result<(), Error> {
+        crate::plug(&mut self.transpositions, &LATIN, first, second).map(|_| ())
+    }
+
+    pub fn clear_transpositions(&mut self) {
+        self.transpositions.clear();
+    }
+}
+
+///
+/// Returns `rotor` with its wired core inserted backwards into its ring.
+///
+/// Typex rotors carry their notches on the ring, so a reversed rotor keeps stepping at the same
+/// positions while enciphering through the mirrored, inverted wiring. Use [`Rotor::reversed`] for
+/// machines where the whole rotor, notches included, is turned around.
+///
+pub fn insert_reversed(mut rotor: Rotor) -> Rotor {
+    rotor.set_core_reversed(!rotor.is_core_reversed());
+    rotor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permutation::Permutation;
+    use crate::{reflectors, rotors};
+
+    const PLAIN: &str = "ATTACKATDAWNONTHEEASTERNRIDGE";
+
+    fn machine() -> Typex {
+        let mut typex = Typex::new(
+            [
+                rotors::create_rotor_typex_example_3(),
+                insert_reversed(rotors::create_rotor_typex_example_5()),
+                rotors::create_rotor_typex_example_1(),
+                insert_reversed(rotors::create_rotor_typex_example_7()),
+                rotors::create_rotor_typex_example_2(),
+            ],
+            reflectors::create_reflector_typex_example(),
+        );
+        typex.set_positions(['M', 'K', 'Q', 'B', 'X']).unwrap();
+        typex.set_transposition('A', 'Z').unwrap();
+        typex
+    }
+
+    #[test]
+    fn typex_should_decipher_its_own_ciphertext() {
+        let ciphertext = machine().encrypt_str(PLAIN).unwrap();
+
+        assert_ne!(ciphertext, PLAIN);
+        assert_eq!(machine().encrypt_str(&ciphertext).unwrap(), PLAIN);
+    }
+
+    #[test]
+    fn typex_should_match_the_example_wiring_tables() {
+        let at = |wiring: &str, position: char| {
+            let offset = position as usize - 'A' as usize;
+            let shift: String = ('A'..='Z').cycle().skip(offset).take(26).collect();
+            let shift = Permutation::new(&shift).unwrap();
+            shift
+                .compose(&Permutation::new(wiring).unwrap())
+                .compose(&shift.inverse())
+        };
+        let stack = [
+            at("BYPDZMGIKQCUSATREHOJNLFWXV", 'M'),
+            at("QXBGUTOVFCZPJIHSWERYNDAMLK", 'K'),
+            at("MCYLPQUVRXGSAOWNBJEZDTFKHI", 'A'),
+            at("PNUBDKSFCJRAOVEWTGMLQHYXZI", 'C'),
+            at("KHWENRCBISXJQGOFMAPVYZDLTU", 'D'),
+        ];
+        let reflector = Permutation::new("NCBKIGFMEXDUHARYWOTSLZQJPV").unwrap();
+        let inverses = stack.map(|rotor| rotor.inverse());
+        let expected = stack
+            .iter()
+            .rev()
+            .chain([&reflector])
+            .chain(&inverses)
+            .fold(Permutation::identity(), |product, part| {
+                product.compose(part)
+            });
+
+        let mut typex = Typex::new(
+            [
+                rotors::create_rotor_typex_example_3(),
+                rotors::create_rotor_typex_example_5(),
+                rotors::create_rotor_typex_example_1(),
+                rotors::create_rotor_typex_example_7(),
+                rotors::create_rotor_typex_example_2(),
+            ],
+            reflectors::create_reflector_typex_example(),
+        );
+        typex.set_positions(['M', 'K', 'A', 'C', 'C']).unwrap();
+
+        let cipher = typex.encrypt_char('T').unwrap();
+
+        assert_eq!(typex.get_positions(), ['M', 'K', 'A', 'C', 'D']);
+        assert_eq!(expected.apply('T'), Ok(cipher));
+        for letter in 'A'..='Z' {
+            assert_eq!(typex.peak_without_increment(letter), expected.apply(letter));
+        }
+    }
+
+    #[test]
+    fn middle_stepping_rotor_should_double_step() {
+        let mut typex = machine();
+        typex.set_positions(['M', 'K', 'A', 'D', 'E']).unwrap();
+
+        // The example rotors turn over at A, E, G, M, P, T and V.
+        let positions: Vec<[char; ROTORS]> = (0..3)
+            .map(|_| {
+                typex.encrypt_char('A').unwrap();
+                typex.get_positions()
+            })
+            .collect();
+
+        assert_eq!(
+            positions,
+            [
+                ['M', 'K', 'A', 'E', 'F'],
+                ['M', 'K', 'B', 'F', 'G'],
+                ['M', 'K', 'B', 'G', 'H'],
+            ]
+        );
+    }
+
+    #[test]
+    fn stators_should_never_move() {
+        let mut typex = machine();
+        typex.encrypt_str(&PLAIN.repeat(40)).unwrap();
+
+        let positions = typex.get_positions();
+        assert_eq!(positions[..2], ['M', 'K']);
+        assert_ne!(positions[2..], ['Q', 'B', 'X']);
+    }
+
+    #[test]
+    fn reversing_a_rotor_should_change_the_ciphertext_but_not_the_stepping() {
+        let mut forward = machine();
+        let mut reversed = machine();
+        reversed
+            .rotor_controller
+            .set_rotor(0, insert_reversed(rotors::create_rotor_typex_example_3()))
+            .unwrap();
+        reversed
+            .rotor_controller
+            .set_rotor_position_from_char(0, 'M')
+            .unwrap();
+
+        assert_ne!(
+            forward.encrypt_str(PLAIN).unwrap(),
+            reversed.encrypt_str(PLAIN).unwrap()
+        );
+        assert_eq!(forward.get_positions(), reversed.get_positions());
+    }
+
+    #[test]
+    fn printer_should_group_letters_in_fives() {
+        let printed = machine().print_str(PLAIN).unwrap();
+        let ciphertext = machine().encrypt_str(PLAIN).unwrap();
+
+        assert!(
+            printed
+                .split(' ')
+                .all(|group| group.len() <= PRINTED_GROUP_SIZE)
+        );
+        assert_eq!(
+            printed.split(' ').count(),
+            PLAIN.len().div_ceil(PRINTED_GROUP_SIZE)
+        );
+        assert_eq!(printed.replace(' ', ""), ciphertext);
+    }
+}